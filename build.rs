@@ -22,6 +22,16 @@ fn main() -> miette::Result<()>
         .add_entry_point("src/fractal/henon.wgsl")
         .add_entry_point("src/fractal/supergolden_julia.wgsl")
         .add_entry_point("src/fractal/supergolden_mandelbrot.wgsl")
+        .add_entry_point("src/fractal/custom_formula.wgsl")
+        .add_entry_point("src/fractal/mandelbrot_perturbation.wgsl")
+        .add_entry_point("src/fractal/julia_perturbation.wgsl")
+        .add_entry_point("src/fractal/supergolden_mandelbrot_perturbation.wgsl")
+        .add_entry_point("src/fractal/supergolden_julia_perturbation.wgsl")
+        .add_entry_point("src/fractal/feigenbaum_df.wgsl")
+        .add_entry_point("src/fractal/fibonacci_hamiltonian_julia_df.wgsl")
+        .add_entry_point("src/fractal/fibonacci_hamiltonian_mandelbrot_df.wgsl")
+        .add_entry_point("src/fractal/heighway_dragon_df.wgsl")
+        .add_entry_point("src/fractal/rauzy_df.wgsl")
         .serialization_strategy(WgslTypeSerializeStrategy::Bytemuck)
         .emit_rerun_if_change(true)
         //.shader_source_type(WgslShaderSourceType::ComposerWithRelativePath)
@@ -0,0 +1,91 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{app::{MoveDirection, RotateDirection, ZoomDirection}, fractal::Target};
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Action
+{
+    MoveCenter(MoveDirection),
+    MoveShift(MoveDirection),
+    MoveExp(MoveDirection),
+    /// Moves the orbit trap, see [`Trap`](crate::fractal::Trap).
+    MoveTrap(MoveDirection),
+    Zoom(ZoomDirection),
+
+    Rotate(RotateDirection),
+    RotateCenter(RotateDirection),
+    RotateShift(RotateDirection),
+    RotateExp(RotateDirection),
+    RotateTrap(RotateDirection),
+
+    AccelCenter(Option<MoveDirection>),
+    AccelShift(Option<MoveDirection>),
+    AccelExp(Option<MoveDirection>),
+    AccelTrap(Option<MoveDirection>),
+    AccelZoom(Option<ZoomDirection>),
+    AccelRotate(Option<RotateDirection>),
+
+    /// Moves the zoom target to the cursor and zooms in that direction.
+    ZoomAtCursor(ZoomDirection),
+    StopZoom,
+
+    /// Saves the view to a numbered bookmark slot of the current fractal.
+    SaveBookmark(u8),
+    GotoBookmark(u8),
+
+    Reverse,
+    Stop,
+    Idle,
+    Exit,
+    Fullscreen,
+    Reset,
+    ResetTime,
+    /// Pauses or resumes the time of the view, and with it all movement.
+    Pause,
+    /// Halves how fast time runs.
+    SlowDown,
+    /// Doubles how fast time runs.
+    SpeedUp,
+    ResetView,
+    /// Colors by the next of the colormaps and palettes in [`PaletteSettings`](crate::fractal::PaletteSettings).
+    NextColormap,
+    /// Colors the boundary of escape time fractals by the next [`Distance`](crate::fractal::Distance) mode, for the ones
+    /// with a [`distance_estimate`](crate::fractal::Fractal::distance_estimate).
+    NextDistance,
+    /// Colors escape time fractals by the next shape of [`Trap`](crate::fractal::Trap).
+    NextTrap,
+    ReloadSettings,
+    /// Turns the [`Autopilot`](crate::app::Autopilot) on or off.
+    Autopilot,
+    /// Snaps the center to the nearest nucleus or Misiurewicz point under the cursor and prints what was found.
+    Locate(Target),
+    /// Shows or hides an inset with the [`dual`](crate::fractal::Fractal::dual) of the fractal for the point under the
+    /// cursor.
+    Dual,
+    /// Switches to the dual of the fractal for the point under the cursor.
+    OpenDual,
+    /// Prints the [`Location`](crate::app::Location) of the view to stdout.
+    PrintLocation,
+    NextFractal
+}
+
+impl Action
+{
+    /// Whether the action only happens when its input is pressed, as opposed to lasting for as long as it's held.
+    pub fn is_trigger(&self) -> bool
+    {
+        !matches!(
+            self,
+            Self::MoveCenter(_)
+            | Self::MoveShift(_)
+            | Self::MoveExp(_)
+            | Self::MoveTrap(_)
+            | Self::Zoom(_)
+            | Self::Rotate(_)
+            | Self::RotateCenter(_)
+            | Self::RotateShift(_)
+            | Self::RotateExp(_)
+            | Self::RotateTrap(_)
+        )
+    }
+}
@@ -0,0 +1,142 @@
+use std::collections::BTreeMap;
+
+use num_complex::Complex;
+use serde::{Deserialize, Serialize};
+
+use crate::settings::{config_path, read_config};
+
+const BOOKMARKS_FILE: &str = "bookmarks.toml";
+
+/// Saved views, by fractal as it's given on the command line and then by name, so each set of parameters has its own, kept in `bookmarks.toml` in the config directory.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Bookmarks
+{
+    fractals: BTreeMap<String, BTreeMap<String, Bookmark>>
+}
+
+/// Everything needed to get back to a view. Shift and exp are the actual values, like in [`InitView`](crate::app::InitView).
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Bookmark
+{
+    pub center: Complex<f64>,
+    pub shift: Complex<f64>,
+    pub exp: Complex<f64>,
+    pub zoom: f64,
+    pub rot: f64,
+    pub win_center: Complex<f64>,
+    /// Left out to arrive at a standstill.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub velocity: Option<Velocity>
+}
+
+/// The raw velocities of the view controls, so shift and exp move in arctangent space and zoom is a factor.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Velocity
+{
+    pub center: Complex<f64>,
+    pub shift: Complex<f64>,
+    pub exp: Complex<f64>,
+    pub zoom: f64,
+    pub rot: f64
+}
+
+impl Bookmarks
+{
+    /// Reads the bookmarks file, or starts out empty if there is none.
+    pub fn load() -> anyhow::Result<Self>
+    {
+        let Some((path, bookmarks)) = read_config::<Self>(BOOKMARKS_FILE)?
+        else
+        {
+            return Ok(Self::default())
+        };
+        bookmarks.validate()
+            .map_err(|error| error.context(format!("Invalid bookmarks in {}", path.display())))?;
+        Ok(bookmarks)
+    }
+
+    pub fn save(&self) -> anyhow::Result<()>
+    {
+        let path = config_path(BOOKMARKS_FILE)
+            .ok_or(anyhow::Error::msg("No config directory to save bookmarks in"))?;
+        if let Some(dir) = path.parent()
+        {
+            std::fs::create_dir_all(dir)
+                .map_err(|error| anyhow::Error::new(error).context(format!("Failed to create {}", dir.display())))?;
+        }
+        std::fs::write(&path, toml::to_string(self)?)
+            .map_err(|error| anyhow::Error::new(error).context(format!("Failed to write {}", path.display())))
+    }
+
+    pub fn validate(&self) -> anyhow::Result<()>
+    {
+        for (fractal, name, bookmark) in self.iter()
+        {
+            bookmark.validate()
+                .map_err(|error| error.context(format!("In bookmark {name} for {fractal}")))?;
+        }
+        Ok(())
+    }
+
+    pub fn get(&self, fractal: &str, name: &str) -> Option<&Bookmark>
+    {
+        self.fractals.get(fractal)?.get(name)
+    }
+
+    pub fn insert(&mut self, fractal: &str, name: &str, bookmark: Bookmark)
+    {
+        self.fractals.entry(fractal.to_string())
+            .or_default()
+            .insert(name.to_string(), bookmark);
+    }
+
+    /// Saves `bookmark` in a numbered slot of `fractal`, its [`arg`](crate::fractal::Fractal::arg), as bound to keys,
+    /// keeping the rest of the file.
+    pub fn save_slot(fractal: &str, slot: u8, bookmark: Bookmark) -> anyhow::Result<()>
+    {
+        let mut bookmarks = Self::load()?;
+        bookmarks.insert(fractal, &slot.to_string(), bookmark);
+        bookmarks.save()
+    }
+
+    /// The bookmark in a numbered slot of `fractal`, its [`arg`](crate::fractal::Fractal::arg), as bound to keys.
+    pub fn load_slot(fractal: &str, slot: u8) -> anyhow::Result<Bookmark>
+    {
+        Self::load()?.get(fractal, &slot.to_string())
+            .copied()
+            .ok_or(anyhow::Error::msg(format!("No bookmark {slot} for {fractal}")))
+    }
+
+    /// Every bookmark as `(fractal, name, bookmark)`, sorted by fractal and name.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str, &Bookmark)>
+    {
+        self.fractals.iter()
+            .flat_map(|(fractal, bookmarks)| bookmarks.iter()
+                .map(move |(name, bookmark)| (fractal.as_str(), name.as_str(), bookmark))
+            )
+    }
+}
+
+impl Bookmark
+{
+    pub fn validate(&self) -> anyhow::Result<()>
+    {
+        let Self { center, shift, exp, zoom, rot, win_center, velocity } = self;
+        let complex = [center, shift, exp, win_center].into_iter()
+            .chain(velocity.iter().flat_map(|velocity| [&velocity.center, &velocity.shift, &velocity.exp]));
+        let real = [rot].into_iter()
+            .chain(velocity.iter().flat_map(|velocity| [&velocity.zoom, &velocity.rot]));
+        if !complex.flat_map(|z| [&z.re, &z.im]).chain(real).all(|x| x.is_finite())
+        {
+            return Err(anyhow::Error::msg("All values must be finite"))
+        }
+        if !zoom.is_finite() || *zoom == 0.0
+        {
+            return Err(anyhow::Error::msg(format!("zoom must be finite and non-zero, was {zoom}")))
+        }
+        Ok(())
+    }
+}
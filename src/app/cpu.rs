@@ -0,0 +1,50 @@
+use num_complex::Complex;
+use num_traits::Float;
+use winit::dpi::PhysicalSize;
+
+use crate::{MyFloat, app::{Frame, View}, f, fractal::{Fractal, cpu::clamp}};
+
+/// Renders a frame by evaluating [`Fractal::fs_main`] for every pixel on the CPU, blended over black like the GPU pipeline.
+pub fn render_cpu<F, Z>(fractal: &Z, view: &View<F>) -> Frame
+where
+    F: MyFloat,
+    Z: Fractal<F>
+{
+    let globals = view.globals();
+    let size = globals.window_size;
+    let PhysicalSize { width, height } = size;
+
+    let mut data = Vec::with_capacity(width as usize*height as usize*4);
+    for y in 0..height
+    {
+        for x in 0..width
+        {
+            // Fragment positions are at pixel centers
+            let pos = Complex::new(
+                f!(x as f64 + 0.5 - width as f64/2.0),
+                f!(y as f64 + 0.5 - height as f64/2.0)
+            );
+            let [r, g, b, a] = fractal.fs_main(&globals, pos);
+            data.extend([r, g, b].map(|c| encode_srgb(c*a)));
+            data.push(u8::MAX);
+        }
+    }
+
+    Frame { size, data }
+}
+
+fn encode_srgb<F>(linear: F) -> u8
+where
+    F: MyFloat
+{
+    let c = clamp(linear, F::zero(), F::one());
+    let srgb = if c <= f!(0.0031308)
+    {
+        c*f!(12.92)
+    }
+    else
+    {
+        f!(1.055)*Float::powf(c, f!(1.0/2.4)) - f!(0.055)
+    };
+    Float::round(srgb*f!(255.0)).to_u8().unwrap()
+}
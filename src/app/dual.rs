@@ -0,0 +1,133 @@
+use std::sync::Arc;
+
+use winit::dpi::PhysicalSize;
+
+use crate::{DUAL_INSET, MyFloat, app::{FractalPass, View}, f, fractal::{Fractal, cpu::Globals}};
+
+/// An inset in the bottom right corner of the window with the [`dual`](Fractal::dual) of the fractal for the point
+/// under the cursor, e.g. the Julia set of that point of the Mandelbrot set.
+///
+/// It's drawn to a texture of its own and copied onto the window, so it looks just like the dual would full size.
+#[derive(Debug)]
+pub struct Dual
+{
+    pass: FractalPass,
+    texture: wgpu::Texture,
+    /// Whether there was a dual under the cursor last update.
+    visible: bool
+}
+
+impl Dual
+{
+    /// Sets up the pipelines of the dual, or gives `None` for fractals that don't have one.
+    pub fn new<F, Z>(device: &wgpu::Device, fractal: &Z, view: &View<F>, format: wgpu::TextureFormat) -> anyhow::Result<Option<Self>>
+    where
+        F: MyFloat,
+        Z: Fractal<F>
+    {
+        // Every dual of a fractal shares a pipeline, the point is only passed as the shift
+        let globals = view.globals();
+        let Some(dual) = fractal.dual(&globals, -globals.center)
+        else
+        {
+            return Ok(None)
+        };
+        let size = Self::size(view.win_size());
+        Ok(Some(Self {
+            pass: FractalPass::new(device, &dual, Self::globals(&dual, view).into(), format, &view.settings().shader)?,
+            texture: Self::create_texture(device, size, format),
+            visible: false
+        }))
+    }
+
+    /// The dual for the point under the cursor.
+    pub fn at_cursor<F, Z>(fractal: &Z, view: &View<F>) -> Option<Arc<dyn Fractal<F>>>
+    where
+        F: MyFloat,
+        Z: Fractal<F>
+    {
+        fractal.dual(&view.globals(), view.cursor()?)
+    }
+
+    pub fn update<F, Z>(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, fractal: &Z, view: &View<F>)
+    where
+        F: MyFloat,
+        Z: Fractal<F>
+    {
+        let size = Self::size(view.win_size());
+        if self.texture.width() != size.width || self.texture.height() != size.height
+        {
+            self.texture = Self::create_texture(device, size, self.texture.format());
+        }
+
+        let dual = Self::at_cursor(fractal, view);
+        self.visible = dual.is_some();
+        if let Some(dual) = dual
+        {
+            self.pass.update(device, queue, &dual, Self::globals(&dual, view));
+        }
+    }
+
+    /// Draws the inset over the corner of `output`, which has to allow copying to it.
+    pub fn draw(&self, encoder: &mut wgpu::CommandEncoder, output: &wgpu::Texture)
+    {
+        if !self.visible
+        {
+            return
+        }
+        self.pass.draw(encoder, &self.texture.create_view(&wgpu::TextureViewDescriptor::default()));
+        let size = self.texture.size();
+        encoder.copy_texture_to_texture(
+            self.texture.as_image_copy(),
+            wgpu::TexelCopyTextureInfo {
+                texture: output,
+                mip_level: 0,
+                origin: wgpu::Origin3d {
+                    x: output.width().saturating_sub(size.width),
+                    y: output.height().saturating_sub(size.height),
+                    z: 0
+                },
+                aspect: wgpu::TextureAspect::All
+            },
+            size
+        );
+    }
+
+    /// How the dual starts out, zoomed out to show as much in the inset as it would in the window.
+    fn globals<F, T>(dual: &T, view: &View<F>) -> Globals<F>
+    where
+        F: MyFloat,
+        T: Fractal<F>
+    {
+        let mut globals = View::new(dual, Self::size(view.win_size()), view.start().dual(), view.settings().clone()).globals();
+        globals.zoom *= f!(DUAL_INSET);
+        globals.time = view.globals().time;
+        globals
+    }
+
+    fn size(win_size: PhysicalSize<u32>) -> PhysicalSize<u32>
+    {
+        PhysicalSize::new(
+            ((win_size.width as f64*DUAL_INSET) as u32).max(1),
+            ((win_size.height as f64*DUAL_INSET) as u32).max(1)
+        )
+    }
+
+    fn create_texture(device: &wgpu::Device, size: PhysicalSize<u32>, format: wgpu::TextureFormat) -> wgpu::Texture
+    {
+        device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Dual texture"),
+            size: wgpu::Extent3d {
+                width: size.width,
+                height: size.height,
+                depth_or_array_layers: 1
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[]
+        })
+    }
+}
@@ -0,0 +1,336 @@
+use wgpu::util::DeviceExt;
+use winit::dpi::PhysicalSize;
+
+use crate::{DF_ZOOM, app::{ESCAPE_FORMAT, HistogramPass}, MyFloat, PERTURBATION_ZOOM, f, fractal::{Fractal, GlobalUniforms, ShaderDefs, GlobalUniformsDf, Reference, ReferenceOrbit, VertexInput, WgpuBindGroup0, WgpuBindGroup0Entries, WgpuBindGroup0EntriesParams, cpu::Globals}};
+
+/// Orbit points the orbit buffer starts out with room for.
+const ORBIT_CAPACITY: u64 = 1024;
+
+/// Everything needed to draw a fractal onto a texture, independent of where that texture comes from.
+#[derive(Debug)]
+pub struct FractalPass
+{
+    global_uniforms_buffer: wgpu::Buffer,
+    global_bind_group: WgpuBindGroup0,
+    vertex_buffer: wgpu::Buffer,
+    render_pipeline: wgpu::RenderPipeline,
+    perturbation: Option<PerturbationPass>,
+    df: Option<DfPass>,
+    /// Colors the escape counts the pipelines draw with histogram coloring on.
+    histogram: Option<HistogramPass>,
+    precision: Precision
+}
+
+/// Which pipeline draws the next frame.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Precision
+{
+    Single,
+    DoubleFloat,
+    Perturbation
+}
+
+/// The deep zoom pipeline, which iterates each pixel relative to a full precision [`ReferenceOrbit`].
+#[derive(Debug)]
+struct PerturbationPass
+{
+    reference_buffer: wgpu::Buffer,
+    orbit_buffer: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+    render_pipeline: wgpu::RenderPipeline
+}
+
+/// The extended precision pipeline, which iterates in double-floats using [`GlobalUniformsDf`].
+#[derive(Debug)]
+struct DfPass
+{
+    uniforms_buffer: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+    render_pipeline: wgpu::RenderPipeline
+}
+
+impl FractalPass
+{
+    /// Sets up the pipelines with the shaders composed with `defs`, failing if they don't compose.
+    pub fn new<F, Z>(
+        device: &wgpu::Device,
+        fractal: &Z,
+        global_uniforms: GlobalUniforms,
+        format: wgpu::TextureFormat,
+        defs: &ShaderDefs
+    ) -> anyhow::Result<Self>
+    where
+        F: MyFloat,
+        Z: Fractal<F>
+    {
+        let global_uniforms_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Global uniforms buffer"),
+            contents: bytemuck::cast_slice(&[global_uniforms]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        // Create bind group using generated types - fully type-safe!
+        let global_bind_group = WgpuBindGroup0::from_bindings(
+            device,
+            WgpuBindGroup0Entries::new(WgpuBindGroup0EntriesParams {
+                globals: wgpu::BufferBinding {
+                    buffer: &global_uniforms_buffer,
+                    offset: 0,
+                    size: None,
+                },
+            })
+        );
+
+        let size = PhysicalSize::new(global_uniforms.window_size.x, global_uniforms.window_size.y);
+        let histogram = defs.histogram
+            .then(|| HistogramPass::new(device, size, format, defs))
+            .transpose()?;
+        let format = if histogram.is_some() { ESCAPE_FORMAT } else { format };
+
+        let render_pipeline = fractal.setup_render_pipeline(device, format, defs)?;
+        let perturbation = fractal.setup_perturbation_pipeline(device, format, defs)?
+            .map(|render_pipeline| PerturbationPass::new(device, render_pipeline));
+        let df = fractal.setup_df_pipeline(device, format, defs)?
+            .map(|render_pipeline| DfPass::new(device, render_pipeline));
+        let vertices = core::array::from_fn::<_, 6, _>(|i| VertexInput { vertex_id: i as u32 });
+
+        println!("Creating vertex buffer.");
+        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Fractal Vertex Buffer"),
+            contents: bytemuck::cast_slice(&vertices),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+
+        Ok(Self {
+            global_uniforms_buffer,
+            global_bind_group,
+            vertex_buffer,
+            render_pipeline,
+            perturbation,
+            df,
+            histogram,
+            precision: Precision::Single
+        })
+    }
+
+    /// Like [`FractalPass::new`], but falls back on the shaders as they were built if the ones composed with `defs`
+    /// don't work, for when there aren't any working ones to keep yet. Why it fell back goes to `report`.
+    pub fn new_or_built_in<F, Z, R>(
+        device: &wgpu::Device,
+        fractal: &Z,
+        global_uniforms: GlobalUniforms,
+        format: wgpu::TextureFormat,
+        defs: &ShaderDefs,
+        report: R
+    ) -> anyhow::Result<Self>
+    where
+        F: MyFloat,
+        Z: Fractal<F>,
+        R: FnOnce(String)
+    {
+        Self::new(device, fractal, global_uniforms, format, defs).or_else(|error| {
+            if defs.is_built_in()
+            {
+                return Err(error)
+            }
+            report(format!("Falling back on the shaders as they were built: {error:#}"));
+            Self::new(device, fractal, global_uniforms, format, &ShaderDefs::default())
+        })
+    }
+
+    /// Uploads the view, and switches to an extended precision pipeline once the zoom is too deep for `f32`, preferring
+    /// perturbation over double-floats.
+    pub fn update<F, Z>(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, fractal: &Z, globals: Globals<F>)
+    where
+        F: MyFloat,
+        Z: Fractal<F>
+    {
+        self.write_uniforms(queue, globals.into());
+        if let Some(histogram) = &mut self.histogram
+        {
+            histogram.resize(device, globals.window_size);
+        }
+
+        self.precision = if let Some(perturbation) = &mut self.perturbation
+            && globals.zoom > f!(PERTURBATION_ZOOM)
+            && let Some(reference) = ReferenceOrbit::find(fractal, &globals)
+        {
+            perturbation.write(device, queue, &reference, &globals);
+            Precision::Perturbation
+        }
+        else if let Some(df) = &self.df
+            && globals.zoom > f!(DF_ZOOM)
+        {
+            queue.write_buffer(&df.uniforms_buffer, 0, bytemuck::cast_slice(&[GlobalUniformsDf::from(globals)]));
+            Precision::DoubleFloat
+        }
+        else
+        {
+            Precision::Single
+        };
+    }
+
+    pub fn write_uniforms(&self, queue: &wgpu::Queue, global_uniforms: GlobalUniforms)
+    {
+        queue.write_buffer(
+            &self.global_uniforms_buffer,
+            0,
+            bytemuck::cast_slice(&[global_uniforms]),
+        );
+    }
+
+    pub fn draw(&self, encoder: &mut wgpu::CommandEncoder, output_view: &wgpu::TextureView)
+    {
+        match &self.histogram
+        {
+            Some(histogram) => {
+                self.draw_fractal(encoder, &histogram.escape_view());
+                histogram.draw(encoder, &self.global_bind_group, output_view)
+            },
+            None => self.draw_fractal(encoder, output_view)
+        }
+    }
+
+    fn draw_fractal(&self, encoder: &mut wgpu::CommandEncoder, output_view: &wgpu::TextureView)
+    {
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Render Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: output_view,
+                depth_slice: None,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            multiview_mask: None,
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+
+        self.global_bind_group.set(&mut render_pass);
+
+        match (self.precision, &self.perturbation, &self.df)
+        {
+            (Precision::Perturbation, Some(perturbation), _) => {
+                render_pass.set_bind_group(1, &perturbation.bind_group, &[]);
+                render_pass.set_pipeline(&perturbation.render_pipeline);
+            },
+            (Precision::DoubleFloat, _, Some(df)) => {
+                render_pass.set_bind_group(1, &df.bind_group, &[]);
+                render_pass.set_pipeline(&df.render_pipeline);
+            },
+            _ => render_pass.set_pipeline(&self.render_pipeline)
+        }
+        render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+        render_pass.draw(0..self.vertex_count(), 0..1);
+    }
+
+    fn vertex_count(&self) -> u32
+    {
+        (self.vertex_buffer.size()/core::mem::size_of::<VertexInput>() as u64) as u32
+    }
+}
+
+impl PerturbationPass
+{
+    fn new(device: &wgpu::Device, render_pipeline: wgpu::RenderPipeline) -> Self
+    {
+        let reference_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Reference buffer"),
+            size: core::mem::size_of::<Reference>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false
+        });
+        let orbit_buffer = Self::create_orbit_buffer(device, ORBIT_CAPACITY);
+        let bind_group = Self::create_bind_group(device, &render_pipeline, &reference_buffer, &orbit_buffer);
+
+        Self {
+            reference_buffer,
+            orbit_buffer,
+            bind_group,
+            render_pipeline
+        }
+    }
+
+    fn write<F>(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, reference: &ReferenceOrbit<F>, globals: &Globals<F>)
+    where
+        F: MyFloat
+    {
+        let orbit = reference.orbit_data();
+        let size = core::mem::size_of_val(orbit.as_slice()) as u64;
+        if size > self.orbit_buffer.size()
+        {
+            self.orbit_buffer = Self::create_orbit_buffer(device, (orbit.len() as u64).next_power_of_two());
+            self.bind_group = Self::create_bind_group(device, &self.render_pipeline, &self.reference_buffer, &self.orbit_buffer);
+        }
+
+        queue.write_buffer(&self.reference_buffer, 0, bytemuck::cast_slice(&[reference.reference(globals)]));
+        queue.write_buffer(&self.orbit_buffer, 0, bytemuck::cast_slice(&orbit));
+    }
+
+    fn create_orbit_buffer(device: &wgpu::Device, capacity: u64) -> wgpu::Buffer
+    {
+        device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Reference orbit buffer"),
+            size: capacity*core::mem::size_of::<[f32; 4]>() as u64,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false
+        })
+    }
+
+    // Every shader module gets its own generated bind group type, so this goes by the pipeline's layout instead
+    fn create_bind_group(
+        device: &wgpu::Device,
+        render_pipeline: &wgpu::RenderPipeline,
+        reference_buffer: &wgpu::Buffer,
+        orbit_buffer: &wgpu::Buffer
+    ) -> wgpu::BindGroup
+    {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Perturbation bind group"),
+            layout: &render_pipeline.get_bind_group_layout(1),
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: reference_buffer.as_entire_binding()
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: orbit_buffer.as_entire_binding()
+                }
+            ]
+        })
+    }
+}
+
+impl DfPass
+{
+    fn new(device: &wgpu::Device, render_pipeline: wgpu::RenderPipeline) -> Self
+    {
+        let uniforms_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Double-float uniforms buffer"),
+            size: core::mem::size_of::<GlobalUniformsDf>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Double-float bind group"),
+            layout: &render_pipeline.get_bind_group_layout(1),
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: uniforms_buffer.as_entire_binding()
+                }
+            ]
+        });
+
+        Self {
+            uniforms_buffer,
+            bind_group,
+            render_pipeline
+        }
+    }
+}
@@ -0,0 +1,287 @@
+use winit::dpi::PhysicalSize;
+
+use crate::fractal::{ShaderDefs, WgpuBindGroup0, color_targets};
+
+/// What the fractal pipelines draw the smooth escape count of each pixel to, for histogram coloring, as the top 24 bits
+/// of the float in `rgb`. OpenGL can't draw to float textures without an extension.
+pub const ESCAPE_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8Unorm;
+/// Bins of the histogram, as in `histogram.wgsl` and `equalize.wgsl`.
+const BINS: u64 = 1024;
+/// Side of the workgroups of the compute passes that go over every pixel.
+const WORKGROUP_SIZE: u32 = 8;
+
+/// Histogram coloring: with the fractal drawn as escape counts to [`ESCAPE_FORMAT`], builds a histogram of them and
+/// colors each pixel by its rank in the frame, so the palette keeps its contrast at any zoom.
+#[derive(Debug)]
+pub struct HistogramPass
+{
+    escape_texture: wgpu::Texture,
+    histogram_buffer: wgpu::Buffer,
+    cdf_buffer: wgpu::Buffer,
+    compute_layout: wgpu::BindGroupLayout,
+    compute_bind_group: wgpu::BindGroup,
+    /// `clear`, `bounds`, `count` and `accumulate` in `histogram.wgsl`, in the order they run.
+    compute_pipelines: [wgpu::ComputePipeline; 4],
+    equalize_layout: wgpu::BindGroupLayout,
+    equalize_bind_group: wgpu::BindGroup,
+    equalize_pipeline: wgpu::RenderPipeline
+}
+
+impl HistogramPass
+{
+    pub fn new(device: &wgpu::Device, size: PhysicalSize<u32>, format: wgpu::TextureFormat, defs: &ShaderDefs) -> anyhow::Result<Self>
+    {
+        let histogram_shader = defs.compose(device, "histogram.wgsl", include_str!("../fractal/histogram.wgsl"))?;
+        let equalize_shader = defs.compose(device, "equalize.wgsl", include_str!("../fractal/equalize.wgsl"))?;
+
+        let escape = |visibility| wgpu::BindGroupLayoutEntry {
+            binding: 0,
+            visibility,
+            ty: wgpu::BindingType::Texture {
+                sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                view_dimension: wgpu::TextureViewDimension::D2,
+                multisampled: false
+            },
+            count: None
+        };
+        let storage = |binding, visibility, read_only| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Storage { read_only },
+                has_dynamic_offset: false,
+                min_binding_size: None
+            },
+            count: None
+        };
+        let compute_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Histogram bind group layout"),
+            entries: &[
+                escape(wgpu::ShaderStages::COMPUTE),
+                storage(1, wgpu::ShaderStages::COMPUTE, false),
+                storage(2, wgpu::ShaderStages::COMPUTE, false)
+            ]
+        });
+        let equalize_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Equalize bind group layout"),
+            entries: &[
+                escape(wgpu::ShaderStages::FRAGMENT),
+                storage(1, wgpu::ShaderStages::FRAGMENT, true)
+            ]
+        });
+
+        let compute_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Histogram pipeline layout"),
+            bind_group_layouts: &[&compute_layout],
+            immediate_size: 0
+        });
+        let compute_pipelines = ["clear", "bounds", "count", "accumulate"].map(|entry_point| {
+            device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some(entry_point),
+                layout: Some(&compute_pipeline_layout),
+                module: &histogram_shader,
+                entry_point: Some(entry_point),
+                compilation_options: Default::default(),
+                cache: None
+            })
+        });
+
+        let equalize_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Equalize pipeline layout"),
+            bind_group_layouts: &[&WgpuBindGroup0::get_bind_group_layout(device), &equalize_layout],
+            immediate_size: 0
+        });
+        let equalize_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Equalize pipeline"),
+            layout: Some(&equalize_pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &equalize_shader,
+                entry_point: Some("vs_main"),
+                compilation_options: Default::default(),
+                buffers: &[]
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &equalize_shader,
+                entry_point: Some("fs_main"),
+                compilation_options: Default::default(),
+                targets: &color_targets(format)
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview_mask: None,
+            cache: None
+        });
+
+        // The atomic bounds and total, then a count per bin
+        let histogram_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Histogram buffer"),
+            size: (3 + BINS)*4,
+            usage: wgpu::BufferUsages::STORAGE,
+            mapped_at_creation: false
+        });
+        // The bounds, then a value per bin
+        let cdf_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("CDF buffer"),
+            size: (2 + BINS)*4,
+            usage: wgpu::BufferUsages::STORAGE,
+            mapped_at_creation: false
+        });
+        let escape_texture = Self::create_escape_texture(device, size);
+        let (compute_bind_group, equalize_bind_group) = Self::create_bind_groups(
+            device,
+            &compute_layout,
+            &equalize_layout,
+            &escape_texture,
+            &histogram_buffer,
+            &cdf_buffer
+        );
+
+        Ok(Self {
+            escape_texture,
+            histogram_buffer,
+            cdf_buffer,
+            compute_layout,
+            compute_bind_group,
+            compute_pipelines,
+            equalize_layout,
+            equalize_bind_group,
+            equalize_pipeline
+        })
+    }
+
+    /// Makes room for escape counts of a window of `size`.
+    pub fn resize(&mut self, device: &wgpu::Device, size: PhysicalSize<u32>)
+    {
+        let size = Self::texture_size(size);
+        if self.escape_texture.width() == size.width && self.escape_texture.height() == size.height
+        {
+            return
+        }
+        self.escape_texture = Self::create_escape_texture(device, size);
+        (self.compute_bind_group, self.equalize_bind_group) = Self::create_bind_groups(
+            device,
+            &self.compute_layout,
+            &self.equalize_layout,
+            &self.escape_texture,
+            &self.histogram_buffer,
+            &self.cdf_buffer
+        );
+    }
+
+    /// Where the fractal pipelines draw escape counts to.
+    pub fn escape_view(&self) -> wgpu::TextureView
+    {
+        self.escape_texture.create_view(&wgpu::TextureViewDescriptor::default())
+    }
+
+    /// Colors the escape counts drawn to [`HistogramPass::escape_view`] onto `output_view`.
+    pub fn draw(&self, encoder: &mut wgpu::CommandEncoder, global_bind_group: &WgpuBindGroup0, output_view: &wgpu::TextureView)
+    {
+        {
+            let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("Histogram pass"),
+                timestamp_writes: None
+            });
+            compute_pass.set_bind_group(0, &self.compute_bind_group, &[]);
+            let [clear, bounds, count, accumulate] = &self.compute_pipelines;
+            let workgroups = |n: u32| n.div_ceil(WORKGROUP_SIZE);
+            let (x, y) = (workgroups(self.escape_texture.width()), workgroups(self.escape_texture.height()));
+            for (pipeline, (x, y)) in [(clear, (1, 1)), (bounds, (x, y)), (count, (x, y)), (accumulate, (1, 1))]
+            {
+                compute_pass.set_pipeline(pipeline);
+                compute_pass.dispatch_workgroups(x, y, 1);
+            }
+        }
+
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Equalize pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: output_view,
+                depth_slice: None,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            multiview_mask: None,
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        global_bind_group.set(&mut render_pass);
+        render_pass.set_bind_group(1, &self.equalize_bind_group, &[]);
+        render_pass.set_pipeline(&self.equalize_pipeline);
+        render_pass.draw(0..3, 0..1);
+    }
+
+    fn texture_size(size: PhysicalSize<u32>) -> PhysicalSize<u32>
+    {
+        PhysicalSize::new(size.width.max(1), size.height.max(1))
+    }
+
+    fn create_escape_texture(device: &wgpu::Device, size: PhysicalSize<u32>) -> wgpu::Texture
+    {
+        let size = Self::texture_size(size);
+        device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Escape texture"),
+            size: wgpu::Extent3d {
+                width: size.width,
+                height: size.height,
+                depth_or_array_layers: 1
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: ESCAPE_FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[]
+        })
+    }
+
+    fn create_bind_groups(
+        device: &wgpu::Device,
+        compute_layout: &wgpu::BindGroupLayout,
+        equalize_layout: &wgpu::BindGroupLayout,
+        escape_texture: &wgpu::Texture,
+        histogram_buffer: &wgpu::Buffer,
+        cdf_buffer: &wgpu::Buffer
+    ) -> (wgpu::BindGroup, wgpu::BindGroup)
+    {
+        let escape_view = escape_texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let compute_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Histogram bind group"),
+            layout: compute_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&escape_view)
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: histogram_buffer.as_entire_binding()
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: cdf_buffer.as_entire_binding()
+                }
+            ]
+        });
+        let equalize_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Equalize bind group"),
+            layout: equalize_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&escape_view)
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: cdf_buffer.as_entire_binding()
+                }
+            ]
+        });
+        (compute_bind_group, equalize_bind_group)
+    }
+}
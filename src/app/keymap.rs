@@ -0,0 +1,224 @@
+use serde::{Deserialize, Serialize};
+use winit::{event::{ElementState, MouseButton}, keyboard::{KeyCode, ModifiersState}};
+
+use crate::{app::{Action, MoveDirection, RotateDirection, ZoomDirection}, fractal::Target, settings::read_config};
+
+const KEYMAP_FILE: &str = "keymap.toml";
+
+/// Which inputs do what, loaded from `keymap.toml` in the config directory. The file replaces the default map entirely.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Keymap
+{
+    #[serde(rename = "bind")]
+    pub bindings: Vec<Binding>
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Binding
+{
+    #[serde(flatten)]
+    pub input: Input,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub modifiers: Vec<Modifier>,
+    pub action: Action
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Input
+{
+    Key(KeyCode),
+    Mouse(MouseButton)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Modifier
+{
+    Shift,
+    Control,
+    Alt,
+    Super
+}
+
+impl Default for Keymap
+{
+    fn default() -> Self
+    {
+        let key = |key_code, action| Binding {
+            input: Input::Key(key_code),
+            modifiers: vec![],
+            action
+        };
+        let mouse = |button, action| Binding {
+            input: Input::Mouse(button),
+            modifiers: vec![],
+            action
+        };
+        let digits = [
+            KeyCode::Digit1,
+            KeyCode::Digit2,
+            KeyCode::Digit3,
+            KeyCode::Digit4,
+            KeyCode::Digit5,
+            KeyCode::Digit6,
+            KeyCode::Digit7,
+            KeyCode::Digit8,
+            KeyCode::Digit9
+        ];
+        let bookmarks = (1..).zip(digits)
+            .flat_map(|(slot, key_code)| [
+                Binding {
+                    input: Input::Key(key_code),
+                    modifiers: vec![Modifier::Shift],
+                    action: Action::SaveBookmark(slot)
+                },
+                key(key_code, Action::GotoBookmark(slot))
+            ]);
+        Self {
+            bindings: vec![
+                key(KeyCode::Escape, Action::Exit),
+                key(KeyCode::Abort, Action::Exit),
+                key(KeyCode::KeyI, Action::MoveExp(MoveDirection::Up)),
+                key(KeyCode::KeyJ, Action::MoveExp(MoveDirection::Left)),
+                key(KeyCode::KeyK, Action::MoveExp(MoveDirection::Down)),
+                key(KeyCode::KeyL, Action::MoveExp(MoveDirection::Right)),
+                key(KeyCode::KeyW, Action::MoveShift(MoveDirection::Up)),
+                key(KeyCode::KeyA, Action::MoveShift(MoveDirection::Left)),
+                key(KeyCode::KeyS, Action::MoveShift(MoveDirection::Down)),
+                key(KeyCode::KeyD, Action::MoveShift(MoveDirection::Right)),
+                key(KeyCode::ArrowUp, Action::MoveCenter(MoveDirection::Up)),
+                key(KeyCode::ArrowLeft, Action::MoveCenter(MoveDirection::Left)),
+                key(KeyCode::ArrowDown, Action::MoveCenter(MoveDirection::Down)),
+                key(KeyCode::ArrowRight, Action::MoveCenter(MoveDirection::Right)),
+                key(KeyCode::KeyQ, Action::Rotate(RotateDirection::Left)),
+                key(KeyCode::KeyE, Action::Rotate(RotateDirection::Right)),
+                key(KeyCode::Comma, Action::RotateCenter(RotateDirection::Left)),
+                key(KeyCode::Period, Action::RotateCenter(RotateDirection::Right)),
+                key(KeyCode::KeyZ, Action::RotateExp(RotateDirection::Left)),
+                key(KeyCode::KeyX, Action::RotateExp(RotateDirection::Right)),
+                key(KeyCode::KeyN, Action::RotateShift(RotateDirection::Left)),
+                key(KeyCode::KeyM, Action::RotateShift(RotateDirection::Right)),
+                key(KeyCode::Numpad8, Action::MoveTrap(MoveDirection::Up)),
+                key(KeyCode::Numpad4, Action::MoveTrap(MoveDirection::Left)),
+                key(KeyCode::Numpad2, Action::MoveTrap(MoveDirection::Down)),
+                key(KeyCode::Numpad6, Action::MoveTrap(MoveDirection::Right)),
+                key(KeyCode::Numpad7, Action::RotateTrap(RotateDirection::Left)),
+                key(KeyCode::Numpad9, Action::RotateTrap(RotateDirection::Right)),
+                key(KeyCode::NumpadAdd, Action::Zoom(ZoomDirection::Inwards)),
+                key(KeyCode::NumpadSubtract, Action::Zoom(ZoomDirection::Outwards)),
+                key(KeyCode::Space, Action::Reverse),
+                key(KeyCode::KeyF, Action::Fullscreen),
+                key(KeyCode::KeyR, Action::Reset),
+                key(KeyCode::KeyV, Action::ResetView),
+                key(KeyCode::KeyT, Action::ResetTime),
+                key(KeyCode::KeyH, Action::Pause),
+                key(KeyCode::BracketLeft, Action::SlowDown),
+                key(KeyCode::BracketRight, Action::SpeedUp),
+                key(KeyCode::KeyG, Action::NextFractal),
+                key(KeyCode::KeyB, Action::NextColormap),
+                Binding {
+                    input: Input::Key(KeyCode::KeyB),
+                    modifiers: vec![Modifier::Shift],
+                    action: Action::NextDistance
+                },
+                key(KeyCode::KeyY, Action::NextTrap),
+                key(KeyCode::F5, Action::ReloadSettings),
+                key(KeyCode::KeyP, Action::PrintLocation),
+                key(KeyCode::KeyO, Action::Autopilot),
+                key(KeyCode::KeyC, Action::Locate(Target::Nucleus)),
+                Binding {
+                    input: Input::Key(KeyCode::KeyC),
+                    modifiers: vec![Modifier::Shift],
+                    action: Action::Locate(Target::Misiurewicz)
+                },
+                key(KeyCode::KeyU, Action::Dual),
+                mouse(MouseButton::Left, Action::ZoomAtCursor(ZoomDirection::Inwards)),
+                Binding {
+                    input: Input::Mouse(MouseButton::Left),
+                    modifiers: vec![Modifier::Shift],
+                    action: Action::OpenDual
+                },
+                mouse(MouseButton::Right, Action::ZoomAtCursor(ZoomDirection::Outwards)),
+                mouse(MouseButton::Middle, Action::StopZoom)
+            ].into_iter()
+                .chain(bookmarks)
+                .collect()
+        }
+    }
+}
+
+impl Keymap
+{
+    /// Reads the keymap file, or falls back to the default map if there is none.
+    pub fn load() -> anyhow::Result<Self>
+    {
+        let Some((path, keymap)) = read_config::<Self>(KEYMAP_FILE)?
+        else
+        {
+            return Ok(Self::default())
+        };
+        keymap.validate()
+            .map_err(|error| error.context(format!("Invalid keymap in {}", path.display())))?;
+        Ok(keymap)
+    }
+
+    /// Fails if the same input and modifiers are bound more than once.
+    pub fn validate(&self) -> anyhow::Result<()>
+    {
+        for (i, binding) in self.bindings.iter().enumerate()
+        {
+            if let Some(other) = self.bindings[..i].iter()
+                .find(|other| other.input == binding.input && other.modifiers_state() == binding.modifiers_state())
+            {
+                return Err(anyhow::Error::msg(format!(
+                    "{:?} with modifiers {:?} is bound to both {:?} and {:?}",
+                    binding.input,
+                    binding.modifiers,
+                    other.action,
+                    binding.action
+                )))
+            }
+        }
+        Ok(())
+    }
+
+    /// The actions an input event leads to.
+    ///
+    /// Presses prefer a binding with exactly the held modifiers, and fall back on one without any. Releases end every
+    /// held action bound to the input, since the modifiers may have changed since it was pressed.
+    pub fn actions(&self, input: Input, modifiers: ModifiersState, button_state: ElementState) -> Vec<Action>
+    {
+        match button_state
+        {
+            ElementState::Pressed => self.bindings.iter()
+                .find(|binding| binding.input == input && binding.modifiers_state() == modifiers)
+                .or_else(|| self.bindings.iter().find(|binding| binding.input == input && binding.modifiers.is_empty()))
+                .map(|binding| binding.action)
+                .into_iter()
+                .collect(),
+            ElementState::Released => self.bindings.iter()
+                .filter(|binding| binding.input == input && !binding.action.is_trigger())
+                .map(|binding| binding.action)
+                .collect()
+        }
+    }
+}
+
+impl Binding
+{
+    pub fn modifiers_state(&self) -> ModifiersState
+    {
+        self.modifiers.iter()
+            .map(|modifier| match modifier
+            {
+                Modifier::Shift => ModifiersState::SHIFT,
+                Modifier::Control => ModifiersState::CONTROL,
+                Modifier::Alt => ModifiersState::ALT,
+                Modifier::Super => ModifiersState::SUPER
+            })
+            .fold(ModifiersState::empty(), |a, b| a | b)
+    }
+}
@@ -1,18 +1,48 @@
 use core::{fmt::Display, ops::RangeInclusive};
+use std::{path::PathBuf, sync::Arc};
 
 use num_traits::{Float, FloatConst, NumAssignOps, float::FloatCore};
 use rand::{distr::{uniform::SampleUniform}};
 use winit::{application::ApplicationHandler, dpi::LogicalSize, event::WindowEvent, event_loop::ActiveEventLoop, window::{Fullscreen, Window, WindowId}};
 
-use crate::{MyFloat, fractal::{self, Fractal}};
+use crate::{MyFloat, fractal::{self, Fractal}, settings::Settings};
 
 moddef::moddef!(
     flat(pub) mod {
+        action,
+        keymap,
+        bookmarks,
         view,
-        state
+        state,
+        fractal_pass,
+        histogram_pass,
+        dual,
+        shader_watch,
+        offscreen,
+        timeline,
+        recording,
+        cpu
     }
 );
 
+#[cfg(test)]
+mod tests;
+
+/// How the window runs, apart from what it shows.
+#[derive(Debug, Clone, Default)]
+pub struct Options
+{
+    pub fullscreen: bool,
+    /// Where to record the session to.
+    pub record: Option<PathBuf>,
+    /// Seconds to step every frame instead of by the wall clock.
+    pub time_step: Option<f64>,
+    /// Whether to start out on autopilot.
+    pub autopilot: bool,
+    /// Whether to reload the shaders from the source tree when they change.
+    pub watch_shaders: bool
+}
+
 pub struct App<F, Z, G>
 where
     F: MyFloat,
@@ -20,6 +50,10 @@ where
     Z: Fractal<F>
 {
     fractal: G::IntoIter,
+    start: StartView<F>,
+    settings: Settings,
+    keymap: Keymap,
+    options: Options,
     state: Option<State<F, Z>>
 }
 
@@ -29,10 +63,14 @@ where
     G: IntoIterator<Item = Z>,
     Z: Fractal<F>
 {
-    pub fn new(fractal: G) -> Self
+    pub fn new(fractal: G, start: StartView<F>, settings: Settings, keymap: Keymap, options: Options) -> Self
     {
         Self {
             fractal: fractal.into_iter(),
+            start,
+            settings,
+            keymap,
+            options,
             state: None
         }
     }
@@ -52,21 +90,41 @@ impl<F, Z, G> ApplicationHandler<()> for App<F, Z, G>
 where
     F: MyFloat,
     G: IntoIterator<Item = Z>,
-    Z: Fractal<F>
+    Z: Fractal<F> + From<Arc<dyn Fractal<F>>>
 {
     fn resumed(&mut self, event_loop: &ActiveEventLoop)
     {
         let window = event_loop.create_window(
             Window::default_attributes()
             .with_title("fractal-zoom")
-            .with_fullscreen(Some(Fullscreen::Borderless(None)))
+            .with_fullscreen(self.options.fullscreen.then_some(Fullscreen::Borderless(None)))
             .with_min_inner_size(LogicalSize::new(640, 480))
             .with_inner_size(LogicalSize::new(1024, 768))
         ).unwrap();
 
         self.state = futures::executor::block_on(async {
-            Some(State::new(window, self.next_fractal(event_loop)?).await.unwrap())
+            Some(State::new(window, self.next_fractal(event_loop)?, self.start, self.settings.clone(), self.keymap.clone()).await.unwrap())
         });
+        if let (Some(state), Some(dt)) = (&mut self.state, self.options.time_step)
+        {
+            state.fixed_step(dt)
+        }
+        if let Some(state) = &mut self.state
+            && self.options.autopilot
+        {
+            state.toggle_autopilot()
+        }
+        if let Some(state) = &mut self.state
+            && self.options.watch_shaders
+        {
+            state.watch_shaders()
+        }
+        if let (Some(state), Some(path)) = (&mut self.state, &self.options.record)
+            && let Err(error) = state.record_to(path)
+        {
+            eprintln!("{error:#}");
+            self.state = None
+        }
         if self.state.is_none()
         {
             event_loop.exit();
@@ -88,6 +146,10 @@ where
                 AppAction::NextFractal => if let Some(fractal) = self.next_fractal(event_loop)
                 {
                     self.state = self.state.take().map(|state| state.with_fractal(fractal).unwrap());
+                },
+                AppAction::OpenDual => if let Some(fractal) = state.dual_at_cursor()
+                {
+                    self.state = self.state.take().map(|state| state.with_dual(Z::from(fractal)).unwrap());
                 }
             }
         }
@@ -97,5 +159,6 @@ where
 pub enum AppAction
 {
     Idle,
-    NextFractal
+    NextFractal,
+    OpenDual
 }
\ No newline at end of file
@@ -0,0 +1,299 @@
+use std::{fs::File, io::{BufWriter, Write}, path::{Path, PathBuf}};
+
+use winit::dpi::PhysicalSize;
+
+use crate::{MyFloat, app::{FractalPass, View}, fractal::Fractal};
+
+/// Renders fractals into a texture instead of a window surface, so images can be produced without a display.
+#[derive(Debug)]
+pub struct Offscreen
+{
+    device: wgpu::Device,
+    queue: wgpu::Queue
+}
+
+impl Offscreen
+{
+    pub const FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
+
+    /// Picks the default adapter, or a software adapter (lavapipe, llvmpipe, WARP) if `force_fallback_adapter` is set
+    /// or no other adapter is available.
+    pub async fn new(force_fallback_adapter: bool) -> anyhow::Result<Self>
+    {
+        let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor {
+            backends: wgpu::Backends::all(),
+            ..Default::default()
+        });
+        let adapter = match instance.request_adapter(&wgpu::RequestAdapterOptions {
+                power_preference: wgpu::PowerPreference::default(),
+                compatible_surface: None,
+                force_fallback_adapter,
+            }).await
+        {
+            Ok(adapter) => adapter,
+            Err(_) if !force_fallback_adapter => instance.request_adapter(&wgpu::RequestAdapterOptions {
+                power_preference: wgpu::PowerPreference::default(),
+                compatible_surface: None,
+                force_fallback_adapter: true,
+            }).await?,
+            Err(err) => return Err(err.into())
+        };
+
+        let info = adapter.get_info();
+        println!("Offscreen adapter: {} ({:?})", info.name, info.backend);
+
+        let (device, queue) = adapter.request_device(&wgpu::DeviceDescriptor {
+            label: Some("Offscreen device"),
+            required_features: wgpu::Features::empty(),
+            required_limits: wgpu::Limits::downlevel_defaults().using_resolution(adapter.limits()),
+            memory_hints: Default::default(),
+            ..Default::default()
+        }).await?;
+
+        Ok(Self { device, queue })
+    }
+
+    pub fn render<F, Z>(&self, fractal: &Z, view: &View<F>) -> anyhow::Result<Frame>
+    where
+        F: MyFloat,
+        Z: Fractal<F>
+    {
+        self.render_view(&mut self.pass(fractal, view)?, fractal, view)
+    }
+
+    pub fn pass<F, Z>(&self, fractal: &Z, view: &View<F>) -> anyhow::Result<FractalPass>
+    where
+        F: MyFloat,
+        Z: Fractal<F>
+    {
+        FractalPass::new(&self.device, fractal, view.uniforms(), Self::FORMAT, &view.settings().shader)
+    }
+
+    /// Like [`Offscreen::pass`], falling back on the shaders as they were built with why going to `report`.
+    pub fn pass_or_built_in<F, Z, R>(&self, fractal: &Z, view: &View<F>, report: R) -> anyhow::Result<FractalPass>
+    where
+        F: MyFloat,
+        Z: Fractal<F>,
+        R: FnOnce(String)
+    {
+        FractalPass::new_or_built_in(&self.device, fractal, view.uniforms(), Self::FORMAT, &view.settings().shader, report)
+    }
+
+    /// Renders with an already set up pass, so consecutive frames of the same fractal don't rebuild the pipeline.
+    pub fn render_view<F, Z>(&self, pass: &mut FractalPass, fractal: &Z, view: &View<F>) -> anyhow::Result<Frame>
+    where
+        F: MyFloat,
+        Z: Fractal<F>
+    {
+        pass.update(&self.device, &self.queue, fractal, view.globals());
+
+        let size = view.win_size();
+        if size.width == 0 || size.height == 0
+        {
+            return Err(anyhow::Error::msg("Cannot render an empty frame"))
+        }
+
+        let extent = wgpu::Extent3d {
+            width: size.width,
+            height: size.height,
+            depth_or_array_layers: 1
+        };
+        let texture = self.device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Offscreen texture"),
+            size: extent,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: Self::FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[]
+        });
+        let output_view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        // Rows of a texture copy must be padded to a multiple of `COPY_BYTES_PER_ROW_ALIGNMENT`
+        let bytes_per_pixel = Self::FORMAT.block_copy_size(None).unwrap_or(4);
+        let unpadded_bytes_per_row = size.width*bytes_per_pixel;
+        let padded_bytes_per_row = unpadded_bytes_per_row.next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+
+        let readback_buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Offscreen readback buffer"),
+            size: padded_bytes_per_row as u64*size.height as u64,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false
+        });
+
+        let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Offscreen Encoder"),
+        });
+
+        pass.draw(&mut encoder, &output_view);
+
+        encoder.copy_texture_to_buffer(
+            texture.as_image_copy(),
+            wgpu::TexelCopyBufferInfo {
+                buffer: &readback_buffer,
+                layout: wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_bytes_per_row),
+                    rows_per_image: Some(size.height)
+                }
+            },
+            extent
+        );
+
+        self.queue.submit(core::iter::once(encoder.finish()));
+
+        let slice = readback_buffer.slice(..);
+        let (tx, rx) = std::sync::mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            let _ = tx.send(result);
+        });
+        self.device.poll(wgpu::PollType::wait_indefinitely())?;
+        rx.recv()??;
+
+        let mapped = slice.get_mapped_range();
+        let data = mapped.chunks_exact(padded_bytes_per_row as usize)
+            .flat_map(|row| &row[..unpadded_bytes_per_row as usize])
+            .copied()
+            .collect();
+        core::mem::drop(mapped);
+        readback_buffer.unmap();
+
+        Ok(Frame { size, data })
+    }
+}
+
+/// A tightly packed RGBA8 (sRGB) image read back from the GPU.
+#[derive(Debug, Clone)]
+pub struct Frame
+{
+    pub size: PhysicalSize<u32>,
+    pub data: Vec<u8>
+}
+
+impl Frame
+{
+    pub fn pixel(&self, x: u32, y: u32) -> [u8; 4]
+    {
+        let i = (y as usize*self.size.width as usize + x as usize)*4;
+        [self.data[i], self.data[i + 1], self.data[i + 2], self.data[i + 3]]
+    }
+
+    pub fn write_png<W>(&self, w: W) -> anyhow::Result<()>
+    where
+        W: Write
+    {
+        let mut encoder = png::Encoder::new(w, self.size.width, self.size.height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.set_source_srgb(png::SrgbRenderingIntent::Perceptual);
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&self.data)?;
+        writer.finish()?;
+        Ok(())
+    }
+
+    pub fn save_png<P>(&self, path: P) -> anyhow::Result<()>
+    where
+        P: AsRef<Path>
+    {
+        self.write_png(BufWriter::new(File::create(path)?))
+    }
+}
+
+/// Writes frames as an uncompressed YUV4MPEG2 stream, which ffmpeg and most players read as is.
+pub struct Y4mWriter<W>
+where
+    W: Write
+{
+    w: W,
+    size: PhysicalSize<u32>
+}
+
+impl<W> Y4mWriter<W>
+where
+    W: Write
+{
+    pub fn new(mut w: W, size: PhysicalSize<u32>, fps: u32) -> anyhow::Result<Self>
+    {
+        writeln!(w, "YUV4MPEG2 W{} H{} F{fps}:1 Ip A1:1 C444", size.width, size.height)?;
+        Ok(Self { w, size })
+    }
+
+    /// Converts to BT.601 limited range YCbCr, without chroma subsampling.
+    pub fn write(&mut self, frame: &Frame) -> anyhow::Result<()>
+    {
+        if frame.size != self.size
+        {
+            return Err(anyhow::Error::msg(format!("Frame size {:?} doesn't match the stream size {:?}", frame.size, self.size)))
+        }
+        let planes = [
+            [16.0, 65.481, 128.553, 24.966],
+            [128.0, -37.797, -74.203, 112.0],
+            [128.0, 112.0, -93.786, -18.214]
+        ];
+        self.w.write_all(b"FRAME\n")?;
+        for [offset, r, g, b] in planes
+        {
+            let plane: Vec<u8> = frame.data.chunks_exact(4)
+                .map(|pixel| {
+                    let [pr, pg, pb] = [pixel[0], pixel[1], pixel[2]].map(|c| c as f32/255.0);
+                    (offset + r*pr + g*pg + b*pb).round().clamp(0.0, 255.0) as u8
+                })
+                .collect();
+            self.w.write_all(&plane)?;
+        }
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> anyhow::Result<W>
+    {
+        self.w.flush()?;
+        Ok(self.w)
+    }
+}
+
+/// Where exported frames go: a `.y4m` video, or numbered PNGs in a directory.
+pub enum FrameSink
+{
+    Y4m(Y4mWriter<BufWriter<File>>),
+    Pngs(PathBuf)
+}
+
+impl FrameSink
+{
+    /// Picks by the extension of `path`.
+    pub fn create(path: &Path, size: PhysicalSize<u32>, fps: u32) -> anyhow::Result<Self>
+    {
+        if path.extension().is_some_and(|extension| extension == "y4m")
+        {
+            let file = File::create(path)
+                .map_err(|error| anyhow::Error::new(error).context(format!("Failed to create {}", path.display())))?;
+            Ok(Self::Y4m(Y4mWriter::new(BufWriter::new(file), size, fps)?))
+        }
+        else
+        {
+            std::fs::create_dir_all(path)
+                .map_err(|error| anyhow::Error::new(error).context(format!("Failed to create {}", path.display())))?;
+            Ok(Self::Pngs(path.to_path_buf()))
+        }
+    }
+
+    pub fn write(&mut self, i: usize, frame: &Frame) -> anyhow::Result<()>
+    {
+        match self
+        {
+            Self::Y4m(y4m) => y4m.write(frame),
+            Self::Pngs(dir) => frame.save_png(dir.join(format!("{i:05}.png")))
+        }
+    }
+
+    pub fn finish(self) -> anyhow::Result<()>
+    {
+        if let Self::Y4m(y4m) = self
+        {
+            y4m.finish()?;
+        }
+        Ok(())
+    }
+}
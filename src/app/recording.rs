@@ -0,0 +1,198 @@
+use std::{fs::File, io::{BufWriter, Write}, path::Path, time::Instant};
+
+use serde::{Deserialize, Serialize};
+use winit::{dpi::{PhysicalPosition, PhysicalSize}, event::ElementState};
+
+use crate::{MyFloat, app::{Action, Bookmark, StartView, View}, f, fractal::Fractal, settings::Settings};
+
+/// What a recorded session starts out from.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Session
+{
+    /// `<label>[:<param>=<value>,...]`, as given on the command line.
+    pub fractal: String,
+    pub size: [u32; 2],
+    pub start: StartView<f64>,
+    pub settings: Settings
+}
+
+/// Everything that moves the view in a live session, in the order it happened.
+///
+/// Whatever is read from disk along the way is recorded as its outcome, so replays don't depend on the files.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum Event
+{
+    /// A bound action, after going through the keymap.
+    Action {
+        action: Action,
+        pressed: bool
+    },
+    Scroll {
+        accel: f64,
+        brk: f64
+    },
+    Cursor {
+        x: f64,
+        y: f64
+    },
+    Resize {
+        width: u32,
+        height: u32
+    },
+    /// The view moved along by `dt` seconds.
+    Update {
+        dt: f64
+    },
+    /// Settings were reloaded.
+    Settings {
+        settings: Settings
+    },
+    /// A bookmark was jumped to.
+    Goto {
+        bookmark: Bookmark
+    },
+    /// Moved on to another fractal, starting over from `start`, which is the dual's own point for a dual.
+    Fractal {
+        fractal: String,
+        start: StartView<f64>
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Entry
+{
+    /// Seconds since the recording started.
+    t: f64,
+    #[serde(flatten)]
+    event: Event
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RecordingFile
+{
+    session: Session,
+    #[serde(default, rename = "event")]
+    events: Vec<Entry>
+}
+
+/// Writes a session to a TOML file as it happens, an `[[event]]` at a time.
+#[derive(Debug)]
+pub struct Recorder
+{
+    w: BufWriter<File>,
+    t0: Instant
+}
+
+impl Recorder
+{
+    pub fn create(path: &Path, session: &Session) -> anyhow::Result<Self>
+    {
+        #[derive(Serialize)]
+        struct Header<'a>
+        {
+            session: &'a Session
+        }
+
+        let file = File::create(path)
+            .map_err(|error| anyhow::Error::new(error).context(format!("Failed to create {}", path.display())))?;
+        let mut w = BufWriter::new(file);
+        w.write_all(toml::to_string(&Header { session })?.as_bytes())?;
+        Ok(Self { w, t0: Instant::now() })
+    }
+
+    pub fn record(&mut self, event: Event) -> anyhow::Result<()>
+    {
+        #[derive(Serialize)]
+        struct Events
+        {
+            event: [Entry; 1]
+        }
+
+        let entry = Entry {
+            t: self.t0.elapsed().as_secs_f64(),
+            event
+        };
+        writeln!(self.w)?;
+        self.w.write_all(toml::to_string(&Events { event: [entry] })?.as_bytes())?;
+        Ok(())
+    }
+}
+
+/// A recorded session, to be replayed into a [`View`].
+#[derive(Debug, Clone)]
+pub struct Recording
+{
+    pub session: Session,
+    /// Events with the seconds since the recording started.
+    pub events: Vec<(f64, Event)>
+}
+
+impl Recording
+{
+    pub fn load(path: &Path) -> anyhow::Result<Self>
+    {
+        let recording = std::fs::read_to_string(path)
+            .map_err(|error| anyhow::Error::new(error).context(format!("Failed to read {}", path.display())))?;
+        let RecordingFile { session, events } = toml::from_str(&recording)
+            .map_err(|error| anyhow::Error::new(error).context(format!("Failed to parse {}", path.display())))?;
+        Ok(Self {
+            session,
+            events: events.into_iter()
+                .map(|Entry { t, event }| (t, event))
+                .collect()
+        })
+    }
+
+    pub fn duration(&self) -> f64
+    {
+        self.events.last().map(|&(t, _)| t).unwrap_or(0.0)
+    }
+}
+
+impl Event
+{
+    /// Plays the event back into `view`. Moving on to another fractal is up to the caller, with
+    /// [`replay_event`](crate::replay_event).
+    ///
+    /// The view keeps its size, so every frame can go to the same video. `window` is the size of the recorded window,
+    /// which resizing changes, and the cursor is moved to stay over the same point in the plane.
+    pub fn replay<F, T>(&self, view: &mut View<F>, fractal: &T, window: &mut PhysicalSize<u32>)
+    where
+        F: MyFloat,
+        T: Fractal<F>
+    {
+        match self
+        {
+            Event::Action { action, pressed } => match action
+            {
+                // These read or write files or steer on their own, what came of it is recorded separately
+                Action::ReloadSettings
+                | Action::SaveBookmark(_)
+                | Action::GotoBookmark(_)
+                | Action::PrintLocation
+                | Action::Autopilot => (),
+                // What it finds only depends on the view and the fractal, so it comes out the same
+                &Action::Locate(target) => {
+                    let _ = view.locate(fractal, target);
+                },
+                &action => view.act(action, if *pressed { ElementState::Pressed } else { ElementState::Released }, fractal)
+            },
+            &Event::Scroll { accel, brk } => view.scroll(f!(accel), f!(brk)),
+            &Event::Cursor { x, y } => {
+                let size = view.win_size();
+                view.update_mouse_pos(PhysicalPosition::new(
+                    x + (size.width as f64 - window.width as f64)/2.0,
+                    y + (size.height as f64 - window.height as f64)/2.0
+                ))
+            },
+            &Event::Resize { width, height } => *window = PhysicalSize::new(width, height),
+            &Event::Update { dt } => view.step(f!(dt)),
+            Event::Settings { settings } => view.set_settings(settings.clone()),
+            Event::Goto { bookmark } => view.goto(bookmark),
+            Event::Fractal { .. } => ()
+        }
+    }
+}
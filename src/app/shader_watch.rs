@@ -0,0 +1,64 @@
+use std::{path::{Path, PathBuf}, time::{Instant, SystemTime}};
+
+use crate::SHADER_POLL;
+
+/// Polls the shader sources in `src/fractal` and `src/fractal/lib` for changes, so they can be reloaded while the
+/// app runs.
+#[derive(Debug)]
+pub struct ShaderWatch
+{
+    dir: PathBuf,
+    modified: Option<SystemTime>,
+    polled: Instant
+}
+
+impl Default for ShaderWatch
+{
+    fn default() -> Self
+    {
+        Self::new(Path::new(env!("CARGO_MANIFEST_DIR")).join("src").join("fractal"))
+    }
+}
+
+impl ShaderWatch
+{
+    pub fn new(dir: PathBuf) -> Self
+    {
+        let modified = Self::last_modified(&dir);
+        Self {
+            dir,
+            modified,
+            polled: Instant::now()
+        }
+    }
+
+    pub fn dir(&self) -> &Path
+    {
+        &self.dir
+    }
+
+    /// Whether a shader was saved since last time, looking at most every [`SHADER_POLL`] seconds.
+    pub fn changed(&mut self) -> bool
+    {
+        if self.polled.elapsed().as_secs_f64() < SHADER_POLL
+        {
+            return false
+        }
+        self.polled = Instant::now();
+        let modified = Self::last_modified(&self.dir);
+        let changed = modified > self.modified;
+        self.modified = modified;
+        changed
+    }
+
+    fn last_modified(dir: &Path) -> Option<SystemTime>
+    {
+        [dir.to_path_buf(), dir.join("lib")].into_iter()
+            .filter_map(|dir| std::fs::read_dir(dir).ok())
+            .flatten()
+            .filter_map(Result::ok)
+            .filter(|entry| entry.path().extension().is_some_and(|extension| extension == "wgsl"))
+            .filter_map(|entry| entry.metadata().and_then(|metadata| metadata.modified()).ok())
+            .max()
+    }
+}
@@ -1,13 +1,13 @@
 use core::{fmt::Display, ops::RangeInclusive};
-use std::{sync::{Arc, UniqueArc}, time::SystemTime};
+use std::{path::Path, sync::{Arc, UniqueArc}, time::SystemTime};
 
 use num_complex::Complex;
 use num_traits::{Float, FloatConst, NumAssignOps, float::FloatCore};
 use rand::distr::uniform::SampleUniform;
-use wgpu::{SurfaceConfiguration, util::DeviceExt};
-use winit::{dpi::{PhysicalPosition, PhysicalSize, Size}, event::{ElementState, MouseButton, MouseScrollDelta, TouchPhase, WindowEvent}, keyboard::{KeyCode, PhysicalKey}, window::{Fullscreen, Window}};
+use wgpu::SurfaceConfiguration;
+use winit::{dpi::{PhysicalPosition, PhysicalSize, Size}, event::{ElementState, MouseScrollDelta, TouchPhase, WindowEvent}, keyboard::{ModifiersState, PhysicalKey}, window::{Fullscreen, Window}};
 
-use crate::{MOVE_CENTER_ACCEL, MOVE_EXP_ACCEL, MOVE_SHIFT_ACCEL, MOVE_ZOOM_ACCEL, MyFloat, ROT_ACCEL, ZOOM_MUL, app::{AppAction, MoveDirection, RotateDirection, ZoomDirection, view::View}, f, fractal::{Fractal, GlobalUniforms, VertexInput, WgpuBindGroup0, WgpuBindGroup0Entries, WgpuBindGroup0EntriesParams}};
+use crate::{MyFloat, ZOOM_MUL, app::{Action, AppAction, Autopilot, Bookmarks, Dual, Event, FractalPass, Input, Keymap, Recorder, Session, ShaderWatch, view::{FixedClock, StartView, View}}, f, fractal::Fractal, settings::Settings};
 
 #[derive(Debug)]
 pub struct State<F, Z>
@@ -17,11 +17,14 @@ where
 {
     fractal: Z,
     view: View<F>,
+    keymap: Keymap,
+    modifiers: ModifiersState,
     render: Render,
-    global_uniforms_buffer: wgpu::Buffer,
-    global_bind_group: WgpuBindGroup0,
-    vertex_buffer: wgpu::Buffer,
-    render_pipeline: wgpu::RenderPipeline
+    pass: FractalPass,
+    recorder: Option<Recorder>,
+    autopilot: Option<Autopilot<F>>,
+    dual: Option<Dual>,
+    watch: Option<ShaderWatch>
 }
 
 impl<F, Z> State<F, Z>
@@ -29,65 +32,196 @@ where
     F: MyFloat + Display,
     Z: Fractal<F>
 {
-    pub async fn new(window: Window, fractal: Z) -> anyhow::Result<Self>
+    pub async fn new(window: Window, fractal: Z, start: StartView<F>, settings: Settings, keymap: Keymap) -> anyhow::Result<Self>
     where
         F: SampleUniform
     {
         let render = Render::new(window).await?;
-        Self::from_parts(render, fractal)
+        Self::from_parts(render, fractal, start, settings, keymap)
     }
 
     pub fn with_fractal<X>(self, fractal: X) -> anyhow::Result<State<F, X>>
     where
         X: Fractal<F>
     {
-        let Self { render: render, .. } = self;
-        State::from_parts(render, fractal)
+        let start = self.view.start();
+        self.with_start(fractal, start)
     }
 
-    fn from_parts(render: Render, fractal: Z) -> anyhow::Result<Self>
+    /// Opens the dual of the point it was picked at, rather than wherever the app was started.
+    pub fn with_dual<X>(self, fractal: X) -> anyhow::Result<State<F, X>>
+    where
+        X: Fractal<F>
     {
-        let size = render.window.inner_size();
-        let view = View::new(&fractal, size);
+        let start = self.view.start().dual();
+        self.with_start(fractal, start)
+    }
 
-        let global_uniforms = view.uniforms();
+    fn with_start<X>(self, fractal: X, start: StartView<F>) -> anyhow::Result<State<F, X>>
+    where
+        X: Fractal<F>
+    {
+        let Self { render, mut view, keymap, recorder, autopilot, dual, watch, .. } = self;
+        let mut state = State::from_parts(render, fractal, start, view.settings().clone(), keymap)?;
+        state.view.take_clock(&mut view);
+        state.recorder = recorder;
+        state.autopilot = autopilot.map(|_| Autopilot::default());
+        state.watch = watch;
+        if dual.is_some()
+        {
+            state.toggle_dual()
+        }
+        state.record(Event::Fractal { fractal: state.fractal.arg(), start: start.cast() });
+        Ok(state)
+    }
 
-        let global_uniforms_buffer = render.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("Global uniforms buffer"),
-            contents: bytemuck::cast_slice(&[global_uniforms]),
-            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
-        });
-        
-        // Create bind group using generated types - fully type-safe!
-        let global_bind_group = WgpuBindGroup0::from_bindings(
-            &render.device,
-            WgpuBindGroup0Entries::new(WgpuBindGroup0EntriesParams {
-                globals: wgpu::BufferBinding {
-                    buffer: &global_uniforms_buffer,
-                    offset: 0,
-                    size: None,
+    /// Starts recording everything that moves the view to `path`, for [`Recording`](crate::app::Recording) to replay.
+    pub fn record_to(&mut self, path: &Path) -> anyhow::Result<()>
+    {
+        let size = self.view.win_size();
+        self.recorder = Some(Recorder::create(path, &Session {
+            fractal: self.fractal.arg(),
+            size: [size.width, size.height],
+            start: self.view.start().cast(),
+            settings: self.view.settings().clone()
+        })?);
+        Ok(())
+    }
+
+    /// Steps the view by `dt` seconds every update rather than by the wall clock.
+    pub fn fixed_step(&mut self, dt: f64)
+    {
+        self.view.set_clock(FixedClock { dt })
+    }
+
+    pub fn toggle_autopilot(&mut self)
+    {
+        self.autopilot = match self.autopilot
+        {
+            Some(_) => None,
+            None => Some(Autopilot::default())
+        }
+    }
+
+    /// Reads the shaders from the source tree from now on, and reloads them whenever one is saved.
+    pub fn watch_shaders(&mut self)
+    {
+        self.watch = Some(ShaderWatch::default());
+        self.read_watched_shaders();
+        self.rebuild_pipelines()
+    }
+
+    /// Points the shader defs at the shaders being watched, which reloading the settings forgets.
+    fn read_watched_shaders(&mut self)
+    {
+        if let Some(watch) = &self.watch
+            && self.view.settings().shader.source_dir.as_deref() != Some(watch.dir())
+        {
+            let mut settings = self.view.settings().clone();
+            settings.shader.source_dir = Some(watch.dir().to_path_buf());
+            self.view.set_settings(settings)
+        }
+    }
+
+    /// Shows or hides the inset with the dual of the fractal, if it has one and the window can be drawn onto.
+    pub fn toggle_dual(&mut self)
+    {
+        self.dual = match self.dual
+        {
+            Some(_) => None,
+            None if !self.render.config.usage.contains(wgpu::TextureUsages::COPY_DST) => {
+                eprintln!("The window can't be copied onto, so there's no inset");
+                None
+            },
+            None => match Dual::new(&self.render.device, &self.fractal, &self.view, self.render.surface_format)
+            {
+                Ok(None) => {
+                    eprintln!("{} has no dual", self.fractal.label());
+                    None
                 },
-            })
-        );
+                Ok(dual) => dual,
+                Err(error) => {
+                    eprintln!("{error:#}");
+                    None
+                }
+            }
+        }
+    }
 
-        let render_pipeline = fractal.setup_render_pipeline(&render.device, render.surface_format);
-        let vertices = core::array::from_fn::<_, 6, _>(|i| VertexInput { vertex_id: i as u32 });
+    /// The dual of the fractal for the point under the cursor, to switch to.
+    pub fn dual_at_cursor(&self) -> Option<Arc<dyn Fractal<F>>>
+    {
+        Dual::at_cursor(&self.fractal, &self.view)
+    }
 
-        println!("Creating vertex buffer.");
-        let vertex_buffer = render.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("Fractal Vertex Buffer"),
-            contents: bytemuck::cast_slice(&vertices),
-            usage: wgpu::BufferUsages::VERTEX,
-        });
+    /// Sets up the pipelines again, for when the shaders change. If they don't compose or the device rejects them, the
+    /// old ones are kept.
+    fn rebuild_pipelines(&mut self)
+    {
+        let device = &self.render.device;
+        let scope = device.push_error_scope(wgpu::ErrorFilter::Validation);
+        let pass = FractalPass::new(device, &self.fractal, self.view.uniforms(), self.render.surface_format, &self.view.settings().shader);
+        let dual = match &self.dual
+        {
+            Some(_) => Dual::new(device, &self.fractal, &self.view, self.render.surface_format),
+            None => Ok(None)
+        };
+        let error = futures::executor::block_on(scope.pop());
+        let (pass, dual) = match (pass, dual, error)
+        {
+            (Ok(pass), Ok(dual), None) => (pass, dual),
+            (Err(error), _, _) | (_, Err(error), _) => {
+                eprintln!("{error:#}");
+                return
+            },
+            (_, _, Some(error)) => {
+                eprintln!("{error}");
+                return
+            }
+        };
+        self.pass = pass;
+        if self.dual.is_some()
+        {
+            self.dual = dual
+        }
+    }
+
+    /// Gives up on recording if writing fails, rather than taking the app down with it.
+    fn record(&mut self, event: Event)
+    {
+        if let Some(recorder) = &mut self.recorder
+            && let Err(error) = recorder.record(event)
+        {
+            eprintln!("{error:#}");
+            self.recorder = None
+        }
+    }
+
+    fn from_parts(render: Render, fractal: Z, start: StartView<F>, settings: Settings, keymap: Keymap) -> anyhow::Result<Self>
+    {
+        let size = render.window.inner_size();
+        let view = View::new(&fractal, size, start, settings);
+
+        let pass = FractalPass::new_or_built_in(
+            &render.device,
+            &fractal,
+            view.uniforms(),
+            render.surface_format,
+            &view.settings().shader,
+            |message| eprintln!("{message}")
+        )?;
 
         Ok(Self {
             fractal,
             view,
+            keymap,
+            modifiers: ModifiersState::empty(),
             render,
-            global_uniforms_buffer,
-            global_bind_group,
-            vertex_buffer,
-            render_pipeline
+            pass,
+            recorder: None,
+            autopilot: None,
+            dual: None,
+            watch: None
         })
     }
     
@@ -95,6 +229,7 @@ where
     {
         if new_size.width > 0 && new_size.height > 0
         {
+            self.record(Event::Resize { width: new_size.width, height: new_size.height });
             self.view.resize(new_size);
             self.render.config.width = new_size.width;
             self.render.config.height = new_size.height;
@@ -104,16 +239,24 @@ where
 
     pub fn update(&mut self) -> anyhow::Result<()>
     {
-        self.view.update()?;
+        if self.watch.as_mut().is_some_and(ShaderWatch::changed)
+        {
+            self.rebuild_pipelines()
+        }
+        let dt = self.view.update();
+        self.record(Event::Update { dt });
+        if let Some(autopilot) = &mut self.autopilot
+        {
+            autopilot.steer(&mut self.view, &self.fractal, f!(dt));
+            self.record(Event::Goto { bookmark: self.view.bookmark() });
+        }
 
         // Update global uniforms with new frame size immediately
-        let global_uniforms = self.view.uniforms();
-
-        self.render.queue.write_buffer(
-            &self.global_uniforms_buffer,
-            0,
-            bytemuck::cast_slice(&[global_uniforms]),
-        );
+        self.pass.update(&self.render.device, &self.render.queue, &self.fractal, self.view.globals());
+        if let Some(dual) = &mut self.dual
+        {
+            dual.update(&self.render.device, &self.render.queue, &self.fractal, &self.view);
+        }
         Ok(())
     }
 
@@ -139,131 +282,26 @@ where
                     self.fractal.label()
                 ));
 
-                enum Action
-                {
-                    MoveCenter(MoveDirection),
-                    MoveShift(MoveDirection),
-                    MoveExp(MoveDirection),
-                    Zoom(ZoomDirection),
-
-                    Rotate(RotateDirection),
-                    RotateCenter(RotateDirection),
-                    RotateShift(RotateDirection),
-                    RotateExp(RotateDirection),
-                    
-                    AccelCenter(Option<MoveDirection>),
-                    AccelShift(Option<MoveDirection>),
-                    AccelExp(Option<MoveDirection>),
-                    AccelZoom(Option<ZoomDirection>),
-                    AccelRotate(Option<RotateDirection>),
-
-                    Reverse,
-                    Stop,
-                    Idle,
-                    Fullscreen,
-                    Reset,
-                    ResetTime,
-                    ResetView,
-                    NextFractal
-                }
-
-                match match event.physical_key
+                let PhysicalKey::Code(key_code) = event.physical_key
+                else
                 {
-                    PhysicalKey::Code(key_code) => match key_code {
-                        KeyCode::Escape | KeyCode::Abort => {
-                            event_loop.exit();
-                            return AppAction::Idle;
-                        }
-                        KeyCode::KeyI => Action::MoveExp(MoveDirection::Up),
-                        KeyCode::KeyJ => Action::MoveExp(MoveDirection::Left),
-                        KeyCode::KeyK => Action::MoveExp(MoveDirection::Down),
-                        KeyCode::KeyL => Action::MoveExp(MoveDirection::Right),
-                        KeyCode::KeyW => Action::MoveShift(MoveDirection::Up),
-                        KeyCode::KeyA => Action::MoveShift(MoveDirection::Left),
-                        KeyCode::KeyS => Action::MoveShift(MoveDirection::Down),
-                        KeyCode::KeyD => Action::MoveShift(MoveDirection::Right),
-                        KeyCode::ArrowUp => Action::MoveCenter(MoveDirection::Up),
-                        KeyCode::ArrowLeft => Action::MoveCenter(MoveDirection::Left),
-                        KeyCode::ArrowDown => Action::MoveCenter(MoveDirection::Down),
-                        KeyCode::ArrowRight => Action::MoveCenter(MoveDirection::Right),
-                        KeyCode::KeyQ => Action::Rotate(RotateDirection::Left),
-                        KeyCode::KeyE => Action::Rotate(RotateDirection::Right),
-                        KeyCode::Comma => Action::RotateCenter(RotateDirection::Left),
-                        KeyCode::Period => Action::RotateCenter(RotateDirection::Right),
-                        KeyCode::KeyZ => Action::RotateExp(RotateDirection::Left),
-                        KeyCode::KeyX => Action::RotateExp(RotateDirection::Right),
-                        KeyCode::KeyN => Action::RotateShift(RotateDirection::Left),
-                        KeyCode::KeyM => Action::RotateShift(RotateDirection::Right),
-                        KeyCode::NumpadAdd => Action::Zoom(ZoomDirection::Inwards),
-                        KeyCode::NumpadSubtract => Action::Zoom(ZoomDirection::Outwards),
-                        KeyCode::Space => Action::Reverse,
-                        KeyCode::KeyF if matches!(event.state, ElementState::Pressed) => Action::Fullscreen,
-                        KeyCode::KeyR if matches!(event.state, ElementState::Pressed) => Action::Reset,
-                        KeyCode::KeyV if matches!(event.state, ElementState::Pressed) => Action::ResetView,
-                        KeyCode::KeyT if matches!(event.state, ElementState::Pressed) => Action::ResetTime,
-                        KeyCode::KeyV if matches!(event.state, ElementState::Pressed) => Action::ResetView,
-                        KeyCode::KeyG if matches!(event.state, ElementState::Pressed) => Action::NextFractal,
-                        _ => Action::Idle
-                    },
-                    PhysicalKey::Unidentified(_) => Action::Idle
-                }
+                    return AppAction::Idle
+                };
+                if let Some(app_action) = self.input(event_loop, Input::Key(key_code), event.state)
                 {
-                    Action::Idle => return AppAction::Idle,
-                    Action::MoveCenter(direction) => self.view.center.mov(direction, event.state),
-                    Action::MoveExp(direction) => self.view.exp.mov(direction, event.state),
-                    Action::MoveShift(direction) => self.view.shift.mov(direction, event.state),
-                    Action::Zoom(direction) => self.view.zoom.mov(direction, event.state),
-
-                    Action::Rotate(direction) => self.view.rot.rot(direction, event.state),
-                    Action::RotateCenter(direction) => self.view.center.rot(direction, event.state),
-                    Action::RotateShift(direction) => self.view.shift.rot(direction, event.state),
-                    Action::RotateExp(direction) => self.view.exp.rot(direction, event.state),
-
-                    Action::AccelCenter(direction) => self.view.center.push(direction.map(|dir| (dir, f!(MOVE_CENTER_ACCEL)))),
-                    Action::AccelShift(direction) => self.view.shift.push(direction.map(|dir| (dir, f!(MOVE_SHIFT_ACCEL)))),
-                    Action::AccelExp(direction) => self.view.exp.push(direction.map(|dir| (dir, f!(MOVE_EXP_ACCEL)))),
-                    Action::AccelRotate(direction) => self.view.rot.push(direction.map(|dir| (dir, f!(ROT_ACCEL)))),
-                    Action::AccelZoom(direction) => self.view.zoom.push(direction.map(|dir| (dir, f!(MOVE_ZOOM_ACCEL)))),
-
-                    Action::Reverse => self.view.reverse(event.state),
-                    Action::Fullscreen => self.render.window.set_fullscreen(match self.render.window.fullscreen()
-                    {
-                        Some(Fullscreen::Borderless(_) | Fullscreen::Exclusive(_)) => None,
-                        None => Some(Fullscreen::Borderless(None))
-                    }),
-                    Action::Reset => {
-                        self.view.reset(&self.fractal);
-                    },
-                    Action::ResetTime => {
-                        self.view.reset_time();
-                    }
-                    Action::ResetView => {
-                        self.view.reset_view(&self.fractal);
-                    }
-                    Action::NextFractal => return AppAction::NextFractal,
-                    Action::Stop => event_loop.exit()
+                    return app_action
                 }
 
                 self.render.window.request_redraw();
-            }
+            },
+            WindowEvent::ModifiersChanged(modifiers) => self.modifiers = modifiers.state(),
             WindowEvent::Resized(physical_size) => {
                 self.resize(physical_size);
                 self.render.window.request_redraw();
             },
-            WindowEvent::MouseInput { device_id: _, state, button } => {
-                match (button, state)
-                {
-                    (MouseButton::Left, ElementState::Pressed) => {
-                        self.view.recenter();
-                        self.view.reverse = false
-                    },
-                    (MouseButton::Middle, ElementState::Pressed) => self.view.zoom.stop(),
-                    (MouseButton::Right, ElementState::Pressed) => {
-                        self.view.recenter();
-                        self.view.reverse = true
-                    }
-                    _ => ()
-                }
+            WindowEvent::MouseInput { device_id: _, state, button } => if let Some(app_action) = self.input(event_loop, Input::Mouse(button), state)
+            {
+                return app_action
             },
             WindowEvent::MouseWheel { device_id: _, delta, phase } => match phase
             {
@@ -273,11 +311,12 @@ where
                         MouseScrollDelta::LineDelta(x, y) => (y as f64, x as f64),
                         MouseScrollDelta::PixelDelta(PhysicalPosition {x, y}) => (y, x)
                     };
-                    self.view.zoom.push(Some((ZoomDirection::Inwards, f!(accel))));
-                    self.view.zoom.brk(f!(brk))
+                    self.record(Event::Scroll { accel, brk });
+                    self.view.scroll(f!(accel), f!(brk))
                 }
             },
             WindowEvent::CursorMoved { position, device_id: _ } => {
+                self.record(Event::Cursor { x: position.x, y: position.y });
                 self.view.update_mouse_pos(position);
                 self.render.window.request_redraw();
             },
@@ -304,6 +343,67 @@ where
         AppAction::Idle
     }
 
+    /// Acts on whatever the input is bound to, returning early if the app has to take over.
+    fn input(&mut self, event_loop: &winit::event_loop::ActiveEventLoop, input: Input, button_state: ElementState) -> Option<AppAction>
+    {
+        for action in self.keymap.actions(input, self.modifiers, button_state)
+        {
+            self.record(Event::Action { action, pressed: button_state.is_pressed() });
+            match action
+            {
+                Action::Idle => return Some(AppAction::Idle),
+                Action::Exit => {
+                    event_loop.exit();
+                    return Some(AppAction::Idle);
+                },
+                Action::Fullscreen => self.render.window.set_fullscreen(match self.render.window.fullscreen()
+                {
+                    Some(Fullscreen::Borderless(_) | Fullscreen::Exclusive(_)) => None,
+                    None => Some(Fullscreen::Borderless(None))
+                }),
+                Action::NextFractal => return Some(AppAction::NextFractal),
+                Action::Stop => event_loop.exit(),
+                Action::PrintLocation => println!("{}", self.view.location(&self.fractal)),
+                Action::Locate(target) => match self.view.locate(&self.fractal, target)
+                {
+                    Ok(located) => println!("{located}"),
+                    Err(error) => eprintln!("{error:#}")
+                },
+                Action::Autopilot => self.toggle_autopilot(),
+                Action::Dual => self.toggle_dual(),
+                Action::OpenDual => return Some(AppAction::OpenDual),
+                Action::SaveBookmark(slot) => if let Err(error) = Bookmarks::save_slot(&self.fractal.arg(), slot, self.view.bookmark())
+                {
+                    eprintln!("{error:#}")
+                },
+                Action::GotoBookmark(slot) => match Bookmarks::load_slot(&self.fractal.arg(), slot)
+                {
+                    Ok(bookmark) => {
+                        self.view.goto(&bookmark);
+                        self.record(Event::Goto { bookmark })
+                    },
+                    Err(error) => eprintln!("{error:#}")
+                },
+                Action::ReloadSettings => match Settings::load()
+                {
+                    Ok(settings) => {
+                        let shader = self.view.settings().shader.clone();
+                        self.view.set_settings(settings);
+                        self.record(Event::Settings { settings: self.view.settings().clone() });
+                        self.read_watched_shaders();
+                        if self.view.settings().shader != shader
+                        {
+                            self.rebuild_pipelines()
+                        }
+                    },
+                    Err(error) => eprintln!("{error:#}")
+                },
+                action => self.view.act(action, button_state, &self.fractal)
+            }
+        }
+        None
+    }
+
     pub fn render(&mut self) -> Result<(), wgpu::SurfaceError>
     where
         F: NumAssignOps + SampleUniform + FloatCore + FloatConst
@@ -317,40 +417,17 @@ where
             label: Some("Render Encoder"),
         });
 
-        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-            label: Some("Render Pass"),
-            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                view: &output_view,
-                depth_slice: None,
-                resolve_target: None,
-                ops: wgpu::Operations {
-                    load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
-                    store: wgpu::StoreOp::Store,
-                },
-            })],
-            multiview_mask: None,
-            depth_stencil_attachment: None,
-            timestamp_writes: None,
-            occlusion_query_set: None,
-        });
-
-        self.global_bind_group.set(&mut render_pass);
-
-        render_pass.set_pipeline(&self.render_pipeline);
-        render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
-        render_pass.draw(0..self.vertex_count(), 0..1);
+        self.pass.draw(&mut encoder, &output_view);
+        if let Some(dual) = &self.dual
+        {
+            dual.draw(&mut encoder, &output.texture);
+        }
 
-        core::mem::drop(render_pass);
         self.render.queue.submit(core::iter::once(encoder.finish()));
 
         output.present();
         Ok(())
     }
-
-    fn vertex_count(&self) -> u32
-    {
-        (self.vertex_buffer.size()/core::mem::size_of::<VertexInput>() as u64) as u32
-    }
 }
 
 #[derive(Debug)]
@@ -412,8 +489,10 @@ impl Render
         let caps = surface.get_capabilities(&adapter);
         let surface_format = *caps.formats.first()
             .ok_or(anyhow::Error::msg("No supported adapter formats"))?;
-        let config = surface.get_default_config(&adapter, size.width, size.height)
+        let mut config = surface.get_default_config(&adapter, size.width, size.height)
             .ok_or(anyhow::Error::msg("No default config provided"))?;
+        // Lets the inset with the dual be copied onto the window
+        config.usage |= caps.usages & wgpu::TextureUsages::COPY_DST;
         surface.configure(&device, &config);
 
         Ok(Self { window, surface, config, device, queue, surface_format })
@@ -0,0 +1,101 @@
+use num_complex::Complex;
+use winit::dpi::{PhysicalPosition, PhysicalSize};
+
+use crate::{app::{Action, Bookmarks, Dual, Event, Recording, Session, StartView, View, ZoomDirection}, cli::parse_fractal, fractal::{Fractal, Julia, Mandelbrot}, render_replay, replay_event, settings::Settings};
+
+const SIZE: PhysicalSize<u32> = PhysicalSize::new(32, 24);
+const FPS: u32 = 10;
+
+fn view(size: PhysicalSize<u32>) -> View<f64>
+{
+    View::new(&Mandelbrot, size, StartView::default(), Settings::default())
+}
+
+#[test]
+fn replay_exports_through_resizes()
+{
+    let recording = Recording {
+        session: Session {
+            fractal: "mandelbrot".to_string(),
+            size: [SIZE.width, SIZE.height],
+            start: StartView::default(),
+            settings: Settings::default()
+        },
+        events: vec![
+            (0.0, Event::Update { dt: 0.1 }),
+            (0.05, Event::Resize { width: 64, height: 48 }),
+            (0.1, Event::Cursor { x: 48.0, y: 36.0 }),
+            (0.1, Event::Action { action: Action::ZoomAtCursor(ZoomDirection::Inwards), pressed: true }),
+            (0.2, Event::Update { dt: 0.1 }),
+            (0.3, Event::Resize { width: 16, height: 12 }),
+            (0.4, Event::Update { dt: 0.1 })
+        ]
+    };
+    let path = std::env::temp_dir().join(format!("fractal-zoom-replay-{}.y4m", std::process::id()));
+    let result = render_replay(&recording, &path, FPS, true);
+    let video = std::fs::read(&path);
+    let _ = std::fs::remove_file(&path);
+    result.unwrap();
+    let video = video.unwrap();
+
+    let header = format!("YUV4MPEG2 W{} H{} F{FPS}:1 Ip A1:1 C444\n", SIZE.width, SIZE.height);
+    assert!(video.starts_with(header.as_bytes()));
+    let frame = b"FRAME\n".len() + (SIZE.width*SIZE.height*3) as usize;
+    assert_eq!(video.len() - header.len(), frame*5);
+}
+
+#[test]
+fn replay_keeps_cursor_over_the_same_point()
+{
+    let mut replayed = view(SIZE);
+    let mut window = replayed.win_size();
+    for event in [Event::Resize { width: 64, height: 48 }, Event::Cursor { x: 50.0, y: 10.0 }]
+    {
+        event.replay(&mut replayed, &Mandelbrot, &mut window)
+    }
+    let mut live = view(PhysicalSize::new(64, 48));
+    live.update_mouse_pos(PhysicalPosition::new(50.0, 10.0));
+
+    assert_eq!(replayed.win_size(), SIZE);
+    assert_eq!(window, PhysicalSize::new(64, 48));
+    assert_eq!(replayed.cursor(), live.cursor());
+}
+
+#[test]
+fn bookmark_slots_are_per_parameter_set()
+{
+    let (clover, dendrite) = (Fractal::<f64>::arg(&Julia::clover()), Fractal::<f64>::arg(&Julia::dendrite()));
+    let mut bookmarks = Bookmarks::default();
+    bookmarks.insert(&clover, "1", view(SIZE).bookmark());
+    assert!(bookmarks.get(&dendrite, "1").is_none());
+
+    let saved: Bookmarks = toml::from_str(&toml::to_string(&bookmarks).unwrap()).unwrap();
+    assert_eq!(saved, bookmarks);
+    assert_eq!(saved.get(&clover, "1"), Some(&view(SIZE).bookmark()));
+}
+
+#[test]
+fn replay_opens_dual_at_the_picked_point()
+{
+    let start = StartView { center: Some(Complex::new(-0.5, 0.1)), shift: Some(Complex::new(0.2, 0.0)), ..StartView::default() };
+    let mut live = View::new(&Mandelbrot, SIZE, start, Settings::default());
+    live.update_mouse_pos(PhysicalPosition::new(5.0, 20.0));
+    let picked = live.cursor().unwrap();
+    let dual = Dual::at_cursor(&Mandelbrot, &live).unwrap();
+    let events = [
+        Event::Cursor { x: 5.0, y: 20.0 },
+        // As recorded by `State::with_dual`
+        Event::Fractal { fractal: dual.arg(), start: live.start().dual() }
+    ];
+
+    let mut fractal = parse_fractal("mandelbrot").unwrap();
+    let mut replayed = View::new(&fractal, SIZE, start, Settings::default());
+    let mut window = replayed.win_size();
+    for event in &events
+    {
+        replay_event(event, &mut fractal, &mut replayed, &mut window).unwrap();
+    }
+    assert_eq!(fractal.arg(), dual.arg());
+    assert_eq!(replayed.globals().shift, picked);
+    assert_eq!(replayed.globals().center, Complex::new(0.0, 0.0));
+}
@@ -0,0 +1,203 @@
+use core::ops::{Add, Mul};
+use std::path::Path;
+
+use num_complex::Complex;
+use num_traits::{Float, Zero};
+use serde::Deserialize;
+
+use crate::{MyFloat, app::{CoordControl, RotControl, View, ZoomControl}, clamp_rem, f};
+
+/// Keyframes as written in a timeline file. Anything left out of a keyframe stays as it was in the one before, and
+/// the first keyframe starts out from the start view.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TimelineFile
+{
+    #[serde(rename = "keyframe")]
+    keyframes: Vec<KeyframeFile>
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct KeyframeFile
+{
+    /// Seconds from the start of the timeline.
+    time: f64,
+    /// The point in the plane at the center of the window.
+    center: Option<Complex<f64>>,
+    shift: Option<Complex<f64>>,
+    exp: Option<Complex<f64>>,
+    zoom: Option<f64>,
+    rot: Option<f64>
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Keyframe<F>
+where
+    F: MyFloat
+{
+    pub time: F,
+    /// The point in the plane at the center of the window.
+    pub center: Complex<F>,
+    pub shift: Complex<F>,
+    pub exp: Complex<F>,
+    pub zoom: F,
+    pub rot: F
+}
+
+/// A path through the parameters of a [`View`], to be rendered frame by frame at a fixed rate.
+///
+/// Zoom is interpolated linearly in log space, rotation along the shortest way around, and center, shift and exp
+/// along Catmull-Rom splines.
+#[derive(Debug, Clone)]
+pub struct Timeline<F>
+where
+    F: MyFloat
+{
+    keyframes: Vec<Keyframe<F>>
+}
+
+impl<F> Timeline<F>
+where
+    F: MyFloat
+{
+    /// Reads a timeline file, filling in the first keyframe from where `view` is.
+    pub fn load(path: &Path, view: &View<F>) -> anyhow::Result<Self>
+    {
+        let timeline = std::fs::read_to_string(path)
+            .map_err(|error| anyhow::Error::new(error).context(format!("Failed to read {}", path.display())))?;
+        let timeline: TimelineFile = toml::from_str(&timeline)
+            .map_err(|error| anyhow::Error::new(error).context(format!("Failed to parse {}", path.display())))?;
+        Self::from_file(timeline, view)
+            .map_err(|error| error.context(format!("Invalid timeline in {}", path.display())))
+    }
+
+    fn from_file(timeline: TimelineFile, view: &View<F>) -> anyhow::Result<Self>
+    {
+        let complex = |z: Complex<f64>| Complex::new(f!(z.re), f!(z.im));
+        let globals = view.globals();
+        let mut last = Keyframe {
+            time: F::zero(),
+            center: -globals.center,
+            shift: globals.shift,
+            exp: globals.exp,
+            zoom: globals.zoom,
+            rot: globals.rot
+        };
+        let mut keyframes = Vec::with_capacity(timeline.keyframes.len());
+        for (i, keyframe) in timeline.keyframes.into_iter().enumerate()
+        {
+            let KeyframeFile { time, center, shift, exp, zoom, rot } = keyframe;
+            if !time.is_finite() || (i > 0 && f!(time) <= last.time)
+            {
+                return Err(anyhow::Error::msg(format!("Keyframe {i} has time {time}, times must be finite and increasing")))
+            }
+            if let Some(zoom) = zoom && !(zoom.is_finite() && zoom > 0.0)
+            {
+                return Err(anyhow::Error::msg(format!("Keyframe {i} has zoom {zoom}, zooms must be finite and positive")))
+            }
+            last = Keyframe {
+                time: f!(time),
+                center: center.map(complex).unwrap_or(last.center),
+                shift: shift.map(complex).unwrap_or(last.shift),
+                exp: exp.map(complex).unwrap_or(last.exp),
+                zoom: zoom.map(|zoom| f!(zoom)).unwrap_or(last.zoom),
+                rot: rot.map(|rot| f!(rot)).unwrap_or(last.rot)
+            };
+            keyframes.push(last);
+        }
+        if keyframes.is_empty()
+        {
+            return Err(anyhow::Error::msg("A timeline needs at least one keyframe"))
+        }
+        Ok(Self { keyframes })
+    }
+
+    pub fn duration(&self) -> F
+    {
+        self.keyframes.last().unwrap().time
+    }
+
+    /// The number of frames at `fps` to cover the timeline, including both ends.
+    pub fn frame_count(&self, fps: F) -> usize
+    {
+        Float::floor(self.duration()*fps).to_usize().unwrap() + 1
+    }
+
+    /// Where the view is at `time`, clamped to the ends of the timeline.
+    pub fn keyframe(&self, time: F) -> Keyframe<F>
+    {
+        let keyframes = &self.keyframes;
+        let i = keyframes.partition_point(|keyframe| keyframe.time <= time);
+        if i == 0
+        {
+            return Keyframe { time, ..keyframes[0] }
+        }
+        if i == keyframes.len()
+        {
+            return Keyframe { time, ..keyframes[i - 1] }
+        }
+        let (a, b) = (&keyframes[i - 1], &keyframes[i]);
+        let dt = b.time - a.time;
+        let s = (time - a.time)/dt;
+
+        let spline = |p: fn(&Keyframe<F>) -> Complex<F>| hermite(
+            p(a),
+            p(b),
+            self.tangent(i - 1, p)*dt,
+            self.tangent(i, p)*dt,
+            s
+        );
+        let turn = clamp_rem(b.rot - a.rot, -F::PI()..F::PI());
+        Keyframe {
+            time,
+            center: spline(|keyframe| keyframe.center),
+            shift: spline(|keyframe| keyframe.shift),
+            exp: spline(|keyframe| keyframe.exp),
+            zoom: Float::exp(Float::ln(a.zoom) + (Float::ln(b.zoom) - Float::ln(a.zoom))*s),
+            rot: a.rot + turn*s
+        }
+    }
+
+    /// Catmull-Rom tangent at keyframe `i`, one-sided at the ends.
+    fn tangent(&self, i: usize, p: fn(&Keyframe<F>) -> Complex<F>) -> Complex<F>
+    {
+        let keyframes = &self.keyframes;
+        let a = &keyframes[i.saturating_sub(1)];
+        let b = &keyframes[(i + 1).min(keyframes.len() - 1)];
+        if b.time > a.time
+        {
+            (p(b) - p(a))/(b.time - a.time)
+        }
+        else
+        {
+            Complex::zero()
+        }
+    }
+
+    /// Puts `view` where the timeline is at `time`, with `time` also driving the time uniform.
+    pub fn apply(&self, view: &mut View<F>, time: F)
+    {
+        let Keyframe { time, center, shift, exp, zoom, rot } = self.keyframe(time);
+        let atan = |z: Complex<F>| Complex::new(Float::atan(z.re), Float::atan(z.im));
+        view.center = CoordControl::from(-center);
+        view.shift = CoordControl::from(atan(shift));
+        view.exp = CoordControl::from(atan(exp));
+        view.zoom = ZoomControl::from(zoom);
+        view.rot = RotControl::from(rot);
+        view.set_time(time);
+    }
+}
+
+/// Cubic Hermite interpolation between `p0` and `p1` for `s` in `0..=1`, with tangents scaled to the segment.
+fn hermite<T, F>(p0: T, p1: T, m0: T, m1: T, s: F) -> T
+where
+    T: Copy + Add<Output = T> + Mul<F, Output = T>,
+    F: MyFloat
+{
+    let s2 = s*s;
+    let s3 = s2*s;
+    let two = f!(2.0);
+    let three = f!(3.0);
+    p0*(two*s3 - three*s2 + F::one()) + m0*(s3 - two*s2 + s) + p1*(three*s2 - two*s3) + m1*(s3 - s2)
+}
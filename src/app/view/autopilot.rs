@@ -0,0 +1,130 @@
+use num_complex::Complex;
+use num_traits::Float;
+
+use crate::{AUTOPILOT_CANDIDATES, AUTOPILOT_DETAIL, AUTOPILOT_DIVE, AUTOPILOT_PAN, AUTOPILOT_PROBE, AUTOPILOT_PROBE_SIZE, AUTOPILOT_RETARGET, AUTOPILOT_SURFACE, DONUT, MyFloat, app::{CoordControl, View, ZoomControl}, f, fractal::{Fractal, cpu::Globals}, random_donut};
+
+/// Explores on its own: dives towards the most detailed spot it can find, and once detail or precision runs out,
+/// surfaces back to the start zoom and picks another.
+#[derive(Debug, Clone, Copy)]
+pub struct Autopilot<F>
+where
+    F: MyFloat
+{
+    diving: bool,
+    /// Seconds until the target is picked again.
+    retarget: F
+}
+
+impl<F> Default for Autopilot<F>
+where
+    F: MyFloat
+{
+    fn default() -> Self
+    {
+        Self {
+            diving: true,
+            retarget: F::zero()
+        }
+    }
+}
+
+impl<F> Autopilot<F>
+where
+    F: MyFloat
+{
+    /// Moves `view` along by `dt` seconds. The target is kept under the zoom target of the view, which drifts towards
+    /// the middle of the window.
+    pub fn steer<T>(&mut self, view: &mut View<F>, fractal: &T, dt: F)
+    where
+        T: Fractal<F>
+    {
+        let start = view.start().zoom;
+        let end = f!(view.settings().zoom_range.end);
+        self.retarget -= dt;
+        if self.diving && self.retarget <= F::zero()
+        {
+            self.retarget = f!(AUTOPILOT_RETARGET);
+            let (win_center, detail) = Self::target(view, fractal);
+            // Surfaces before the next retarget would take it out of the zoom range
+            let next = *view.zoom*Float::powf(f!(AUTOPILOT_DIVE), f!(AUTOPILOT_RETARGET));
+            if (detail < f!(AUTOPILOT_DETAIL) && *view.zoom > start) || next >= end
+            {
+                self.diving = false;
+            }
+            else
+            {
+                view.win_center = win_center
+            }
+        }
+        if self.diving
+        {
+            let win_center = view.win_center*Float::exp(-f!(AUTOPILOT_PAN)*dt);
+            Self::zoom_around(view, win_center, *view.zoom*Float::powf(f!(AUTOPILOT_DIVE), dt));
+        }
+        else
+        {
+            let zoom = *view.zoom/Float::powf(f!(AUTOPILOT_SURFACE), dt);
+            if zoom <= start
+            {
+                self.diving = true;
+                self.retarget = F::zero();
+            }
+            Self::zoom_around(view, view.win_center, Float::max(zoom, start));
+        }
+    }
+
+    /// Zooms to `zoom` with the point under the zoom target moved to `win_center`.
+    fn zoom_around(view: &mut View<F>, win_center: Complex<F>, zoom: F)
+    {
+        let point = view.globals().coord(view.win_center);
+        let rot = Complex::cis(*view.rot);
+        view.win_center = win_center;
+        view.zoom = ZoomControl::from(zoom);
+        view.center = CoordControl::from(win_center/zoom*rot - point);
+    }
+
+    /// The most detailed spot out of the current target and some random ones, as a position in the window.
+    fn target<T>(view: &View<F>, fractal: &T) -> (Complex<F>, F)
+    where
+        T: Fractal<F>
+    {
+        let globals = view.globals();
+        let size = view.win_size();
+        let radius = f!(size.width.min(size.height))/f!(4.0);
+        let candidates = core::iter::once(view.win_center)
+            .chain((0..AUTOPILOT_CANDIDATES).map(|_| random_donut(f!(DONUT.start)..f!(DONUT.end))*radius));
+        candidates.map(|win_center| (win_center, Self::detail(&globals, fractal, win_center)))
+            .fold((view.win_center, <F as Float>::neg_infinity()), |best, candidate| if candidate.1 > best.1 { candidate } else { best })
+    }
+
+    /// The variance of escape counts in a small grid around `pos`, or of brightness for fractals that don't escape.
+    fn detail<T>(globals: &Globals<F>, fractal: &T, pos: Complex<F>) -> F
+    where
+        T: Fractal<F>
+    {
+        let step = f!(AUTOPILOT_PROBE_SIZE/(AUTOPILOT_PROBE - 1) as f64);
+        let half = f!(AUTOPILOT_PROBE_SIZE/2.0);
+        let samples: Vec<F> = (0..AUTOPILOT_PROBE)
+            .flat_map(|i| (0..AUTOPILOT_PROBE).map(move |j| (i, j)))
+            .map(|(i, j)| {
+                let pos = pos + Complex::new(f!(i)*step - half, f!(j)*step - half);
+                match fractal.escape_count(globals, pos)
+                {
+                    Some(i) => f!(i),
+                    None => {
+                        let [r, g, b, a] = fractal.fs_main(globals, pos);
+                        (r + g + b)*a
+                    }
+                }
+            })
+            .filter(|x| Float::is_finite(*x))
+            .collect();
+        if samples.is_empty()
+        {
+            return F::zero()
+        }
+        let n = f!(samples.len());
+        let mean = samples.iter().copied().fold(F::zero(), |sum, x| sum + x)/n;
+        samples.iter().fold(F::zero(), |sum, &x| sum + (x - mean)*(x - mean))/n
+    }
+}
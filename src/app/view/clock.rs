@@ -0,0 +1,125 @@
+use core::fmt::Debug;
+use std::time::Instant;
+
+/// Where a [`View`](crate::app::View) gets its time steps from.
+pub trait Clock: Debug
+{
+    /// Seconds since the last tick.
+    fn tick(&mut self) -> f64;
+}
+
+impl<C> Clock for Box<C>
+where
+    C: Clock + ?Sized
+{
+    fn tick(&mut self) -> f64
+    {
+        (**self).tick()
+    }
+}
+
+/// The wall clock. It's monotonic, so it never steps backwards when the system time is adjusted.
+#[derive(Debug, Clone, Copy)]
+pub struct RealClock
+{
+    t: Instant
+}
+
+impl Default for RealClock
+{
+    fn default() -> Self
+    {
+        Self {
+            t: Instant::now()
+        }
+    }
+}
+
+impl Clock for RealClock
+{
+    fn tick(&mut self) -> f64
+    {
+        let t = Instant::now();
+        t.duration_since(core::mem::replace(&mut self.t, t)).as_secs_f64()
+    }
+}
+
+/// Steps by the same amount every tick, for deterministic runs.
+#[derive(Debug, Clone, Copy)]
+pub struct FixedClock
+{
+    pub dt: f64
+}
+
+impl Clock for FixedClock
+{
+    fn tick(&mut self) -> f64
+    {
+        self.dt
+    }
+}
+
+/// Runs another clock faster or slower, or not at all while paused.
+///
+/// The other clock keeps ticking while paused, so resuming doesn't make up for the time lost.
+#[derive(Debug, Clone, Copy)]
+pub struct ScaledClock<C>
+where
+    C: Clock
+{
+    clock: C,
+    scale: f64,
+    paused: bool
+}
+
+impl<C> ScaledClock<C>
+where
+    C: Clock
+{
+    pub fn new(clock: C, scale: f64) -> Self
+    {
+        Self {
+            clock,
+            scale,
+            paused: false
+        }
+    }
+
+    pub fn scale(&self) -> f64
+    {
+        self.scale
+    }
+
+    pub fn set_scale(&mut self, scale: f64)
+    {
+        self.scale = scale
+    }
+
+    pub fn is_paused(&self) -> bool
+    {
+        self.paused
+    }
+
+    pub fn toggle_pause(&mut self)
+    {
+        self.paused = !self.paused
+    }
+}
+
+impl<C> Clock for ScaledClock<C>
+where
+    C: Clock
+{
+    fn tick(&mut self) -> f64
+    {
+        let dt = self.clock.tick();
+        if self.paused
+        {
+            0.0
+        }
+        else
+        {
+            dt*self.scale
+        }
+    }
+}
@@ -1,18 +1,15 @@
 use core::ops::{Deref, DerefMut};
-use std::time::SystemTime;
-
 use num_complex::Complex;
 use num_traits::{Float, Zero};
 use winit::event::ElementState;
 
-use crate::{MyFloat, app::{MoveDirection, RotateDirection}, f};
+use crate::{MyFloat, app::{MoveDirection, RotateDirection}, f, settings::Settings};
 
 #[derive(Debug, Clone, Copy)]
 pub struct CoordControl<F>
 where
     F: MyFloat
 {
-    t: SystemTime,
     pos: Complex<F>,
     vel: Complex<F>,
     acc: Complex<F>,
@@ -59,7 +56,6 @@ where
     fn default() -> Self
     {
         Self {
-            t: SystemTime::now(),
             pos: Zero::zero(),
             vel: Zero::zero(),
             acc: Zero::zero(),
@@ -82,7 +78,12 @@ where
         }
     }
 
-    pub fn update(&mut self, speed: Complex<F>, rot_speed: F, reverse: bool) -> anyhow::Result<()>
+    pub fn vel(&self) -> Complex<F>
+    {
+        self.vel
+    }
+
+    pub fn update(&mut self, dt: F, speed: Complex<F>, settings: &Settings, reverse: bool)
     {
         fn rot270<F>(z: Complex<F>) -> Complex<F>
         where
@@ -97,8 +98,6 @@ where
             z
         }
 
-        let t0 = core::mem::replace(&mut self.t, SystemTime::now());
-        let dt = f!(self.t.duration_since(t0)?.as_secs_f64());
         self.vel += core::mem::replace(&mut self.acc, Complex::zero())*dt;
         match reverse
         {
@@ -122,12 +121,10 @@ where
         {
             self.pos *= Complex::cis(match rot
             {
-                true => rot_speed*dt,
-                false => -rot_speed*dt
+                true => f!(settings.rot_speed)*dt,
+                false => -f!(settings.rot_speed)*dt
             })
         }
-
-        Ok(())
     }
 
     pub fn rot(&mut self, direction: RotateDirection, button_state: ElementState)
@@ -175,12 +172,12 @@ where
 
     pub fn stop(&mut self)
     {
-        let Self { t, pos, mov, rot, .. } = *self;
-        *self = Self { t, pos, mov, rot, ..Default::default() };
+        let Self { pos, mov, rot, .. } = *self;
+        *self = Self { pos, mov, rot, ..Default::default() };
     }
     pub fn reset(&mut self)
     {
-        let Self { t, mov, rot, .. } = *self;
-        *self = Self { t, mov, rot, ..Default::default() };
+        let Self { mov, rot, .. } = *self;
+        *self = Self { mov, rot, ..Default::default() };
     }
 }
\ No newline at end of file
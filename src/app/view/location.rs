@@ -0,0 +1,144 @@
+use core::{fmt::{self, Display}, str::FromStr};
+
+use num_complex::Complex;
+use num_traits::Float;
+
+use crate::{MyFloat, app::StartView};
+
+const KEYS: [&str; 5] = ["center", "shift", "exp", "zoom", "rot"];
+
+/// A view as a single line of text that can be shared, e.g. `julia:c=-0.8+0.156i;center=0.1-0.2i;shift=-0.8+0.156i;exp=2+0i;zoom=1000;rot=0.5`.
+///
+/// The fractal comes first, as it's given on the command line. Numbers are written so they parse back to the same bits.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Location<F>
+where
+    F: MyFloat
+{
+    /// `<label>[:<param>=<value>,...]`
+    pub fractal: String,
+    /// The point in the plane at the center of the window.
+    pub center: Complex<F>,
+    pub shift: Complex<F>,
+    pub exp: Complex<F>,
+    pub zoom: F,
+    pub rot: F
+}
+
+impl<F> Location<F>
+where
+    F: MyFloat
+{
+    pub fn start(&self, max_iterations: Option<u32>) -> StartView<F>
+    {
+        StartView {
+            zoom: self.zoom,
+            max_iterations,
+            center: Some(self.center),
+            shift: Some(self.shift),
+            exp: Some(self.exp),
+            rot: self.rot
+        }
+    }
+}
+
+impl<F> Display for Location<F>
+where
+    F: MyFloat
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        let Self { fractal, center, shift, exp, zoom, rot } = self;
+        let (center, shift, exp) = (Signed(*center), Signed(*shift), Signed(*exp));
+        write!(f, "{fractal};center={center};shift={shift};exp={exp};zoom={zoom};rot={rot}")
+    }
+}
+
+impl<F> FromStr for Location<F>
+where
+    F: MyFloat
+{
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err>
+    {
+        let mut fields = s.trim().split(';');
+        let fractal = fields.next()
+            .filter(|fractal| !fractal.is_empty())
+            .ok_or(anyhow::Error::msg("Missing fractal in location"))?;
+        let fields = fields.map(|field| field.split_once('=')
+                .ok_or(anyhow::Error::msg(format!("Expected <key>=<value> in location, got '{field}'")))
+            )
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        if let Some((key, _)) = fields.iter().find(|(key, _)| !KEYS.contains(key))
+        {
+            return Err(anyhow::Error::msg(format!("Unknown key '{key}' in location, expected {}", KEYS.join(", "))))
+        }
+
+        let value = |key: &str| {
+            let mut values = fields.iter().filter(|&&(other, _)| other == key);
+            match (values.next(), values.next())
+            {
+                (Some(&(_, value)), None) => Ok(value),
+                (None, _) => Err(anyhow::Error::msg(format!("Missing {key} in location"))),
+                (Some(_), Some(_)) => Err(anyhow::Error::msg(format!("More than one {key} in location")))
+            }
+        };
+        let real = |key: &str| {
+            let value = value(key)?;
+            F::from_str_radix(value, 10)
+                .map_err(|_| anyhow::Error::msg(format!("Invalid number '{value}' for {key} in location")))
+        };
+        let complex = |key: &str| {
+            let value = value(key)?;
+            parse_complex(value)
+                .ok_or(anyhow::Error::msg(format!("Invalid complex number '{value}' for {key} in location")))
+        };
+
+        let location = Self {
+            fractal: fractal.to_string(),
+            center: complex("center")?,
+            shift: complex("shift")?,
+            exp: complex("exp")?,
+            zoom: real("zoom")?,
+            rot: real("rot")?
+        };
+        if !Float::is_finite(location.zoom) || location.zoom.is_zero()
+        {
+            return Err(anyhow::Error::msg(format!("Zoom must be finite and non-zero in location, was {}", location.zoom)))
+        }
+        Ok(location)
+    }
+}
+
+/// Writes a complex number as `<re>±<im>i`, with the sign of the imaginary part even if it's zero, which [`Complex`]'s
+/// own `Display` doesn't keep in a way that parses back.
+struct Signed<F>(Complex<F>);
+
+impl<F> Display for Signed<F>
+where
+    F: MyFloat
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        let Complex { re, im } = self.0;
+        let sign = if Float::is_sign_negative(im) { '-' } else { '+' };
+        write!(f, "{re}{sign}{}i", Float::abs(im))
+    }
+}
+
+/// Parses `<re>±<im>i` as written by [`Signed`], keeping the signs of zeros, or `<re>+-<im>i` as [`Complex`] writes it.
+fn parse_complex<F>(s: &str) -> Option<Complex<F>>
+where
+    F: MyFloat
+{
+    let real = |s: &str| F::from_str_radix(s.strip_prefix('+').unwrap_or(s), 10).ok();
+    let im = s.strip_suffix('i')?;
+    // The imaginary part starts at the first sign that isn't leading or part of an exponent
+    let split = im.char_indices()
+        .skip(1)
+        .find(|&(i, c)| matches!(c, '+' | '-') && !im[..i].ends_with(['e', 'E']))
+        .map(|(i, _)| i)?;
+    let (re, im) = im.split_at(split);
+    Some(Complex::new(real(re)?, real(im)?))
+}
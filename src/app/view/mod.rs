@@ -1,22 +1,27 @@
-use std::time::SystemTime;
-
 use num_complex::Complex;
 use num_traits::{Float, Zero};
+use serde::{Deserialize, Serialize};
 use winit::{dpi::{PhysicalPosition, PhysicalSize}, event::ElementState};
 
-use crate::{EXP_ZOOM_VARIANCE, MAX_ITERATIONS, MOVE_CENTER_SPEED, MOVE_EXP_SPEED, MOVE_SHIFT_SPEED, MyFloat, ROT_SPEED, SHIFT_ZOOM_VARIANCE, START_ZOOM, ZOOM_MUL, f, fractal::{Fractal, GlobalUniforms}};
+use crate::{MyFloat, START_ZOOM, TIME_SCALE_RANGE, TIME_SCALE_STEP, app::{Action, Bookmark, Velocity}, f, fractal::{Fractal, GlobalUniforms, Located, Target, cpu::Globals, locate}, settings::Settings};
 
 moddef::moddef!(
     flat(pub) mod {
+        autopilot,
+        clock,
         coord_control,
         rot_control,
         zoom_control,
+        location,
         move_direction,
         rotate_direction,
         zoom_direction,
     }
 );
 
+#[cfg(test)]
+mod tests;
+
 #[derive(Debug, Clone, Copy)]
 pub struct InitView<F>
 where
@@ -28,6 +33,85 @@ where
     pub exp: Complex<F>
 }
 
+/// How a fractal starts out, overriding parts of what it asks for in [`Fractal::init_view`].
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StartView<F>
+where
+    F: MyFloat
+{
+    pub zoom: F,
+    /// Overrides [`Settings::max_iterations`].
+    pub max_iterations: Option<u32>,
+    /// The point in the plane at the center of the window.
+    pub center: Option<Complex<F>>,
+    pub shift: Option<Complex<F>>,
+    pub exp: Option<Complex<F>>,
+    pub rot: F
+}
+
+impl<F> Default for StartView<F>
+where
+    F: MyFloat
+{
+    fn default() -> Self
+    {
+        Self {
+            zoom: f!(START_ZOOM),
+            max_iterations: None,
+            center: None,
+            shift: None,
+            exp: None,
+            rot: F::zero()
+        }
+    }
+}
+
+impl<F> StartView<F>
+where
+    F: MyFloat
+{
+    /// The start for the dual of a point, which keeps the zoom, rotation and iterations but leaves the point to the
+    /// dual itself.
+    pub fn dual(&self) -> Self
+    {
+        Self {
+            center: None,
+            shift: None,
+            exp: None,
+            ..*self
+        }
+    }
+
+    /// The same start in another float type.
+    pub fn cast<G>(&self) -> StartView<G>
+    where
+        G: MyFloat
+    {
+        let real = |x: F| <G as num_traits::NumCast>::from(x).unwrap();
+        let complex = |z: Complex<F>| Complex::new(real(z.re), real(z.im));
+        StartView {
+            zoom: real(self.zoom),
+            max_iterations: self.max_iterations,
+            center: self.center.map(complex),
+            shift: self.shift.map(complex),
+            exp: self.exp.map(complex),
+            rot: real(self.rot)
+        }
+    }
+
+    pub fn apply(&self, init_view: InitView<F>) -> InitView<F>
+    {
+        let InitView { win_center, center, shift, exp } = init_view;
+        InitView {
+            win_center,
+            center: self.center.map(|center| -center).unwrap_or(center),
+            shift: self.shift.unwrap_or(shift),
+            exp: self.exp.unwrap_or(exp)
+        }
+    }
+}
+
 impl<F> Default for InitView<F>
 where
     F: MyFloat
@@ -43,7 +127,7 @@ where
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug)]
 pub struct View<F>
 where
     F: MyFloat
@@ -54,49 +138,80 @@ where
     pub center: CoordControl<F>,
     pub shift: CoordControl<F>,
     pub exp: CoordControl<F>,
+    /// Where the orbit trap is, see [`Trap`](crate::fractal::Trap).
+    pub trap: CoordControl<F>,
     pub zoom: ZoomControl<F>,
     pub rot: RotControl<F>,
     pub reverse: bool,
-    t0: SystemTime
+    /// The shift and exp as they were last set, since [`View::shift`] and [`View::exp`] hold their arctangents, whose
+    /// tangents can be off by a bit.
+    exact: [Complex<F>; 2],
+    start: StartView<F>,
+    settings: Settings,
+    /// Where updates get their time steps from.
+    clock: ScaledClock<Box<dyn Clock>>,
+    /// Seconds of updates since the start or the last [`Action::ResetTime`], driving the time uniform.
+    time: F
 }
 
 impl<F> View<F>
 where
     F: MyFloat
 {
-    pub fn new<T>(fractal: &T, win_size: PhysicalSize<u32>) -> Self
+    pub fn new<T>(fractal: &T, win_size: PhysicalSize<u32>, start: StartView<F>, settings: Settings) -> Self
     where
         T: Fractal<F>
     {
-        let zoom = f!(START_ZOOM);
-        let InitView { win_center, center, shift, exp } = fractal.init_view(zoom, win_size);
+        Self::from_init_view(start.apply(fractal.init_view(start.zoom, win_size)), start, settings, start.rot, win_size)
+    }
+
+    pub fn from_init_view(init_view: InitView<F>, start: StartView<F>, settings: Settings, rot: F, win_size: PhysicalSize<u32>) -> Self
+    {
+        let InitView { win_center, center, shift, exp } = init_view;
         Self {
             mouse_pos: None,
             win_center,
             win_size,
             center: CoordControl::from(center),
-            shift: CoordControl::from(Complex::new(Float::atan(shift.re), Float::atan(shift.im))),
-            exp: CoordControl::from(Complex::new(Float::atan(exp.re), Float::atan(exp.im))),
-            zoom: ZoomControl::from(zoom),
-            rot: RotControl::default(),
+            shift: CoordControl::from(atan(shift)),
+            exp: CoordControl::from(atan(exp)),
+            trap: CoordControl::default(),
+            zoom: ZoomControl::from(start.zoom),
+            rot: RotControl::from(rot),
             reverse: false,
-            t0: SystemTime::now()
+            exact: [shift, exp],
+            start,
+            settings,
+            clock: ScaledClock::new(Box::new(RealClock::default()), 1.0),
+            time: F::zero()
         }
     }
 
     pub fn uniforms(&self) -> GlobalUniforms
     {
-        GlobalUniforms {
-            time: SystemTime::now().duration_since(self.t0).unwrap().as_secs_f32(),
-            _pad_time: [0; _],
-            max_iterations: MAX_ITERATIONS,
-            _pad_max_iterations: [0; _],
-            window_size: glam::uvec2(self.win_size.width, self.win_size.height),
-            center: glam::vec2(self.center.re.to_f32().unwrap(), self.center.im.to_f32().unwrap()),
-            zoom: self.zoom.to_f32().unwrap(),
-            rot: self.rot.to_f32().unwrap(),
-            exp: glam::vec2(Float::tan(self.exp.re).to_f32().unwrap(), Float::tan(self.exp.im).to_f32().unwrap()),
-            shift: glam::vec2(Float::tan(self.shift.re).to_f32().unwrap(), Float::tan(self.shift.im).to_f32().unwrap())
+        self.globals().into()
+    }
+
+    pub fn globals(&self) -> Globals<F>
+    {
+        let (colormap, palette) = self.settings.palette.pick();
+        Globals {
+            time: self.time,
+            window_size: self.win_size,
+            max_iterations: self.start.max_iterations.unwrap_or(self.settings.max_iterations),
+            center: *self.center,
+            zoom: *self.zoom,
+            rot: *self.rot,
+            exp: untan(*self.exp, self.exact[1]),
+            shift: untan(*self.shift, self.exact[0]),
+            colormap,
+            palette_offset: f!(self.settings.palette.offset),
+            palette_speed: f!(self.settings.palette.speed),
+            palette,
+            distance: self.settings.palette.distance as u32,
+            trap: self.settings.palette.trap as u32,
+            trap_pos: *self.trap,
+            bailout: f!(self.settings.shader.bailout())
         }
     }
     
@@ -117,6 +232,12 @@ where
         }
     }
 
+    /// The point in the plane under the cursor.
+    pub fn cursor(&self) -> Option<Complex<F>>
+    {
+        self.mouse_pos.map(|pos| self.globals().coord(pos))
+    }
+
     pub fn recenter(&mut self)
     {
         if let Some(mouse_pos) = self.mouse_pos
@@ -125,6 +246,14 @@ where
         }
     }
 
+
+    /// Vertical scrolling accelerates the zoom, horizontal scrolling brakes it.
+    pub fn scroll(&mut self, accel: F, brk: F)
+    {
+        self.zoom.push(Some((ZoomDirection::Inwards, accel)));
+        self.zoom.brk(brk)
+    }
+
     pub fn resize(&mut self, win_size: PhysicalSize<u32>)
     {
         self.win_size = win_size
@@ -139,22 +268,132 @@ where
         }
     }
 
+    /// Applies the parts of an [`Action`] that only concern the view, the rest is up to the frontend.
+    pub fn act<T>(&mut self, action: Action, button_state: ElementState, fractal: &T)
+    where
+        T: Fractal<F>
+    {
+        match action
+        {
+            Action::MoveCenter(direction) => self.center.mov(direction, button_state),
+            Action::MoveExp(direction) => self.exp.mov(direction, button_state),
+            Action::MoveShift(direction) => self.shift.mov(direction, button_state),
+            Action::MoveTrap(direction) => self.trap.mov(direction, button_state),
+            Action::Zoom(direction) => self.zoom.mov(direction, button_state),
+
+            Action::Rotate(direction) => self.rot.rot(direction, button_state),
+            Action::RotateCenter(direction) => self.center.rot(direction, button_state),
+            Action::RotateShift(direction) => self.shift.rot(direction, button_state),
+            Action::RotateExp(direction) => self.exp.rot(direction, button_state),
+            Action::RotateTrap(direction) => self.trap.rot(direction, button_state),
+
+            Action::AccelCenter(direction) => self.center.push(direction.map(|dir| (dir, f!(self.settings.move_center_accel)))),
+            Action::AccelShift(direction) => self.shift.push(direction.map(|dir| (dir, f!(self.settings.move_shift_accel)))),
+            Action::AccelExp(direction) => self.exp.push(direction.map(|dir| (dir, f!(self.settings.move_exp_accel)))),
+            Action::AccelTrap(direction) => self.trap.push(direction.map(|dir| (dir, f!(self.settings.move_shift_accel)))),
+            Action::AccelRotate(direction) => self.rot.push(direction.map(|dir| (dir, f!(self.settings.rot_accel)))),
+            Action::AccelZoom(direction) => self.zoom.push(direction.map(|dir| (dir, f!(self.settings.move_zoom_accel)))),
+
+            Action::ZoomAtCursor(direction) => {
+                self.recenter();
+                self.reverse = !direction.forward()
+            },
+            Action::StopZoom => self.zoom.stop(),
+
+            Action::Reverse => self.reverse(button_state),
+            Action::Reset => self.reset(fractal),
+            Action::ResetTime => self.reset_time(),
+            Action::Pause => if button_state.is_pressed()
+            {
+                self.clock.toggle_pause()
+            },
+            Action::SlowDown => if button_state.is_pressed()
+            {
+                self.scale_time(f64::recip(TIME_SCALE_STEP))
+            },
+            Action::SpeedUp => if button_state.is_pressed()
+            {
+                self.scale_time(TIME_SCALE_STEP)
+            },
+            Action::ResetView => self.reset_view(fractal),
+            Action::NextColormap => if button_state.is_pressed()
+            {
+                self.settings.palette.next_colormap()
+            },
+            // Only where it shows, rather than changing a setting nothing is drawn with
+            Action::NextDistance => if button_state.is_pressed() && fractal.distance_estimate()
+            {
+                self.settings.palette.distance = self.settings.palette.distance.next()
+            },
+            Action::NextTrap => if button_state.is_pressed()
+            {
+                self.settings.palette.trap = self.settings.palette.trap.next()
+            },
+
+            Action::Stop
+            | Action::ReloadSettings
+            | Action::SaveBookmark(_)
+            | Action::GotoBookmark(_)
+            | Action::Locate(_)
+            | Action::Idle
+            | Action::PrintLocation
+            | Action::Autopilot
+            | Action::Dual
+            | Action::OpenDual
+            | Action::Exit
+            | Action::Fullscreen
+            | Action::NextFractal => ()
+        }
+    }
+
     pub fn reset<T>(&mut self, fractal: &T)
     where
         T: Fractal<F>
     {
-        *self = View::new(fractal, self.win_size)
+        let mut view = View::new(fractal, self.win_size, self.start, self.settings.clone());
+        view.take_clock(self);
+        *self = view
     }
     pub fn reset_time(&mut self)
     {
-        self.t0 = SystemTime::now();
+        self.time = F::zero();
+    }
+
+    pub fn set_time(&mut self, time: F)
+    {
+        self.time = time
+    }
+
+    /// Takes time steps from `clock` from now on, keeping the speed and whether time is paused.
+    pub fn set_clock<C>(&mut self, clock: C)
+    where
+        C: Clock + 'static
+    {
+        let mut scaled = ScaledClock::new(Box::new(clock) as Box<dyn Clock>, self.clock.scale());
+        if self.clock.is_paused()
+        {
+            scaled.toggle_pause()
+        }
+        self.clock = scaled
+    }
+
+    /// Takes over the clock of `other`, so moving on to a new view keeps time paused or sped up.
+    pub fn take_clock(&mut self, other: &mut Self)
+    {
+        core::mem::swap(&mut self.clock, &mut other.clock)
+    }
+
+    fn scale_time(&mut self, factor: f64)
+    {
+        self.clock.set_scale((self.clock.scale()*factor).clamp(*TIME_SCALE_RANGE.start(), *TIME_SCALE_RANGE.end()))
     }
 
     pub fn reset_view<T>(&mut self, fractal: &T)
     where
         T: Fractal<F>
     {
-        let Self { mouse_pos: _, win_size, win_center, center, shift: _, exp: _, zoom, rot, reverse, t0: _ } = View::new(fractal, self.win_size);
+        let Self { mouse_pos: _, win_size, win_center, center, shift: _, exp: _, trap: _, zoom, rot, reverse, exact: _, start, settings, mut clock, time: _ } = View::new(fractal, self.win_size, self.start, self.settings.clone());
+        core::mem::swap(&mut clock, &mut self.clock);
 
         *self = Self {
             mouse_pos: self.mouse_pos,
@@ -163,26 +402,155 @@ where
             center,
             shift: self.shift,
             exp: self.exp,
+            trap: self.trap,
             zoom,
             rot,
             reverse,
-            t0: self.t0
+            exact: self.exact,
+            start,
+            settings,
+            clock,
+            time: self.time
         }
     }
     
+    pub fn location<T>(&self, fractal: &T) -> Location<F>
+    where
+        T: Fractal<F>
+    {
+        let Globals { center, zoom, rot, exp, shift, .. } = self.globals();
+        Location {
+            fractal: fractal.arg(),
+            center: -center,
+            shift,
+            exp,
+            zoom,
+            rot
+        }
+    }
+
+    /// Where the view is right now, and how it's moving.
+    pub fn bookmark(&self) -> Bookmark
+    {
+        let real = |x: F| x.to_f64().unwrap();
+        let complex = |z: Complex<F>| Complex::new(real(z.re), real(z.im));
+        let Globals { center, zoom, rot, exp, shift, .. } = self.globals();
+        Bookmark {
+            center: complex(center),
+            shift: complex(shift),
+            exp: complex(exp),
+            zoom: real(zoom),
+            rot: real(rot),
+            win_center: complex(self.win_center),
+            velocity: Some(Velocity {
+                center: complex(self.center.vel()),
+                shift: complex(self.shift.vel()),
+                exp: complex(self.exp.vel()),
+                zoom: real(self.zoom.vel()),
+                rot: real(self.rot.vel())
+            })
+        }
+    }
+
+    /// Jumps to a bookmarked view, standing still unless it has velocities.
+    pub fn goto(&mut self, bookmark: &Bookmark)
+    {
+        let Bookmark { center, shift, exp, zoom, rot, win_center, velocity } = *bookmark;
+        let complex = |z: Complex<f64>| Complex::new(f!(z.re), f!(z.im));
+        let velocity = velocity.unwrap_or(Velocity {
+            center: Complex::zero(),
+            shift: Complex::zero(),
+            exp: Complex::zero(),
+            zoom: 1.0,
+            rot: 0.0
+        });
+        self.win_center = complex(win_center);
+        self.center = CoordControl::new(complex(center), complex(velocity.center));
+        self.shift = CoordControl::new(atan(complex(shift)), complex(velocity.shift));
+        self.exp = CoordControl::new(atan(complex(exp)), complex(velocity.exp));
+        self.exact = [complex(shift), complex(exp)];
+        self.zoom = ZoomControl::new(f!(zoom), f!(velocity.zoom));
+        self.rot = RotControl::new(f!(rot), f!(velocity.rot));
+    }
+
+    /// Snaps the center to the nucleus or Misiurewicz point nearest the cursor, or the zoom target without a cursor.
+    pub fn locate<T>(&mut self, fractal: &T, target: Target) -> anyhow::Result<Located<F>>
+    where
+        T: Fractal<F>
+    {
+        let globals = self.globals();
+        let pos = self.mouse_pos.unwrap_or(self.win_center);
+        let located = locate(fractal, &globals, globals.coord(pos), target)?;
+        self.center = CoordControl::from(-located.point);
+        self.win_center = Complex::zero();
+        Ok(located)
+    }
+
     pub fn win_size(&self) -> PhysicalSize<u32>
     {
         self.win_size
     }
 
-    pub fn update(&mut self) -> anyhow::Result<()>
+    pub fn start(&self) -> StartView<F>
+    {
+        self.start
+    }
+
+    pub fn settings(&self) -> &Settings
+    {
+        &self.settings
+    }
+
+    pub fn set_settings(&mut self, settings: Settings)
+    {
+        self.settings = settings
+    }
+
+    /// Steps by the time since the last update on the view's clock, returning the step in seconds.
+    pub fn update(&mut self) -> f64
+    {
+        let dt = self.clock.tick();
+        self.step(f!(dt));
+        dt
+    }
+
+    /// Moves everything along by `dt` seconds.
+    pub fn step(&mut self, dt: F)
     {
         let speed = Float::signum(*self.zoom)*Float::min(Float::recip(Float::abs(*self.zoom)), F::from(START_ZOOM.recip()).unwrap());
-        self.center.update(Complex::from_polar(f!(MOVE_CENTER_SPEED)*speed, *self.rot), f!(ROT_SPEED), self.reverse)?;
-        self.shift.update(Complex::from(f!(MOVE_SHIFT_SPEED))*Float::powf(speed, f!(SHIFT_ZOOM_VARIANCE)), f!(ROT_SPEED), self.reverse)?;
-        self.exp.update(Complex::from(f!(MOVE_EXP_SPEED))*Float::powf(speed, f!(EXP_ZOOM_VARIANCE)), f!(ROT_SPEED), self.reverse)?;
-        self.zoom.update(f!(ZOOM_MUL), self.reverse, &mut self.center, self.win_center, *self.rot)?;
-        self.rot.update(f!(ROT_SPEED), self.reverse, *self.center, &mut self.win_center, *self.zoom)?;
-        Ok(())
-    }
-}
\ No newline at end of file
+        let settings = &self.settings;
+        self.center.update(dt, Complex::from_polar(f!(settings.move_center_speed)*speed, *self.rot), settings, self.reverse);
+        self.shift.update(dt, Complex::from(f!(settings.move_shift_speed))*Float::powf(speed, f!(settings.shift_zoom_variance)), settings, self.reverse);
+        self.exp.update(dt, Complex::from(f!(settings.move_exp_speed))*Float::powf(speed, f!(settings.exp_zoom_variance)), settings, self.reverse);
+        // The trap moves like the shift, being in the same plane as the orbits
+        self.trap.update(dt, Complex::from(f!(settings.move_shift_speed))*Float::powf(speed, f!(settings.shift_zoom_variance)), settings, self.reverse);
+        self.zoom.update(dt, settings, self.reverse, &mut self.center, self.win_center, *self.rot);
+        self.rot.update(dt, settings, self.reverse, *self.center, &mut self.win_center, *self.zoom);
+        self.time += dt;
+    }
+}
+
+/// The arctangent of each part, which is what shift and exp move in.
+fn atan<F>(z: Complex<F>) -> Complex<F>
+where
+    F: MyFloat
+{
+    Complex::new(Float::atan(z.re), Float::atan(z.im))
+}
+
+/// The tangent of each part of `angle`, taking the part of `exact` instead wherever `angle` is still its arctangent, so
+/// a shift or exp comes back out with the same bits it went in with.
+fn untan<F>(angle: Complex<F>, exact: Complex<F>) -> Complex<F>
+where
+    F: MyFloat
+{
+    let untan = |angle: F, exact: F| if Float::atan(exact) == angle && Float::is_sign_negative(exact) == Float::is_sign_negative(angle)
+    {
+        exact
+    }
+    else
+    {
+        Float::tan(angle)
+    };
+    Complex::new(untan(angle.re, exact.re), untan(angle.im, exact.im))
+}
@@ -1,18 +1,15 @@
 use core::ops::Deref;
-use std::time::SystemTime;
-
 use num_complex::Complex;
 use num_traits::Zero;
 use winit::event::ElementState;
 
-use crate::{MyFloat, app::{CoordControl, RotateDirection}, f};
+use crate::{MyFloat, app::{CoordControl, RotateDirection}, f, settings::Settings};
 
 #[derive(Debug, Clone, Copy)]
 pub struct RotControl<F>
 where
     F: MyFloat
 {
-    t: SystemTime,
     pos: F,
     vel: F,
     acc: F,
@@ -49,7 +46,6 @@ where
     fn default() -> Self
     {
         Self {
-            t: SystemTime::now(),
             pos: Zero::zero(),
             vel: Zero::zero(),
             acc: Zero::zero(),
@@ -71,10 +67,14 @@ where
         }
     }
 
-    pub fn update(&mut self, speed: F, reverse: bool, center: Complex<F>, win_center: &mut Complex<F>, zoom: F) -> anyhow::Result<()>
+    pub fn vel(&self) -> F
     {
-        let t0 = core::mem::replace(&mut self.t, SystemTime::now());
-        let dt = f!(self.t.duration_since(t0)?.as_secs_f64());
+        self.vel
+    }
+
+    pub fn update(&mut self, dt: F, settings: &Settings, reverse: bool, center: Complex<F>, win_center: &mut Complex<F>, zoom: F)
+    {
+        let speed = f!(settings.rot_speed);
         self.vel += core::mem::replace(&mut self.acc, F::zero())*dt;
         let pos0 = self.pos;
         match reverse
@@ -91,8 +91,6 @@ where
             }
         }
         *win_center = *win_center*Complex::cis(pos0 - self.pos);
-
-        Ok(())
     }
 
     pub fn rot(&mut self, direction: RotateDirection, button_state: ElementState)
@@ -119,12 +117,12 @@ where
 
     pub fn stop(&mut self)
     {
-        let Self { t, pos, mov, .. } = *self;
-        *self = Self { t, pos, mov, ..Default::default() };
+        let Self { pos, mov, .. } = *self;
+        *self = Self { pos, mov, ..Default::default() };
     }
     pub fn reset(&mut self)
     {
-        let Self { t, mov, .. } = *self;
-        *self = Self { t, mov, ..Default::default() };
+        let Self { mov, .. } = *self;
+        *self = Self { mov, ..Default::default() };
     }
 }
\ No newline at end of file
@@ -0,0 +1,277 @@
+use num_complex::Complex;
+use winit::{dpi::{PhysicalPosition, PhysicalSize}, event::ElementState};
+
+use crate::{app::{Action, FixedClock, Location, MoveDirection, RotateDirection, StartView, View, ZoomDirection}, fractal::{Distance, Fractal, Julia, Mandelbrot, SupergoldenMandelbrot}, settings::Settings};
+
+const DT: f64 = 1.0/60.0;
+const SIZE: PhysicalSize<u32> = PhysicalSize::new(800, 600);
+
+fn view() -> View<f64>
+{
+    let mut view = View::new(&Mandelbrot, SIZE, StartView::default(), Settings::default());
+    view.set_clock(FixedClock { dt: DT });
+    view
+}
+
+/// The point in the plane under the zoom target.
+fn target(view: &View<f64>) -> Complex<f64>
+{
+    view.globals().coord(view.win_center)
+}
+
+fn press(view: &mut View<f64>, action: Action)
+{
+    view.act(action, ElementState::Pressed, &Mandelbrot)
+}
+
+fn assert_close(a: Complex<f64>, b: Complex<f64>, scale: f64)
+{
+    assert!((a - b).norm() <= 1e-9*scale, "{a} != {b}");
+}
+
+#[test]
+fn zoom_keeps_target_fixed()
+{
+    let mut view = view();
+    view.update_mouse_pos(PhysicalPosition::new(600.0, 100.0));
+    press(&mut view, Action::ZoomAtCursor(ZoomDirection::Inwards));
+    press(&mut view, Action::AccelZoom(Some(ZoomDirection::Inwards)));
+    let z = target(&view);
+    for _ in 0..600
+    {
+        view.update();
+        assert_close(target(&view), z, 1.0);
+    }
+    assert!(*view.zoom > 1e3, "zoom stayed at {}", *view.zoom);
+}
+
+#[test]
+fn rotation_keeps_target_fixed()
+{
+    let mut view = view();
+    view.update_mouse_pos(PhysicalPosition::new(100.0, 500.0));
+    view.recenter();
+    press(&mut view, Action::AccelRotate(Some(RotateDirection::Right)));
+    press(&mut view, Action::AccelZoom(Some(ZoomDirection::Inwards)));
+    press(&mut view, Action::Rotate(RotateDirection::Left));
+    let z = target(&view);
+    for _ in 0..600
+    {
+        view.update();
+        assert_close(target(&view), z, 1.0);
+    }
+}
+
+#[test]
+fn reverse_undoes_forward()
+{
+    let mut view = view();
+    view.update_mouse_pos(PhysicalPosition::new(250.0, 400.0));
+    view.recenter();
+    for action in [
+        Action::AccelZoom(Some(ZoomDirection::Inwards)),
+        Action::AccelRotate(Some(RotateDirection::Left)),
+        Action::AccelCenter(Some(MoveDirection::Up)),
+        Action::AccelShift(Some(MoveDirection::Left)),
+        Action::AccelExp(Some(MoveDirection::Right))
+    ]
+    {
+        press(&mut view, action);
+    }
+    view.update();
+    let start = view.globals();
+    let win_center = view.win_center;
+
+    for _ in 0..300
+    {
+        view.update();
+    }
+    press(&mut view, Action::Reverse);
+    for _ in 0..300
+    {
+        view.update();
+    }
+
+    let end = view.globals();
+    assert!((end.zoom/start.zoom - 1.0).abs() < 1e-9, "zoom {} != {}", end.zoom, start.zoom);
+    assert!((end.rot - start.rot).abs() < 1e-9, "rot {} != {}", end.rot, start.rot);
+    assert_close(end.center, start.center, 1.0/start.zoom);
+    assert_close(end.shift, start.shift, 1.0);
+    assert_close(end.exp, start.exp, 1.0);
+    assert_close(view.win_center, win_center, 1.0);
+}
+
+#[test]
+fn zoom_bounces_within_one_frame()
+{
+    for direction in [ZoomDirection::Inwards, ZoomDirection::Outwards]
+    {
+        let mut view = view();
+        let settings = view.settings().clone();
+        press(&mut view, Action::AccelZoom(Some(direction)));
+        press(&mut view, Action::AccelZoom(Some(direction)));
+
+        let mut last_outside = false;
+        let mut bounces = 0;
+        for _ in 0..20000
+        {
+            let last = *view.zoom;
+            view.update();
+            let zoom = *view.zoom;
+            // Coming back takes the zoom to where it was before leaving, up to rounding
+            let outside = zoom > settings.zoom_range.end*(1.0 + 1e-9) || zoom < settings.zoom_range.start*(1.0 - 1e-9);
+            assert!(!(outside && last_outside), "zoom stayed outside {:?} for two frames, at {last} then {zoom}", settings.zoom_range);
+            if outside
+            {
+                let step = zoom/last;
+                assert!(
+                    zoom <= settings.zoom_range.end*step && zoom >= settings.zoom_range.start*step,
+                    "zoom {zoom} overshot {:?} by more than a frame",
+                    settings.zoom_range
+                );
+                bounces += 1
+            }
+            last_outside = outside;
+        }
+        assert!(bounces > 0, "zoom never reached the ends of {:?} going {direction:?}", settings.zoom_range);
+    }
+}
+
+#[test]
+fn braking_stops_without_turning()
+{
+    let mut view = view();
+    press(&mut view, Action::AccelZoom(Some(ZoomDirection::Inwards)));
+    view.update();
+    let mut speed = view.zoom.vel().ln();
+    assert!(speed > 0.0);
+    for _ in 0..10000
+    {
+        view.scroll(0.0, 1.0);
+        view.update();
+        let next = view.zoom.vel().ln();
+        assert!(next >= 0.0 && next <= speed, "zoom speed went from {speed} to {next} while braking");
+        speed = next;
+        if speed == 0.0
+        {
+            return
+        }
+    }
+    panic!("zoom never came to a stop, still at speed {speed}")
+}
+
+#[test]
+fn pause_and_time_scale()
+{
+    let mut view = view();
+    press(&mut view, Action::AccelZoom(Some(ZoomDirection::Inwards)));
+    assert_eq!(view.update(), DT);
+
+    press(&mut view, Action::Pause);
+    let zoom = *view.zoom;
+    let time = view.globals().time;
+    for _ in 0..10
+    {
+        assert_eq!(view.update(), 0.0);
+    }
+    assert_eq!(*view.zoom, zoom);
+    assert_eq!(view.globals().time, time);
+
+    press(&mut view, Action::Pause);
+    press(&mut view, Action::SlowDown);
+    assert_eq!(view.update(), DT/2.0);
+    press(&mut view, Action::SpeedUp);
+    press(&mut view, Action::SpeedUp);
+    assert_eq!(view.update(), DT*2.0);
+
+    press(&mut view, Action::Reset);
+    assert_eq!(view.update(), DT*2.0);
+}
+
+fn assert_bits(a: Complex<f64>, b: Complex<f64>)
+{
+    assert_eq!((a.re.to_bits(), a.im.to_bits()), (b.re.to_bits(), b.im.to_bits()), "{a:?} != {b:?}");
+}
+
+#[test]
+fn location_round_trips_bits()
+{
+    let values = [0.0, -0.0, 1.0, -0.8, 0.1 + 0.2, -1e-300, 5e-324, 1.7976931348623157e308, core::f64::consts::PI];
+    for (i, &re) in values.iter().enumerate()
+    {
+        let im = values[(i*4 + 1) % values.len()];
+        let location = Location {
+            fractal: "julia:c=-0.8+0.156i".to_string(),
+            center: Complex::new(re, im),
+            shift: Complex::new(im, re),
+            exp: Complex::new(-re, -im),
+            zoom: 1.0/(1.0 + re.abs().min(1e300)),
+            rot: -im
+        };
+        let text = location.to_string();
+        let parsed: Location<f64> = text.parse().unwrap();
+        assert_eq!(parsed.fractal, location.fractal);
+        for (a, b) in [(parsed.center, location.center), (parsed.shift, location.shift), (parsed.exp, location.exp)]
+        {
+            assert_bits(a, b)
+        }
+        assert_eq!(parsed.zoom.to_bits(), location.zoom.to_bits(), "{text}");
+        assert_eq!(parsed.rot.to_bits(), location.rot.to_bits(), "{text}");
+        assert_eq!(parsed.to_string(), text);
+    }
+}
+
+#[test]
+fn view_location_round_trips_bits()
+{
+    let julia = Julia { c: Complex::new(-0.8, 0.156), exp: Complex::new(2.0, 0.0) };
+    let mut view = View::new(&julia, SIZE, StartView::default(), Settings::default());
+    view.set_clock(FixedClock { dt: DT });
+    let location = view.location(&julia);
+    assert_bits(location.shift, Complex::new(-0.8, 0.156));
+    assert_bits(location.exp, Complex::new(2.0, 0.0));
+
+    let text = "julia:c=-0.8+0.156i,exp=2+0i;center=-0.1-0i;shift=-0.8+0.156i;exp=2-0i;zoom=1000.5;rot=-0";
+    let mut location: Location<f64> = text.parse().unwrap();
+    for _ in 0..3
+    {
+        let view = View::new(&julia, SIZE, location.start(None), Settings::default());
+        location = view.location(&julia);
+        assert_eq!(location.to_string(), text);
+    }
+
+    // Once it moves, the shift is whatever it moved to
+    press(&mut view, Action::AccelShift(Some(MoveDirection::Left)));
+    view.update();
+    assert_ne!(view.location(&julia).shift, Complex::new(-0.8, 0.156));
+}
+
+#[test]
+fn dual_starts_at_the_picked_point()
+{
+    let start = StartView {
+        center: Some(Complex::new(0.3, 0.2)),
+        shift: Some(Complex::new(0.1, 0.0)),
+        exp: Some(Complex::new(3.0, 0.0)),
+        ..StartView::default()
+    };
+    let view = View::new(&Mandelbrot, SIZE, start, Settings::default());
+    let c = Complex::new(-0.75, 0.1);
+    let dual = Mandelbrot.dual(&view.globals(), c).unwrap();
+
+    let globals = View::new(&dual, SIZE, view.start().dual(), Settings::default()).globals();
+    assert_close(globals.shift, c, 1.0);
+    assert_close(globals.exp, Complex::new(3.0, 0.0), 1.0);
+    assert_close(globals.center, Complex::new(0.0, 0.0), 1.0);
+}
+
+#[test]
+fn distance_modes_only_cycle_where_they_show()
+{
+    let mut view = view();
+    press(&mut view, Action::NextDistance);
+    assert_eq!(view.settings().palette.distance, Distance::Lines);
+
+    view.act(Action::NextDistance, ElementState::Pressed, &SupergoldenMandelbrot);
+    assert_eq!(view.settings().palette.distance, Distance::Lines);
+}
@@ -1,18 +1,15 @@
 use core::{cmp::Ordering, ops::Deref};
-use std::time::SystemTime;
-
 use num_complex::Complex;
 use num_traits::{Float, One, Zero};
 use winit::event::ElementState;
 
-use crate::{MyFloat, ZOOM_BASE, ZOOM_MUL, ZOOM_RANGE, app::{CoordControl, RotateDirection, ZoomDirection}, f};
+use crate::{MyFloat, app::{CoordControl, RotateDirection, ZoomDirection}, f, settings::Settings};
 
 #[derive(Debug, Clone, Copy)]
 pub struct ZoomControl<F>
 where
     F: MyFloat
 {
-    t: SystemTime,
     pos: F,
     vel: F,
     acc: F,
@@ -50,7 +47,6 @@ where
     fn default() -> Self
     {
         Self {
-            t: SystemTime::now(),
             pos: One::one(),
             vel: One::one(),
             acc: Zero::zero(),
@@ -73,6 +69,11 @@ where
         }
     }
 
+    pub fn vel(&self) -> F
+    {
+        self.vel
+    }
+
     pub fn mov(&mut self, direction: ZoomDirection, button_state: ElementState)
     {
         self.mov = match button_state
@@ -82,18 +83,17 @@ where
         }
     }
 
-    pub fn update(&mut self, speed: F, reverse: bool, center: &mut CoordControl<F>, win_center: Complex<F>, rot: F) -> anyhow::Result<()>
+    pub fn update(&mut self, dt: F, settings: &Settings, reverse: bool, center: &mut CoordControl<F>, win_center: Complex<F>, rot: F)
     {
-        let t0 = core::mem::replace(&mut self.t, SystemTime::now());
-        let dt = f!(self.t.duration_since(t0)?.as_secs_f64());
+        let speed = f!(settings.zoom_mul);
 
         let acc = core::mem::replace(&mut self.acc, F::zero());
-        let vel_mul = Float::exp(f!(ZOOM_BASE)*acc*dt);
+        let vel_mul = Float::exp(f!(settings.zoom_base)*acc*dt);
         self.vel *= vel_mul;
         let brk = core::mem::replace(&mut self.brk, F::zero());
         let hyp = Float::ln(Float::abs(self.vel));
         let sgn = Float::signum(hyp);
-        let kat = hyp*hyp - f!(ZOOM_BASE)*dt*brk*Float::abs(brk);
+        let kat = hyp*hyp - f!(settings.zoom_base)*dt*brk*Float::abs(brk);
         if Float::is_sign_negative(kat)
         {
             self.vel = F::one()
@@ -121,13 +121,11 @@ where
         center.nudge((win_center/new_zoom - win_center/self.pos)*Complex::cis(rot));
         self.pos = new_zoom;
         let dir = (self.vel > F::one()) ^ reverse;
-        if (self.pos > f!(ZOOM_RANGE.end) && dir)
-            || (self.pos < f!(ZOOM_RANGE.start) && !dir)
+        if (self.pos > f!(settings.zoom_range.end) && dir)
+            || (self.pos < f!(settings.zoom_range.start) && !dir)
         {
             self.vel = Float::recip(self.vel)
         }
-
-        Ok(())
     }
 
     pub fn push(&mut self, push: Option<(ZoomDirection, F)>)
@@ -150,12 +148,12 @@ where
 
     pub fn stop(&mut self)
     {
-        let Self { t, pos, mov, .. } = *self;
-        *self = Self { t, pos, mov, ..Default::default() };
+        let Self { pos, mov, .. } = *self;
+        *self = Self { pos, mov, ..Default::default() };
     }
     pub fn reset(&mut self)
     {
-        let Self { t, mov, .. } = *self;
-        *self = Self { t, mov, ..Default::default() };
+        let Self { mov, .. } = *self;
+        *self = Self { mov, ..Default::default() };
     }
 }
\ No newline at end of file
@@ -0,0 +1,441 @@
+use core::{fmt::Display, ops::Range, str::FromStr};
+use std::{path::PathBuf, sync::Arc};
+
+use clap::{ArgGroup, Parser};
+use num_complex::Complex;
+use winit::dpi::PhysicalSize;
+
+use crate::{START_ZOOM, app::{Bookmarks, Location, StartView}, fractal::*, terminal::Protocol};
+
+/// Parsed arguments have to be shareable between threads.
+pub type DynFractal = Arc<dyn Fractal<f64> + Send + Sync>;
+
+/// Every fractal that can be selected, and the parameters it takes.
+const FRACTALS: [Entry; 15] = [
+    Entry {
+        params: "w=<complex>",
+        build: |params| {
+            let mut fractal = Blancmange::default();
+            if let Some(w) = params.value("w")?
+            {
+                fractal.w = w
+            }
+            Ok(Arc::new(fractal))
+        }
+    },
+    Entry {
+        params: "phi=<start>..<end> | gamma=<real>, lambda=<complex>, sierpinski",
+        build: |params| {
+            let mut fractal = match (params.value_with("phi", parse_range)?, params.value("gamma")?)
+            {
+                (Some(_), Some(_)) => return Err(anyhow::Error::msg("cantor takes either phi or gamma, not both")),
+                (Some(phi), None) => Cantor::assymetric(phi),
+                (None, Some(gamma)) => Cantor::symmetric(gamma),
+                (None, None) => Cantor::cantor()
+            };
+            if let Some(lambda) = params.value("lambda")?
+            {
+                fractal.lambda = lambda
+            }
+            if params.flag("sierpinski")?
+            {
+                fractal = fractal.sierpinski()
+            }
+            Ok(Arc::new(fractal))
+        }
+    },
+    Entry {
+        params: "r=<complex>",
+        build: |params| {
+            let mut fractal = Feigenbaum::default();
+            if let Some(r) = params.value("r")?
+            {
+                fractal.r = r
+            }
+            Ok(Arc::new(fractal))
+        }
+    },
+    Entry {
+        params: "f=<complex>, lambda=<complex>",
+        build: |params| {
+            let mut fractal = FibonacciHamiltonianJulia::default();
+            if let Some(f) = params.value("f")?
+            {
+                fractal.f = f
+            }
+            if let Some(lambda) = params.value("lambda")?
+            {
+                fractal.lambda = lambda
+            }
+            Ok(Arc::new(fractal))
+        }
+    },
+    Entry {
+        params: "f=<complex>, lambda=<complex>",
+        build: |params| {
+            let mut fractal = FibonacciHamiltonianMandelbrot::default();
+            if let Some(f) = params.value("f")?
+            {
+                fractal.f = f
+            }
+            if let Some(lambda) = params.value("lambda")?
+            {
+                fractal.lambda = lambda
+            }
+            Ok(Arc::new(fractal))
+        }
+    },
+    Entry {
+        params: "",
+        build: |_| Ok(Arc::new(FibonacciSnowlake))
+    },
+    Entry {
+        params: "",
+        build: |_| Ok(Arc::new(HeighwayDragon))
+    },
+    Entry {
+        params: "a=<real>, b=<real>",
+        build: |params| {
+            let mut fractal = Henon::default();
+            if let Some(a) = params.value("a")?
+            {
+                fractal.a = a
+            }
+            if let Some(b) = params.value("b")?
+            {
+                fractal.b = b
+            }
+            Ok(Arc::new(fractal))
+        }
+    },
+    Entry {
+        params: "dendrite | clover | c=<complex>, exp=<complex>",
+        build: |params| {
+            let mut fractal = match (params.flag("dendrite")?, params.flag("clover")?)
+            {
+                (true, true) => return Err(anyhow::Error::msg("julia takes either dendrite or clover, not both")),
+                (_, true) => Julia::clover(),
+                (_, false) => Julia::dendrite()
+            };
+            if let Some(c) = params.value("c")?
+            {
+                fractal.c = c
+            }
+            if let Some(exp) = params.value("exp")?
+            {
+                fractal.exp = exp
+            }
+            Ok(Arc::new(fractal))
+        }
+    },
+    Entry {
+        params: "",
+        build: |_| Ok(Arc::new(Mandelbrot))
+    },
+    Entry {
+        params: "",
+        build: |_| Ok(Arc::new(Pendulum))
+    },
+    Entry {
+        params: "f=<complex>, lambda=<complex>",
+        build: |params| {
+            let mut fractal = Rauzy::default();
+            if let Some(f) = params.value("f")?
+            {
+                fractal.f = f
+            }
+            if let Some(lambda) = params.value("lambda")?
+            {
+                fractal.lambda = lambda
+            }
+            Ok(Arc::new(fractal))
+        }
+    },
+    Entry {
+        params: "c=<complex>, exp=<complex>",
+        build: |params| {
+            let mut fractal = SupergoldenJulia::default();
+            if let Some(c) = params.value("c")?
+            {
+                fractal.c = c
+            }
+            if let Some(exp) = params.value("exp")?
+            {
+                fractal.exp = exp
+            }
+            Ok(Arc::new(fractal))
+        }
+    },
+    Entry {
+        params: "",
+        build: |_| Ok(Arc::new(SupergoldenMandelbrot))
+    },
+    Entry {
+        params: "step=<formula>, init=<formula>",
+        build: |params| {
+            let mut fractal = CustomFormula::default();
+            if let Some(step) = params.value_with("step", |step| Formula::parse(step, &Var::ALL))?
+            {
+                fractal.step = step
+            }
+            if let Some(init) = params.value_with("init", |init| Formula::parse(init, &Var::INIT))?
+            {
+                fractal.init = init
+            }
+            fractal.validate()?;
+            Ok(Arc::new(fractal))
+        }
+    }
+];
+
+#[derive(Parser)]
+#[command(version, about, group(ArgGroup::new("output").args(["png", "export"])), group(ArgGroup::new("script").args(["timeline", "replay"])))]
+pub struct Cli
+{
+    /// Fractals to cycle through, as <label>[:<param>=<value>,...], e.g. `cantor:phi=0.25..0.5,sierpinski` or `henon:a=1.2,b=0.3`
+    #[arg(value_parser = parse_fractal)]
+    pub fractals: Vec<DynFractal>,
+    /// List the available fractals and their parameters
+    #[arg(long)]
+    pub list: bool,
+    /// List the saved bookmarks
+    #[arg(long)]
+    pub bookmarks: bool,
+    /// Print the default keymap, as a starting point for keymap.toml in the config directory
+    #[arg(long)]
+    pub default_keymap: bool,
+    /// Start out at a location printed by the app, which also picks the first fractal
+    #[arg(long, value_parser = parse_location, conflicts_with_all = ["zoom", "center", "shift", "exp"])]
+    pub location: Option<(DynFractal, Location<f64>)>,
+    #[arg(long, default_value_t = START_ZOOM as f64, value_parser = parse_zoom)]
+    pub zoom: f64,
+    /// Overrides max_iterations from the config file
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+    pub max_iterations: Option<u32>,
+    /// The point in the plane to start out centered on, e.g. `-0.75+0.1i`
+    #[arg(long, allow_hyphen_values = true)]
+    pub center: Option<Complex<f64>>,
+    #[arg(long, allow_hyphen_values = true)]
+    pub shift: Option<Complex<f64>>,
+    #[arg(long, allow_hyphen_values = true)]
+    pub exp: Option<Complex<f64>>,
+    /// Open a window instead of going fullscreen
+    #[arg(long)]
+    pub windowed: bool,
+    /// Render the first fractal to a PNG without opening a window
+    #[arg(long, value_name = "PATH", conflicts_with = "terminal")]
+    pub png: Option<PathBuf>,
+    /// Render a timeline of keyframes from a TOML file with the first fractal, without opening a window
+    #[arg(long, value_name = "PATH", requires = "export")]
+    pub timeline: Option<PathBuf>,
+    /// Replay a session recorded with --record, rendering it at the window size the recording started out with
+    #[arg(long, value_name = "PATH", requires = "export")]
+    pub replay: Option<PathBuf>,
+    /// Where to write the frames of a timeline or replay, as a `.y4m` video or a directory of numbered PNGs
+    #[arg(long, value_name = "PATH", requires = "script", conflicts_with = "terminal")]
+    pub export: Option<PathBuf>,
+    #[arg(long, default_value_t = 30, value_parser = clap::value_parser!(u32).range(1..), requires = "script")]
+    pub fps: u32,
+    /// Record everything that moves the view in the window to a file, for --replay
+    #[arg(long, value_name = "PATH", conflicts_with_all = ["output", "terminal"])]
+    pub record: Option<PathBuf>,
+    /// Step the view by a fixed number of seconds every frame rather than by the wall clock
+    #[arg(long, value_name = "SECONDS", value_parser = parse_time_step, conflicts_with_all = ["output", "terminal"])]
+    pub time_step: Option<f64>,
+    /// Explore on autopilot, diving into detailed spots on its own
+    #[arg(long, conflicts_with = "output")]
+    pub autopilot: bool,
+    /// Reload the shaders from the source tree whenever one is saved, keeping the view, for working on them
+    #[arg(long, conflicts_with_all = ["output", "terminal"])]
+    pub watch_shaders: bool,
+    #[arg(long, value_name = "WIDTHxHEIGHT", default_value = "1024x768", value_parser = parse_size, requires = "output")]
+    pub size: PhysicalSize<u32>,
+    /// Render the PNG or timeline on the CPU, in full precision
+    #[arg(long, requires = "output")]
+    pub cpu: bool,
+    /// Stream the zoom to the terminal, as halfblocks, sixel or kitty graphics (detected if left out)
+    #[arg(long, value_name = "PROTOCOL", num_args = 0..=1)]
+    pub terminal: Option<Option<Protocol>>
+}
+
+impl Cli
+{
+    pub fn start(&self) -> StartView<f64>
+    {
+        if let Some((_, location)) = &self.location
+        {
+            return location.start(self.max_iterations)
+        }
+        StartView {
+            zoom: self.zoom,
+            max_iterations: self.max_iterations,
+            center: self.center,
+            shift: self.shift,
+            exp: self.exp,
+            rot: 0.0
+        }
+    }
+}
+
+pub fn list_fractals() -> anyhow::Result<()>
+{
+    for entry in &FRACTALS
+    {
+        println!("{:<40}{}", entry.label()?, entry.params)
+    }
+    Ok(())
+}
+
+/// Lists the saved bookmarks, with the center as given to `--center`.
+pub fn list_bookmarks() -> anyhow::Result<()>
+{
+    for (fractal, name, bookmark) in Bookmarks::load()?.iter()
+    {
+        println!("{fractal:<40}{name:<8}center={} zoom={:e}", -bookmark.center, bookmark.zoom)
+    }
+    Ok(())
+}
+
+struct Entry
+{
+    params: &'static str,
+    build: fn(&mut Params) -> anyhow::Result<DynFractal>
+}
+
+impl Entry
+{
+    fn label(&self) -> anyhow::Result<&'static str>
+    {
+        Ok((self.build)(&mut Params::default())?.label())
+    }
+}
+
+/// What follows the label of a fractal, as `<param>=<value>`s and flags.
+#[derive(Default)]
+struct Params<'a>
+{
+    label: &'a str,
+    params: Vec<(&'a str, Option<&'a str>)>
+}
+
+impl<'a> Params<'a>
+{
+    fn parse(label: &'a str, params: &'a str) -> anyhow::Result<Self>
+    {
+        let params = params.split(',')
+            .filter(|param| !param.is_empty())
+            .map(|param| match param.split_once('=')
+            {
+                Some(("", _)) => Err(anyhow::Error::msg(format!("Missing parameter name in '{param}' for {label}"))),
+                Some((name, value)) => Ok((name, Some(value))),
+                None => Ok((param, None))
+            })
+            .collect::<anyhow::Result<_>>()?;
+        Ok(Self { label, params })
+    }
+
+    fn take(&mut self, name: &str) -> Option<Option<&'a str>>
+    {
+        let i = self.params.iter().position(|&(param, _)| param == name)?;
+        Some(self.params.remove(i).1)
+    }
+
+    fn value<T>(&mut self, name: &str) -> anyhow::Result<Option<T>>
+    where
+        T: FromStr,
+        T::Err: Display
+    {
+        self.value_with(name, |value| value.parse().map_err(|error| anyhow::Error::msg(format!("{error}"))))
+    }
+
+    fn value_with<T, P>(&mut self, name: &str, parse: P) -> anyhow::Result<Option<T>>
+    where
+        P: FnOnce(&str) -> anyhow::Result<T>
+    {
+        match self.take(name)
+        {
+            Some(Some(value)) => parse(value)
+                .map(Some)
+                .map_err(|error| anyhow::Error::msg(format!("Invalid value '{value}' for {}'s {name}: {error}", self.label))),
+            Some(None) => Err(anyhow::Error::msg(format!("Missing value for {}'s {name}", self.label))),
+            None => Ok(None)
+        }
+    }
+
+    fn flag(&mut self, name: &str) -> anyhow::Result<bool>
+    {
+        match self.take(name)
+        {
+            Some(Some(_)) => Err(anyhow::Error::msg(format!("{}'s {name} doesn't take a value", self.label))),
+            Some(None) => Ok(true),
+            None => Ok(false)
+        }
+    }
+
+    /// Fails on anything the fractal didn't ask for.
+    fn finish(self) -> anyhow::Result<()>
+    {
+        match self.params.first()
+        {
+            Some((name, _)) => Err(anyhow::Error::msg(format!("Unknown parameter '{name}' for {}, see --list", self.label))),
+            None => Ok(())
+        }
+    }
+}
+
+pub fn parse_fractal(arg: &str) -> anyhow::Result<DynFractal>
+{
+    let (label, params) = arg.split_once(':').unwrap_or((arg, ""));
+    for entry in &FRACTALS
+    {
+        if entry.label()? == label
+        {
+            let mut params = Params::parse(label, params)?;
+            let fractal = (entry.build)(&mut params)?;
+            params.finish()?;
+            return Ok(fractal)
+        }
+    }
+    Err(anyhow::Error::msg(format!("Unknown fractal '{label}', see --list")))
+}
+
+fn parse_location(arg: &str) -> anyhow::Result<(DynFractal, Location<f64>)>
+{
+    let location: Location<f64> = arg.parse()?;
+    let fractal = parse_fractal(&location.fractal)?;
+    Ok((fractal, location))
+}
+
+fn parse_range(arg: &str) -> anyhow::Result<Range<f64>>
+{
+    let (start, end) = arg.split_once("..")
+        .ok_or(anyhow::Error::msg("Expected a range as <start>..<end>"))?;
+    Ok(start.parse()?..end.parse()?)
+}
+
+fn parse_zoom(arg: &str) -> anyhow::Result<f64>
+{
+    let zoom: f64 = arg.parse()?;
+    if !zoom.is_finite() || zoom <= 0.0
+    {
+        return Err(anyhow::Error::msg("Zoom must be positive and finite"))
+    }
+    Ok(zoom)
+}
+
+fn parse_time_step(arg: &str) -> anyhow::Result<f64>
+{
+    let dt: f64 = arg.parse()?;
+    if !dt.is_finite() || dt < 0.0
+    {
+        return Err(anyhow::Error::msg("Time step must be non-negative and finite"))
+    }
+    Ok(dt)
+}
+
+fn parse_size(arg: &str) -> anyhow::Result<PhysicalSize<u32>>
+{
+    let (width, height) = arg.split_once('x')
+        .ok_or(anyhow::Error::msg("Expected resolution as <width>x<height>"))?;
+    Ok(PhysicalSize::new(width.parse()?, height.parse()?))
+}
@@ -1,15 +1,15 @@
 use num_complex::Complex;
-use num_traits::{One, Zero};
+use num_traits::{Float, One, Zero};
 use winit::dpi::PhysicalSize;
 
-use crate::{MyFloat, app::InitView, f, fractal::{Fractal, dcdz}};
+use crate::{MyFloat, app::InitView, f, fractal::{pipeline, ShaderDefs, Fractal, dcdz, cpu::{Globals, colormap_trap, escape, norm_sqr, smooth}}};
 
 use super::wgsl_bindgen::blancmange;
 
 #[derive(Clone, Copy)]
 pub struct Blancmange
 {
-    w: Complex<f64>
+    pub w: Complex<f64>
 }
 
 impl Default for Blancmange
@@ -31,6 +31,11 @@ where
         "blancmange"
     }
 
+    fn params(&self) -> String
+    {
+        format!("w={}", self.w)
+    }
+
     fn init_view(&self, _zoom: F, _win_size: PhysicalSize<u32>) -> InitView<F>
     {
         let Self { w } = self;
@@ -41,34 +46,34 @@ where
         }
     }
 
-    fn setup_render_pipeline(&self, device: &wgpu::Device, surface_format: wgpu::TextureFormat) -> wgpu::RenderPipeline
+    fn fs_main(&self, globals: &Globals<F>, pos: Complex<F>) -> [F; 4]
+    {
+        fn triangle<F>(r: F) -> F
+        where
+            F: MyFloat
+        {
+            f!(2.0)*Float::abs(f!(2.0)*(r - Float::floor(r + f!(0.5)))) - F::one()
+        }
+
+        let mut c = globals.coord(pos);
+        let z = globals.shift;
+        let mut w = Complex::from(F::one());
+        let r = Float::max(Float::max(F::one(), norm_sqr(z)), norm_sqr(c));
+
+        let n = globals.max_iterations().to_u32().unwrap();
+        let (i, z, trap) = escape(globals, z, r, n, |z| {
+            c *= f!(2.0);
+            let z = z + Complex::new(triangle(c.re), triangle(c.im))*w;
+            w *= globals.exp;
+            z
+        });
+        let m = smooth(i, z, Float::recip(globals.exp.norm()));
+
+        colormap_trap(globals, z, m, trap, 3)
+    }
+
+    fn setup_render_pipeline(&self, device: &wgpu::Device, surface_format: wgpu::TextureFormat, defs: &ShaderDefs) -> anyhow::Result<wgpu::RenderPipeline>
     {
-        // Create shader module from generated code
-        let shader = blancmange::create_shader_module_embed_source(device);
-        
-        // Use generated pipeline layout
-        let pipeline_layout = blancmange::create_pipeline_layout(device);
-        
-        // Use generated vertex entry with proper buffer layout
-        let vertex_entry = blancmange::vs_main_entry(wgpu::VertexStepMode::Vertex);
-     
-        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some(Fractal::<F>::label(self)),
-            layout: Some(&pipeline_layout),
-            vertex: blancmange::vertex_state(&shader, &vertex_entry),
-            fragment: Some(blancmange::fragment_state(&shader, &blancmange::fs_main_entry([
-                Some(wgpu::ColorTargetState {
-                    format: surface_format,
-                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
-                    write_mask: wgpu::ColorWrites::COLOR,
-                })
-            ]))),
-            primitive: wgpu::PrimitiveState::default(),
-            depth_stencil: None,
-            multisample: wgpu::MultisampleState::default(),
-            multiview_mask: None,
-            cache: None
-            // ... other pipeline state
-        })
+        pipeline!(blancmange, device, surface_format, defs, Fractal::<F>::label(self))
     }
 }
\ No newline at end of file
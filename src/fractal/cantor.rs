@@ -4,7 +4,7 @@ use num_complex::Complex;
 use num_traits::{Float, One, Zero};
 use winit::dpi::PhysicalSize;
 
-use crate::{MyFloat, app::InitView, f, fractal::{Fractal, dcdz}};
+use crate::{MyFloat, app::InitView, f, fractal::{pipeline, ShaderDefs, Fractal, dcdz, cpu::{Globals, colormap, powc}}};
 
 use super::wgsl_bindgen::cantor;
 
@@ -86,6 +86,11 @@ where
         "cantor"
     }
 
+    fn params(&self) -> String
+    {
+        format!("phi={}..{},lambda={}", self.phi.start, self.phi.end, self.lambda)
+    }
+
     fn init_view(&self, _zoom: F, _win_size: PhysicalSize<u32>) -> InitView<F>
     {
         let Self { phi, lambda } = self;
@@ -97,34 +102,81 @@ where
         }
     }
 
-    fn setup_render_pipeline(&self, device: &wgpu::Device, surface_format: wgpu::TextureFormat) -> wgpu::RenderPipeline
+    fn fs_main(&self, globals: &Globals<F>, pos: Complex<F>) -> [F; 4]
+    {
+        let mut z = globals.coord(pos);
+        let phi = globals.shift;
+        let lambda = globals.exp;
+
+        let r = Complex::new(phi.im - phi.re, phi.im - phi.re)/f!(2.0);
+        let r_lambda = powc(r, lambda);
+        let mut r_k = r*r_lambda;
+        let c = (phi.im + phi.re)/f!(2.0);
+
+        let n = globals.max_iterations().to_u32().unwrap();
+        let mut i: u32 = 1;
+        let d = F::zero();
+        while i < n
+        {
+            z = Complex::new(z.re - Float::floor(z.re), z.im - Float::floor(z.im));
+            let mut ok = false;
+            let mut stop = false;
+            z.re = cantor_dim(z.re, c, r_k.re, &mut ok, &mut stop);
+            z.im = cantor_dim(z.im, c, r_k.im, &mut ok, &mut stop);
+            if !ok || stop || (ok == stop)
+            {
+                break;
+            }
+            r_k *= r_lambda;
+            i += 1;
+        }
+        let m = f!(i) - Float::sqrt(d);
+
+        colormap(globals, z, m, 3)
+    }
+
+    fn setup_render_pipeline(&self, device: &wgpu::Device, surface_format: wgpu::TextureFormat, defs: &ShaderDefs) -> anyhow::Result<wgpu::RenderPipeline>
+    {
+        pipeline!(cantor, device, surface_format, defs, Fractal::<F>::label(self))
+    }
+}
+
+fn cantor_dim<F>(z: F, c: F, r: F, ok: &mut bool, stop: &mut bool) -> F
+where
+    F: MyFloat
+{
+    let s = r < F::zero(); // sierpinski carpet
+    let r = Float::abs(r);
+    if z <= c
+    {
+        if z > c - r
+        {
+            if !s
+            {
+                *stop = true;
+            }
+            (z - (c - r))/(f!(2.0)*r)
+        }
+        else
+        {
+            *ok = true;
+            (z - (c - F::one() + r))/(f!(0.5) - r)
+        }
+    }
+    else
     {
-        // Create shader module from generated code
-        let shader = cantor::create_shader_module_embed_source(device);
-        
-        // Use generated pipeline layout
-        let pipeline_layout = cantor::create_pipeline_layout(device);
-        
-        // Use generated vertex entry with proper buffer layout
-        let vertex_entry = cantor::vs_main_entry(wgpu::VertexStepMode::Vertex);
-     
-        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some(Fractal::<F>::label(self)),
-            layout: Some(&pipeline_layout),
-            vertex: cantor::vertex_state(&shader, &vertex_entry),
-            fragment: Some(cantor::fragment_state(&shader, &cantor::fs_main_entry([
-                Some(wgpu::ColorTargetState {
-                    format: surface_format,
-                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
-                    write_mask: wgpu::ColorWrites::COLOR,
-                })
-            ]))),
-            primitive: wgpu::PrimitiveState::default(),
-            depth_stencil: None,
-            multisample: wgpu::MultisampleState::default(),
-            multiview_mask: None,
-            cache: None
-            // ... other pipeline state
-        })
+        if z < c + r
+        {
+            if !s
+            {
+                *stop = true;
+            }
+            (z - (c - r))/(f!(2.0)*r)
+        }
+        else
+        {
+            *ok = true;
+            (z - (c + r))/(f!(0.5) - r)
+        }
     }
-}
\ No newline at end of file
+}
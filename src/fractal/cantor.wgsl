@@ -1,5 +1,5 @@
 #import global_bindings::{GlobalUniforms, VertexInput, wrap, globals, max_iterations, view_radius, epsilon};
-#import colormap::colormap3;
+#import colormap::colormap;
 #import complex::{cmul, cis, norm_sqr, norm, powc, cdiv}
 #import consts::PI;
 
@@ -59,7 +59,7 @@ fn fs_main(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32>
     }
     let m = f32(i) - sqrt(d);
 
-    return colormap3(z, m);
+    return colormap(z, m, 3);
 }
 
 fn cantor_dim(z: f32, c: f32, r: f32, ok: ptr<function, bool>, stop: ptr<function, bool>) -> f32
@@ -0,0 +1,147 @@
+use std::{borrow::Cow, collections::HashMap, path::PathBuf};
+
+use naga_oil::compose::{ComposableModuleDescriptor, Composer, NagaModuleDescriptor, ShaderDefValue};
+use serde::{Deserialize, Serialize};
+
+/// The modules in `lib/`, each after the ones it imports.
+const LIB: [(&str, &str); 9] = [
+    ("consts", include_str!("lib/consts.wgsl")),
+    ("complex", include_str!("lib/complex.wgsl")),
+    ("color", include_str!("lib/color.wgsl")),
+    ("global_bindings", include_str!("lib/global_bindings.wgsl")),
+    ("global_bindings_df", include_str!("lib/global_bindings_df.wgsl")),
+    ("colormap", include_str!("lib/colormap.wgsl")),
+    ("complex_df", include_str!("lib/complex_df.wgsl")),
+    ("perturbation", include_str!("lib/perturbation.wgsl")),
+    ("formula", include_str!("lib/formula.wgsl"))
+];
+
+/// Variations on the shaders, composed at runtime from the `lib/` modules with shader defs. Without any, the shaders
+/// generated at build time are used as they are.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ShaderDefs
+{
+    /// How far orbits go before they count as escaped, relative to how far out they start.
+    pub escape_radius: Option<u32>,
+    /// Whether to color by how the escape counts rank in each frame, spreading the palette evenly over it.
+    pub histogram: bool,
+    /// Where to read the shaders from instead of what was built in, while they're being worked on.
+    #[serde(skip)]
+    pub source_dir: Option<PathBuf>
+}
+
+impl ShaderDefs
+{
+    pub fn validate(&self) -> anyhow::Result<()>
+    {
+        if self.escape_radius == Some(0)
+        {
+            return Err(anyhow::Error::msg("escape_radius must be positive"))
+        }
+        Ok(())
+    }
+
+    /// The squared escape radius `bailout` in `lib/global_bindings.wgsl` gives, squared in `f32` the same way.
+    pub fn bailout(&self) -> f32
+    {
+        self.escape_radius.map_or(4.0, |radius| (radius as f32)*(radius as f32))
+    }
+
+    /// Whether the shaders generated at build time will do.
+    pub fn is_built_in(&self) -> bool
+    {
+        self.defs().is_empty() && self.source_dir.is_none()
+    }
+
+    fn defs(&self) -> HashMap<String, ShaderDefValue>
+    {
+        let Self { escape_radius, histogram, source_dir: _ } = self;
+
+        escape_radius.map(|radius| ("ESCAPE_RADIUS".to_string(), ShaderDefValue::UInt(radius)))
+            .into_iter()
+            .chain(histogram.then(|| ("ESCAPE_VALUE".to_string(), ShaderDefValue::Bool(true))))
+            .collect()
+    }
+
+    /// Composes the shader `source`, found at `path` in `src/fractal`, with these defs.
+    pub fn compose(&self, device: &wgpu::Device, path: &str, source: &str) -> anyhow::Result<wgpu::ShaderModule>
+    {
+        self.compose_with(device, path, source, &[])
+    }
+
+    /// Like [`ShaderDefs::compose`], with the `lib/` modules named in `lib` replaced by the sources given, e.g. the
+    /// `formula` module generated for a [`CustomFormula`](super::CustomFormula).
+    pub fn compose_with(
+        &self,
+        device: &wgpu::Device,
+        path: &str,
+        source: &str,
+        lib: &[(&str, &str)]
+    ) -> anyhow::Result<wgpu::ShaderModule>
+    {
+        let module = self.module(path, source, lib)?;
+        Ok(device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some(path),
+            source: wgpu::ShaderSource::Naga(Cow::Owned(module))
+        }))
+    }
+
+    /// The module [`ShaderDefs::compose_with`] makes a shader of.
+    pub fn module(&self, path: &str, source: &str, lib: &[(&str, &str)]) -> anyhow::Result<wgpu::naga::Module>
+    {
+        let mut composer = Composer::default();
+        for (name, source) in LIB
+        {
+            let lib_path = format!("lib/{name}.wgsl");
+            let source = match lib.iter().find(|&&(other, _)| other == name)
+            {
+                Some(&(_, source)) => Cow::Borrowed(source),
+                None => self.read(&lib_path, source)?
+            };
+            let added = composer.add_composable_module(ComposableModuleDescriptor {
+                    source: &source,
+                    file_path: &lib_path,
+                    as_name: Some(name.to_string()),
+                    shader_defs: self.defs(),
+                    ..Default::default()
+                })
+                .map(|_| ());
+            added.map_err(|error| anyhow::Error::msg(error.emit_to_string(&composer)))?;
+        }
+        let source = self.read(path, source)?;
+        composer.make_naga_module(NagaModuleDescriptor {
+                source: &source,
+                file_path: path,
+                shader_defs: self.defs(),
+                ..Default::default()
+            })
+            .map_err(|error| anyhow::Error::msg(error.emit_to_string(&composer)))
+    }
+
+    /// The source at `path` in the source directory if there is one, or else the one built in.
+    fn read<'a>(&self, path: &str, built_in: &'a str) -> anyhow::Result<Cow<'a, str>>
+    {
+        let Some(dir) = &self.source_dir
+        else
+        {
+            return Ok(Cow::Borrowed(built_in))
+        };
+        let path = dir.join(path);
+        std::fs::read_to_string(&path)
+            .map(Cow::Owned)
+            .map_err(|error| anyhow::Error::new(error).context(format!("Failed to read {}", path.display())))
+    }
+
+    /// The shader composed with these defs, or `embedded` if it's built in.
+    pub fn shader_module<E>(&self, device: &wgpu::Device, path: &str, source: &str, embedded: E) -> anyhow::Result<wgpu::ShaderModule>
+    where
+        E: FnOnce(&wgpu::Device) -> wgpu::ShaderModule
+    {
+        if self.is_built_in()
+        {
+            return Ok(embedded(device))
+        }
+        self.compose(device, path, source)
+    }
+}
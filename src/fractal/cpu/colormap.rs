@@ -0,0 +1,181 @@
+use num_complex::Complex;
+use num_traits::Float;
+
+use crate::{MyFloat, f, fractal::cpu::{Globals, arg, norm, wrap}};
+
+/// How far out from the axes Pickover stalks show, as in `lib/colormap.wgsl`.
+const STALK_WIDTH: f64 = 0.05;
+/// What the closest approach to the orbit trap starts from, as in `lib/colormap.wgsl`.
+pub const TRAP_FAR: f64 = 1e6;
+
+/// WGSL `clamp`, which is `min(max(e, low), high)`, so NaN ends up at `low`.
+pub fn clamp<F>(e: F, low: F, high: F) -> F
+where
+    F: MyFloat
+{
+    Float::min(Float::max(e, low), high)
+}
+
+pub fn hsl2rgb<F>([h, s, l]: [F; 3]) -> [F; 3]
+where
+    F: MyFloat
+{
+    [0.0, 4.0, 2.0].map(|offset| {
+        let rgb = clamp(Float::abs(((h*f!(6.0) + f!(offset)) % f!(6.0)) - f!(3.0)) - F::one(), F::zero(), F::one());
+        l + s*(rgb - f!(0.5))*(F::one() - Float::abs(f!(2.0)*l - F::one()))
+    })
+}
+
+/// CPU mirror of `colormap`, which colors by the colormap picked at runtime, or the fractal's `own` if none is.
+pub fn colormap<F>(globals: &Globals<F>, z: Complex<F>, i: F, own: u32) -> [F; 4]
+where
+    F: MyFloat
+{
+    match if globals.colormap == 0 { own } else { globals.colormap }
+    {
+        1 => colormap1(z),
+        2 => colormap2(globals, z, i),
+        4 => colormap4(z),
+        5 => colormap5(globals, i),
+        _ => colormap3(globals, z, i)
+    }
+}
+
+/// CPU mirror of `trap_distance`, which is how far `z` is from the orbit trap.
+pub fn trap_distance<F>(globals: &Globals<F>, z: Complex<F>) -> F
+where
+    F: MyFloat
+{
+    let d = z - globals.trap_pos;
+    match globals.trap
+    {
+        1 => norm(d),
+        2 => Float::abs(d.im),
+        3 | 5 => Float::min(Float::abs(d.re), Float::abs(d.im)),
+        4 => Float::abs(norm(d) - F::one()),
+        _ => F::zero()
+    }
+}
+
+/// CPU mirror of `colormap_trap`, which colors by the palette at the closest the orbit came to the orbit trap.
+pub fn colormap_trap<F>(globals: &Globals<F>, z: Complex<F>, i: F, trap: F, own: u32) -> [F; 4]
+where
+    F: MyFloat
+{
+    let color = colormap(globals, z, i, own);
+    if globals.trap == 0
+    {
+        return color
+    }
+    let [r, g, b] = globals.palette.sample(trap + globals.palette_offset + globals.palette_speed*globals.time);
+    let trapped = [r, g, b, f!(0.8)];
+    if globals.trap == 5
+    {
+        let s = smoothstep(F::zero(), f!(STALK_WIDTH), trap);
+        return [0, 1, 2, 3].map(|k| trapped[k] + (color[k] - trapped[k])*s)
+    }
+    trapped
+}
+
+/// CPU mirror of `colormap_de`, which colors by the distance estimate from `der` on top of `colormap_trap`.
+pub fn colormap_de<F>(globals: &Globals<F>, z: Complex<F>, der: Complex<F>, i: F, trap: F, own: u32) -> [F; 4]
+where
+    F: MyFloat
+{
+    let color = colormap_trap(globals, z, i, trap, own);
+    if globals.distance == 0
+    {
+        return color
+    }
+    let escaped = i < globals.max_iterations();
+    let d = f!(0.5)*norm(z)*Float::ln(norm(z))/norm(der);
+    let [r, g, b, a] = color;
+    if globals.distance == 1
+    {
+        let x = if escaped { smoothstep(f!(0.5), F::one(), d) } else { F::one() };
+        return [x, x, x, a]
+    }
+    if !escaped
+    {
+        return color
+    }
+    if globals.distance == 2
+    {
+        let x = Float::sqrt(clamp(d/f!(8.0), F::zero(), F::one()));
+        return [r*x, g*x, b*x, a]
+    }
+    let inside = colormap_trap(globals, z, globals.max_iterations(), trap, own);
+    let coverage = clamp(d, F::zero(), F::one());
+    [0, 1, 2, 3].map(|k| inside[k] + (color[k] - inside[k])*coverage)
+}
+
+/// WGSL `smoothstep`.
+pub fn smoothstep<F>(low: F, high: F, x: F) -> F
+where
+    F: MyFloat
+{
+    let t = clamp((x - low)/(high - low), F::zero(), F::one());
+    t*t*(f!(3.0) - f!(2.0)*t)
+}
+
+pub fn colormap5<F>(globals: &Globals<F>, i: F) -> [F; 4]
+where
+    F: MyFloat
+{
+    let t = i/globals.max_iterations();
+    if Float::is_nan(t) || t >= F::one()
+    {
+        return [F::zero(), F::zero(), F::zero(), f!(0.8)]
+    }
+
+    let [r, g, b] = globals.palette.sample(t + globals.palette_offset + globals.palette_speed*globals.time);
+    [r, g, b, f!(0.8)]
+}
+
+pub fn colormap4<F>(z: Complex<F>) -> [F; 4]
+where
+    F: MyFloat
+{
+    let light = f!(0.25) + wrap(z.re/F::TAU() + f!(0.25), F::one())*f!(0.5);
+    let hue = wrap(z.im/F::TAU(), F::one());
+
+    let [r, g, b] = hsl2rgb([hue, f!(0.5), light]);
+    [r, g, b, f!(0.8)]
+}
+
+pub fn colormap3<F>(globals: &Globals<F>, z: Complex<F>, i: F) -> [F; 4]
+where
+    F: MyFloat
+{
+    let t = clamp(i/globals.max_iterations() % F::one(), F::zero(), F::one());
+
+    let z_norm = F::one() - Float::exp(-norm(z));
+    let hue = arg(z)/F::TAU() + f!(0.5);
+
+    let [r, g, b] = hsl2rgb([hue, z_norm/f!(2.0), t]);
+    [r, g, b, f!(0.8)]
+}
+
+pub fn colormap2<F>(globals: &Globals<F>, z: Complex<F>, i: F) -> [F; 4]
+where
+    F: MyFloat
+{
+    let t = clamp(i/globals.max_iterations(), F::zero(), F::one());
+
+    let hue = arg(z)/F::TAU() + f!(0.5);
+
+    let [r, g, b] = hsl2rgb([hue, f!(0.5), t]);
+    [r, g, b, f!(0.8)]
+}
+
+pub fn colormap1<F>(z: Complex<F>) -> [F; 4]
+where
+    F: MyFloat
+{
+    let z_norm = norm(z);
+
+    let hue = arg(z)/F::TAU() + f!(0.5);
+
+    let [r, g, b] = hsl2rgb([hue, f!(0.5), z_norm % F::one()]);
+    [r, g, b, F::one()]
+}
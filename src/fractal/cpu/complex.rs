@@ -0,0 +1,73 @@
+use num_complex::Complex;
+use num_traits::{Float, Zero};
+
+use crate::{MyFloat, f};
+
+pub fn conj<F>(z: Complex<F>) -> Complex<F>
+where
+    F: MyFloat
+{
+    Complex::new(z.re, -z.im)
+}
+
+pub fn cis<F>(rot: F) -> Complex<F>
+where
+    F: MyFloat
+{
+    Complex::new(Float::cos(rot), Float::sin(rot))
+}
+
+pub fn norm_sqr<F>(x: Complex<F>) -> F
+where
+    F: MyFloat
+{
+    x.re*x.re + x.im*x.im
+}
+
+pub fn norm<F>(x: Complex<F>) -> F
+where
+    F: MyFloat
+{
+    Float::sqrt(norm_sqr(x))
+}
+
+pub fn arg<F>(x: Complex<F>) -> F
+where
+    F: MyFloat
+{
+    Float::atan2(x.im, x.re)
+}
+
+pub fn clog<F>(x: Complex<F>) -> Complex<F>
+where
+    F: MyFloat
+{
+    Complex::new(Float::ln(norm_sqr(x))/f!(2.0), arg(x))
+}
+
+pub fn cexp<F>(x: Complex<F>) -> Complex<F>
+where
+    F: MyFloat
+{
+    cis(x.im)*Float::exp(x.re)
+}
+
+/// Unlike [`Complex::powc`], zero to any power is zero, as is anything small enough that its norm squared underflows,
+/// which would take the log to infinity and the result to NaN.
+pub fn powc<F>(x: Complex<F>, y: Complex<F>) -> Complex<F>
+where
+    F: MyFloat
+{
+    if norm_sqr(x) == F::zero()
+    {
+        return Complex::zero()
+    }
+    cexp(y*clog(x))
+}
+
+pub fn dpowc<F>(x: Complex<F>, y: Complex<F>) -> Complex<F>
+where
+    F: MyFloat
+{
+    y*powc(x, y - F::one())
+}
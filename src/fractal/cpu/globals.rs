@@ -0,0 +1,110 @@
+use num_complex::Complex;
+use num_traits::Float;
+use winit::dpi::PhysicalSize;
+
+use crate::{MyFloat, f, fractal::{GlobalUniforms, GlobalUniformsDf, Palette, cpu::cis}};
+
+/// [`GlobalUniforms`] in full precision.
+#[derive(Debug, Clone, Copy)]
+pub struct Globals<F>
+where
+    F: MyFloat
+{
+    pub time: F,
+    pub window_size: PhysicalSize<u32>,
+    pub max_iterations: u32,
+    pub center: Complex<F>,
+    pub zoom: F,
+    pub rot: F,
+    pub exp: Complex<F>,
+    pub shift: Complex<F>,
+    pub colormap: u32,
+    pub palette_offset: F,
+    pub palette_speed: F,
+    pub palette: Palette,
+    pub distance: u32,
+    pub trap: u32,
+    pub trap_pos: Complex<F>,
+    /// The squared radius orbits escape beyond, relative to how far out they start, as `bailout` in
+    /// `lib/global_bindings.wgsl` from [`ShaderDefs::bailout`](crate::fractal::ShaderDefs::bailout).
+    pub bailout: F
+}
+
+impl<F> Globals<F>
+where
+    F: MyFloat
+{
+    pub fn max_iterations(&self) -> F
+    {
+        f!(self.max_iterations)*Float::max(F::one(), Float::ln(self.zoom))
+    }
+
+    /// Maps a pixel position relative to the center of the window to the complex plane, like every `fs_main` does.
+    pub fn coord(&self, pos: Complex<F>) -> Complex<F>
+    {
+        pos/self.zoom*cis(self.rot) - self.center
+    }
+}
+
+impl<F> From<Globals<F>> for GlobalUniforms
+where
+    F: MyFloat
+{
+    fn from(globals: Globals<F>) -> Self
+    {
+        let Globals { time, window_size, max_iterations, center, zoom, rot, exp, shift, colormap, palette_offset, palette_speed, palette, distance, trap, trap_pos, bailout: _ } = globals;
+        let vec2 = |z: Complex<F>| glam::vec2(z.re.to_f32().unwrap(), z.im.to_f32().unwrap());
+        let (palette, palette_len) = palette.uniforms();
+        GlobalUniforms {
+            time: time.to_f32().unwrap(),
+            _pad_time: [0; _],
+            max_iterations,
+            _pad_max_iterations: [0; _],
+            window_size: glam::uvec2(window_size.width, window_size.height),
+            center: vec2(center),
+            zoom: zoom.to_f32().unwrap(),
+            rot: rot.to_f32().unwrap(),
+            exp: vec2(exp),
+            shift: vec2(shift),
+            colormap,
+            palette_offset: palette_offset.to_f32().unwrap(),
+            palette_speed: palette_speed.to_f32().unwrap(),
+            palette_len,
+            distance,
+            trap,
+            trap_pos: vec2(trap_pos),
+            _pad_trap_pos: [0; _],
+            palette
+        }
+    }
+}
+
+impl<F> From<Globals<F>> for GlobalUniformsDf
+where
+    F: MyFloat
+{
+    fn from(globals: Globals<F>) -> Self
+    {
+        let Globals { center, zoom, .. } = globals;
+        let [re_hi, re_lo] = split_df(center.re);
+        let [im_hi, im_lo] = split_df(center.im);
+        GlobalUniformsDf::new(glam::vec4(re_hi, re_lo, im_hi, im_lo), glam::Vec2::from(split_df(zoom)), 1.0)
+    }
+}
+
+/// Splits into a double-float `[hi, lo]`, where `hi + lo` holds about 48 bits of mantissa.
+pub fn split_df<F>(x: F) -> [f32; 2]
+where
+    F: MyFloat
+{
+    let hi = x.to_f32().unwrap();
+    let lo = (x - f!(hi)).to_f32().unwrap();
+    [hi, lo]
+}
+
+pub fn wrap<F>(x: F, w: F) -> F
+where
+    F: MyFloat
+{
+    ((x % w) + w) % w
+}
@@ -0,0 +1,80 @@
+//! CPU mirrors of the shared WGSL libraries in `lib/`, so every fractal's `fs_main` can be evaluated without a GPU, and
+//! in any [`MyFloat`] precision.
+
+moddef::moddef!(
+    flat(pub) mod {
+        globals,
+        complex,
+        colormap
+    }
+);
+
+#[cfg(test)]
+mod tests;
+
+use num_complex::Complex;
+use num_traits::Float;
+
+use crate::{MyFloat, f};
+
+/// The escape-time loop most of the shaders share, keeping track of the closest the orbit comes to the orbit trap:
+///
+/// ```wgsl
+/// var trap = TRAP_FAR;
+/// for(; i < n && norm_sqr(z) < r*bailout(); i++) { z = step(z); trap = min(trap, trap_distance(z)); }
+/// ```
+pub fn escape<F, S>(globals: &Globals<F>, mut z: Complex<F>, r: F, n: u32, mut step: S) -> (u32, Complex<F>, F)
+where
+    F: MyFloat,
+    S: FnMut(Complex<F>) -> Complex<F>
+{
+    let mut i = 0;
+    let mut trap = f!(TRAP_FAR);
+    while i < n && norm_sqr(z) < r*globals.bailout
+    {
+        z = step(z);
+        trap = Float::min(trap, trap_distance(globals, z));
+        i += 1;
+    }
+    (i, z, trap)
+}
+
+/// [`escape`], also carrying the derivative `der` of `z` through the loop for the distance estimate, given the
+/// derivative of `z` in the step as well:
+///
+/// ```wgsl
+/// for(; i < n && norm_sqr(z) < r*bailout(); i++) { der = cmul(dstep(z), der) + dc; z = step(z); ... }
+/// ```
+pub fn escape_derivative<F, S>(
+    globals: &Globals<F>,
+    mut z: Complex<F>,
+    mut der: Complex<F>,
+    dc: Complex<F>,
+    r: F,
+    n: u32,
+    mut step: S
+) -> (u32, Complex<F>, Complex<F>, F)
+where
+    F: MyFloat,
+    S: FnMut(Complex<F>) -> (Complex<F>, Complex<F>)
+{
+    let mut i = 0;
+    let mut trap = f!(TRAP_FAR);
+    while i < n && norm_sqr(z) < r*globals.bailout
+    {
+        let (next, dstep) = step(z);
+        der = dstep*der + dc;
+        z = next;
+        trap = Float::min(trap, trap_distance(globals, z));
+        i += 1;
+    }
+    (i, z, der, trap)
+}
+
+/// Smooth escape count `m = f32(i) - log(log(norm(z)))/log(base)`.
+pub fn smooth<F>(i: u32, z: Complex<F>, base: F) -> F
+where
+    F: MyFloat
+{
+    f!(i) - Float::ln(Float::ln(norm(z)))/Float::ln(base)
+}
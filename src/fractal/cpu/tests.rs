@@ -0,0 +1,153 @@
+use core::f64::consts::E;
+
+use num_complex::Complex;
+use winit::dpi::PhysicalSize;
+
+use crate::{app::{StartView, View}, cli::parse_fractal, fractal::{CustomFormula, Distance, Fractal, Julia, Mandelbrot, PaletteSettings, cpu::{Globals, colormap3, colormap_de, colormap_trap, smooth}}, settings::Settings};
+
+const SIZE: PhysicalSize<u32> = PhysicalSize::new(320, 240);
+
+fn globals<Z>(fractal: &Z) -> Globals<f64>
+where
+    Z: Fractal<f64>
+{
+    View::new(fractal, SIZE, StartView::default(), Settings::default()).globals()
+}
+
+/// The pixel position relative to the center of the window that [`Globals::coord`] maps to `c`.
+fn pos(globals: &Globals<f64>, c: Complex<f64>) -> Complex<f64>
+{
+    (c + globals.center)*globals.zoom/Complex::cis(globals.rot)
+}
+
+fn max_iterations(globals: &Globals<f64>) -> u32
+{
+    globals.max_iterations() as u32
+}
+
+fn assert_color(color: [f64; 4], expected: [f64; 4])
+{
+    assert!(color.iter().zip(expected).all(|(a, b)| (a - b).abs() < 1e-9), "{color:?} != {expected:?}");
+}
+
+#[test]
+fn mandelbrot_interior_never_escapes()
+{
+    let globals = globals(&Mandelbrot);
+    for c in [Complex::new(0.0, 0.0), Complex::new(-1.0, 0.0), Complex::new(-0.1, 0.1), Complex::new(0.25, 0.0)]
+    {
+        assert_eq!(Mandelbrot.escape_count(&globals, pos(&globals, c)), Some(max_iterations(&globals)), "c = {c}");
+    }
+}
+
+#[test]
+fn mandelbrot_exterior_escape_counts()
+{
+    let globals = globals(&Mandelbrot);
+    // 0, -2.5, 3.75, 11.5625 with the escape radius at 4*6.25
+    assert_eq!(Mandelbrot.escape_count(&globals, pos(&globals, Complex::new(-2.5, 0.0))), Some(3));
+    // 0, 3, 12 with the escape radius at 4*9
+    assert_eq!(Mandelbrot.escape_count(&globals, pos(&globals, Complex::new(3.0, 0.0))), Some(2));
+    let n = Mandelbrot.escape_count(&globals, pos(&globals, Complex::new(0.3, 0.0))).unwrap();
+    assert!(n > 3 && n < max_iterations(&globals), "escaped after {n}");
+}
+
+#[test]
+fn escape_radius_moves_the_bailout()
+{
+    let mut settings = Settings::default();
+    settings.shader.escape_radius = Some(10);
+    let globals = View::new(&Mandelbrot, SIZE, StartView::default(), settings).globals();
+    assert_eq!(globals.bailout, 100.0);
+    // 0, -2.5, 3.75, 11.5625, 131.19... with the escape radius at 100*6.25
+    assert_eq!(Mandelbrot.escape_count(&globals, pos(&globals, Complex::new(-2.5, 0.0))), Some(4));
+    assert_eq!(Mandelbrot.escape_count(&globals, pos(&globals, Complex::new(3.0, 0.0))), Some(3));
+}
+
+#[test]
+fn julia_escape_counts()
+{
+    let julia = Julia { c: Complex::new(0.0, 0.0), exp: Complex::new(2.0, 0.0) };
+    let globals = globals(&julia);
+    assert_eq!(julia.escape_count(&globals, pos(&globals, Complex::new(0.5, 0.0))), Some(max_iterations(&globals)));
+    // 3, 9 with the escape radius at 4*9
+    assert_eq!(julia.escape_count(&globals, pos(&globals, Complex::new(3.0, 0.0))), Some(1));
+}
+
+#[test]
+fn custom_formula_defaults_to_mandelbrot()
+{
+    let custom = CustomFormula::default();
+    let globals = globals(&Mandelbrot);
+    for y in (0..SIZE.height).step_by(16)
+    {
+        for x in (0..SIZE.width).step_by(16)
+        {
+            let pos = Complex::new(x as f64 - SIZE.width as f64/2.0, y as f64 - SIZE.height as f64/2.0);
+            assert_eq!(custom.escape_count(&globals, pos), Mandelbrot.escape_count(&globals, pos), "at {pos}");
+        }
+    }
+}
+
+#[test]
+fn every_fractal_colors_within_range()
+{
+    for label in [
+        "blancmange", "cantor", "feigenbaum", "fibonacci_hamiltonian_julia", "fibonacci_hamiltonian_mandelbrot",
+        "fibonacci_snowflake", "heighway_dragon", "henon", "julia", "mandelbrot", "pendulum", "rauzy",
+        "supergolden_julia", "supergolden_mandelbrot", "custom"
+    ]
+    {
+        let fractal = parse_fractal(label).unwrap();
+        let globals = globals(&fractal);
+        for y in (0..SIZE.height).step_by(40)
+        {
+            for x in (0..SIZE.width).step_by(40)
+            {
+                let pos = Complex::new(x as f64 - SIZE.width as f64/2.0, y as f64 - SIZE.height as f64/2.0);
+                let color = fractal.fs_main(&globals, pos);
+                assert!(color.iter().all(|c| (0.0..=1.0).contains(c)), "{label} at {pos}: {color:?}");
+            }
+        }
+    }
+}
+
+#[test]
+fn smooth_escape_count()
+{
+    assert!((smooth(3, Complex::new(E, 0.0), E) - 3.0).abs() < 1e-12);
+    assert!((smooth(3, Complex::new(0.0, E.powf(E)), E) - 2.0).abs() < 1e-12);
+    assert!((smooth(3, Complex::new(E.powf(E*E), 0.0), E.powi(2)) - 2.0).abs() < 1e-12);
+}
+
+#[test]
+fn colormap3_lightness_follows_escape_count()
+{
+    let globals = globals(&Mandelbrot);
+    let n = globals.max_iterations();
+    // No saturation at the origin, so the lightness is all there is
+    assert_color(colormap3(&globals, Complex::new(0.0, 0.0), n/2.0), [0.5, 0.5, 0.5, 0.8]);
+    assert_color(colormap3(&globals, Complex::new(0.0, 0.0), n/4.0), [0.25, 0.25, 0.25, 0.8]);
+    // Never escaping wraps around to black, whatever the hue
+    assert_color(colormap3(&globals, Complex::new(1.0, 1.0), n), [0.0, 0.0, 0.0, 0.8]);
+}
+
+#[test]
+fn antialias_blends_with_the_inside()
+{
+    let settings = Settings {
+        palette: PaletteSettings { distance: Distance::Antialias, ..PaletteSettings::default() },
+        ..Settings::default()
+    };
+    let globals = View::new(&Mandelbrot, SIZE, StartView::default(), settings).globals();
+    let (z, trap) = (Complex::new(3.0, 0.0), 0.0);
+    let outside = colormap_trap(&globals, z, 2.0, trap, 3);
+    let inside = colormap_trap(&globals, z, globals.max_iterations(), trap, 3);
+    assert!(outside != inside);
+
+    // Far from the boundary the pixel is all outside, right on it all inside, and halfway half of each
+    assert_color(colormap_de(&globals, z, Complex::new(1e-6, 0.0), 2.0, trap, 3), outside);
+    assert_color(colormap_de(&globals, z, Complex::new(1e12, 0.0), 2.0, trap, 3), inside);
+    let der = Complex::new(3.0*3.0f64.ln(), 0.0);
+    assert_color(colormap_de(&globals, z, der, 2.0, trap, 3), [0, 1, 2, 3].map(|k| (inside[k] + outside[k])/2.0));
+}
@@ -0,0 +1,125 @@
+use num_complex::Complex;
+use num_traits::{Float, Zero};
+use winit::dpi::PhysicalSize;
+
+use crate::{f, MyFloat, app::InitView, fractal::{ShaderDefs, Fractal, Formula, Var, color_targets, create_pipeline, formula_module, cpu::{Globals, colormap_trap, escape, norm_sqr, smooth}}};
+
+use super::wgsl_bindgen::custom_formula;
+
+/// An escape time fractal iterating `z = step` from `z = init`, both given as text, e.g. `z^exp*(z - shift) + c`.
+#[derive(Clone)]
+pub struct CustomFormula
+{
+    pub step: Formula,
+    /// The start of the orbit, which can refer to anything but `z`.
+    pub init: Formula
+}
+
+impl CustomFormula
+{
+    pub fn parse(step: &str, init: &str) -> anyhow::Result<Self>
+    {
+        Ok(Self {
+            step: Formula::parse(step, &Var::ALL)?,
+            init: Formula::parse(init, &Var::INIT)?
+        })
+    }
+
+    /// Whether the formula compiles to a shader, so it's known when the fractal is built rather than when it's drawn.
+    pub fn validate(&self) -> anyhow::Result<()>
+    {
+        ShaderDefs::default().module("custom_formula.wgsl", include_str!("custom_formula.wgsl"), &[("formula", &self.module())])
+            .map(|_| ())
+            .map_err(|error| self.compile_error(error))
+    }
+
+    /// The `formula` module the shader is composed with.
+    fn module(&self) -> String
+    {
+        formula_module(&self.init, &self.step)
+    }
+
+    fn compile_error(&self, error: anyhow::Error) -> anyhow::Error
+    {
+        error.context(format!("z = {} from z = {} didn't compile", self.step, self.init))
+    }
+
+    fn iterate<F>(&self, globals: &Globals<F>, pos: Complex<F>) -> (u32, Complex<F>, F)
+    where
+        F: MyFloat
+    {
+        let c = globals.coord(pos);
+        let vars = |z: Complex<F>| move |var| match var
+        {
+            Var::Z => z,
+            Var::C => c,
+            Var::Shift => globals.shift,
+            Var::Exp => globals.exp
+        };
+        let z = self.init.expr.eval(&vars(Complex::zero()));
+        let r = Float::max(Float::max(F::one(), norm_sqr(z)), norm_sqr(c));
+
+        let n = globals.max_iterations().to_u32().unwrap();
+        escape(globals, z, r, n, |z| self.step.expr.eval(&vars(z)))
+    }
+}
+
+/// The Mandelbrot set.
+impl Default for CustomFormula
+{
+    fn default() -> Self
+    {
+        Self::parse("z^exp + c", "shift").unwrap()
+    }
+}
+
+impl<F> Fractal<F> for CustomFormula
+where
+    F: MyFloat
+{
+    fn label(&self) -> &'static str
+    {
+        "custom"
+    }
+
+    fn params(&self) -> String
+    {
+        format!("step={},init={}", self.step, self.init)
+    }
+
+    fn init_view(&self, _zoom: F, _win_size: PhysicalSize<u32>) -> InitView<F>
+    {
+        InitView {
+            exp: Complex::new(f!(2.0), F::zero()),
+            ..Default::default()
+        }
+    }
+
+    fn fs_main(&self, globals: &Globals<F>, pos: Complex<F>) -> [F; 4]
+    {
+        let (i, z, trap) = self.iterate(globals, pos);
+        let m = smooth(i, z, globals.exp.norm());
+
+        colormap_trap(globals, z, m, trap, 3)
+    }
+
+    fn escape_count(&self, globals: &Globals<F>, pos: Complex<F>) -> Option<u32>
+    {
+        Some(self.iterate(globals, pos).0)
+    }
+
+    fn setup_render_pipeline(&self, device: &wgpu::Device, surface_format: wgpu::TextureFormat, defs: &ShaderDefs) -> anyhow::Result<wgpu::RenderPipeline>
+    {
+        let shader = defs.compose_with(device, "custom_formula.wgsl", include_str!("custom_formula.wgsl"), &[("formula", &self.module())])
+            .map_err(|error| self.compile_error(error))?;
+        let vertex_entry = custom_formula::vs_main_entry(wgpu::VertexStepMode::Vertex);
+        let fragment_entry = custom_formula::fs_main_entry(color_targets(surface_format));
+        Ok(create_pipeline(
+            device,
+            Fractal::<F>::label(self),
+            &custom_formula::create_pipeline_layout(device),
+            custom_formula::vertex_state(&shader, &vertex_entry),
+            custom_formula::fragment_state(&shader, &fragment_entry)
+        ))
+    }
+}
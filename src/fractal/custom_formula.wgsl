@@ -0,0 +1,39 @@
+#import global_bindings::{GlobalUniforms, VertexInput, globals, max_iterations, bailout};
+#import colormap::{colormap_trap, trap_distance, TRAP_FAR};
+#import complex::{cmul, cis, norm_sqr, norm}
+#import formula::{formula_init, formula_step}
+
+@vertex
+fn vs_main(in: VertexInput) -> @builtin(position) vec4<f32>
+{
+    let corner = in.vertex_index % 3;
+    let n = in.vertex_index/3 % 2 == 1;
+    let pos = vec2(
+        f32(u32(corner == 1 || (corner == 0 && n))*globals.window_size.x) - f32(globals.window_size.x)/2.0,
+        f32(u32(corner == 2 || (corner == 0 && n))*globals.window_size.y) - f32(globals.window_size.y)/2.0
+    );
+
+    return vec4<f32>(pos, 0.0, 1.0);
+}
+
+@fragment
+fn fs_main(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32>
+{
+    let pos = position.xy/position.w - vec2(f32(globals.window_size.x), f32(globals.window_size.y))/2.0;
+
+    let c = cmul(pos/globals.zoom, cis(globals.rot)) - globals.center;
+    var z = formula_init(c);
+    let r = max(max(1.0, norm_sqr(z)), norm_sqr(c));
+    
+    var trap = TRAP_FAR;
+    let n = u32(max_iterations());
+    var i: u32 = 0;
+    for(; i < n && norm_sqr(z) < r*bailout(); i++)
+    {
+        z = formula_step(z, c);
+        trap = min(trap, trap_distance(z));
+    }
+    let m = f32(i) - log(log(norm(z)))/log(norm(globals.exp));
+
+    return colormap_trap(z, m, trap, 3);
+}
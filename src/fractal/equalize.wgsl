@@ -0,0 +1,51 @@
+#import global_bindings::globals;
+#import colormap::{escape_value, palette};
+
+// Colors each pixel by where its escape count ranks in the histogram built by histogram.wgsl, so the palette is spread
+// evenly over the frame whatever the zoom
+
+const BINS: u32 = 1024;
+
+struct Cdf {
+    lowest: f32,
+    highest: f32,
+    values: array<f32, BINS>,
+};
+
+@group(1) @binding(0)
+var escape: texture_2d<f32>;
+@group(1) @binding(1)
+var<storage, read> cdf: Cdf;
+
+// One triangle covering the window
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32) -> @builtin(position) vec4<f32>
+{
+    let uv = vec2(f32((vertex_index << 1) & 2), f32(vertex_index & 2));
+    return vec4(uv*2.0 - 1.0, 0.0, 1.0);
+}
+
+@fragment
+fn fs_main(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32>
+{
+    let m = escape_value(textureLoad(escape, vec2<u32>(position.xy), 0));
+    if !(m >= 0.0)
+    {
+        return vec4(0.0, 0.0, 0.0, 0.8);
+    }
+
+    // Interpolates within the bin, from the end of the one before
+    let x = clamp((m - cdf.lowest)/max(cdf.highest - cdf.lowest, 1.1920929e-7), 0.0, 1.0)*f32(BINS);
+    let k = min(u32(x), BINS - 1);
+    var start = 0.0;
+    if k > 0
+    {
+        start = cdf.values[k - 1];
+    }
+    let t = mix(start, cdf.values[k], x - f32(k));
+
+    return vec4(
+        palette(t + globals.palette_offset + globals.palette_speed*globals.time),
+        0.8
+    );
+}
@@ -1,9 +1,10 @@
 use num_complex::Complex;
+use num_traits::Float;
 use winit::dpi::PhysicalSize;
 
-use crate::{MyFloat, app::InitView, f, fractal::{Fractal, dcdz}};
+use crate::{MyFloat, app::InitView, f, fractal::{pipeline, ShaderDefs, Fractal, dcdz, cpu::{Globals, colormap_trap, escape, norm_sqr, smooth}}};
 
-use super::wgsl_bindgen::feigenbaum;
+use super::wgsl_bindgen::{feigenbaum, feigenbaum_df};
 
 /// z := rz(1 - z)
 #[derive(Clone, Copy)]
@@ -31,6 +32,11 @@ where
         "feigenbaum"
     }
 
+    fn params(&self) -> String
+    {
+        format!("r={}", self.r)
+    }
+
     fn init_view(&self, _zoom: F, _win_size: PhysicalSize<u32>) -> InitView<F>
     {
         let Self { r } = self;
@@ -40,34 +46,26 @@ where
         }
     }
 
-    fn setup_render_pipeline(&self, device: &wgpu::Device, surface_format: wgpu::TextureFormat) -> wgpu::RenderPipeline
+    fn fs_main(&self, globals: &Globals<F>, pos: Complex<F>) -> [F; 4]
+    {
+        let c = globals.coord(pos);
+        let z = globals.exp;
+        let r = Float::max(Float::max(F::one(), norm_sqr(z)), norm_sqr(c));
+
+        let n = globals.max_iterations().to_u32().unwrap();
+        let (i, z, trap) = escape(globals, z, r, n, |z| c*(z*(Complex::from(F::one()) - z)) + globals.shift);
+        let m = smooth(i, z, globals.exp.norm());
+
+        colormap_trap(globals, z, m, trap, 3)
+    }
+
+    fn setup_render_pipeline(&self, device: &wgpu::Device, surface_format: wgpu::TextureFormat, defs: &ShaderDefs) -> anyhow::Result<wgpu::RenderPipeline>
+    {
+        pipeline!(feigenbaum, device, surface_format, defs, Fractal::<F>::label(self))
+    }
+
+    fn setup_df_pipeline(&self, device: &wgpu::Device, surface_format: wgpu::TextureFormat, defs: &ShaderDefs) -> anyhow::Result<Option<wgpu::RenderPipeline>>
     {
-        // Create shader module from generated code
-        let shader = feigenbaum::create_shader_module_embed_source(device);
-        
-        // Use generated pipeline layout
-        let pipeline_layout = feigenbaum::create_pipeline_layout(device);
-        
-        // Use generated vertex entry with proper buffer layout
-        let vertex_entry = feigenbaum::vs_main_entry(wgpu::VertexStepMode::Vertex);
-     
-        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some(Fractal::<F>::label(self)),
-            layout: Some(&pipeline_layout),
-            vertex: feigenbaum::vertex_state(&shader, &vertex_entry),
-            fragment: Some(feigenbaum::fragment_state(&shader, &feigenbaum::fs_main_entry([
-                Some(wgpu::ColorTargetState {
-                    format: surface_format,
-                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
-                    write_mask: wgpu::ColorWrites::COLOR,
-                })
-            ]))),
-            primitive: wgpu::PrimitiveState::default(),
-            depth_stencil: None,
-            multisample: wgpu::MultisampleState::default(),
-            multiview_mask: None,
-            cache: None
-            // ... other pipeline state
-        })
+        pipeline!(feigenbaum_df, device, surface_format, defs, &format!("{} (double-float)", Fractal::<F>::label(self))).map(Some)
     }
 }
\ No newline at end of file
@@ -1,5 +1,5 @@
-#import global_bindings::{GlobalUniforms, VertexInput, globals, max_iterations, view_radius, epsilon};
-#import colormap::colormap3;
+#import global_bindings::{GlobalUniforms, VertexInput, globals, max_iterations, view_radius, epsilon, bailout};
+#import colormap::{colormap_trap, trap_distance, TRAP_FAR};
 #import complex::{cmul, cis, norm_sqr, norm, powc, cdiv}
 
 @vertex
@@ -24,15 +24,17 @@ fn fs_main(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32>
     var z = globals.exp;
     let r = max(max(1.0, norm_sqr(z)), norm_sqr(c));
     
+    var trap = TRAP_FAR;
     let n = u32(max_iterations());
     var i: u32 = 0;
-    for(; i < n && norm_sqr(z) < r*4.0; i++)
+    for(; i < n && norm_sqr(z) < r*bailout(); i++)
     {
         z = cmul(c, cmul(z, vec2(1.0, 0.0) - z)) + globals.shift;
+        trap = min(trap, trap_distance(z));
     }
     let m = f32(i) - log(log(norm(z)))/log(norm(globals.exp));
 
-    return colormap3(z, m);
+    return colormap_trap(z, m, trap, 3);
 }
 
 fn triangle(z: f32) -> f32
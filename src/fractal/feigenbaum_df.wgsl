@@ -0,0 +1,39 @@
+#import global_bindings::{GlobalUniforms, VertexInput, globals, max_iterations, bailout};
+#import colormap::{colormap_trap, trap_distance, TRAP_FAR};
+#import complex::{norm_sqr, norm}
+#import complex_df::{coord_df, cdf, cdf_to_f32, cadd_df, csub_df, cmul_df, norm_sqr_df}
+
+@vertex
+fn vs_main(in: VertexInput) -> @builtin(position) vec4<f32>
+{
+    let corner = in.vertex_index % 3;
+    let n = in.vertex_index/3 % 2 == 1;
+    let pos = vec2(
+        f32(u32(corner == 1 || (corner == 0 && n))*globals.window_size.x) - f32(globals.window_size.x)/2.0,
+        f32(u32(corner == 2 || (corner == 0 && n))*globals.window_size.y) - f32(globals.window_size.y)/2.0
+    );
+
+    return vec4<f32>(pos, 0.0, 1.0);
+}
+
+@fragment
+fn fs_main(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32>
+{
+    let pos = position.xy/position.w - vec2(f32(globals.window_size.x), f32(globals.window_size.y))/2.0;
+
+    let c = coord_df(pos);
+    var z = cdf(globals.exp);
+    let r = max(max(1.0, norm_sqr(cdf_to_f32(z))), norm_sqr(cdf_to_f32(c)));
+    
+    var trap = TRAP_FAR;
+    let n = u32(max_iterations());
+    var i: u32 = 0;
+    for(; i < n && norm_sqr_df(z).x < r*bailout(); i++)
+    {
+        z = cadd_df(cmul_df(c, cmul_df(z, csub_df(cdf(vec2(1.0, 0.0)), z))), cdf(globals.shift));
+        trap = min(trap, trap_distance(cdf_to_f32(z)));
+    }
+    let m = f32(i) - log(log(norm(cdf_to_f32(z))))/log(norm(globals.exp));
+
+    return colormap_trap(cdf_to_f32(z), m, trap, 3);
+}
\ No newline at end of file
@@ -4,9 +4,9 @@ use num_complex::Complex;
 use num_traits::{Float, One, Zero};
 use winit::dpi::PhysicalSize;
 
-use crate::{MyFloat, app::InitView, f, fractal::{Fractal, dcdz}};
+use crate::{MyFloat, app::InitView, f, fractal::{pipeline, ShaderDefs, Fractal, dcdz, cpu::{Globals, colormap_trap, smooth}, fibonacci_hamiltonian_mandelbrot::fibonacci_hamiltonian}};
 
-use super::wgsl_bindgen::fibonacci_hamiltonian_julia;
+use super::wgsl_bindgen::{fibonacci_hamiltonian_julia, fibonacci_hamiltonian_julia_df};
 
 #[derive(Clone)]
 pub struct FibonacciHamiltonianJulia
@@ -36,6 +36,11 @@ where
         "fibonacci_hamiltonian_julia"
     }
 
+    fn params(&self) -> String
+    {
+        format!("f={},lambda={}", self.f, self.lambda)
+    }
+
     fn init_view(&self, _zoom: F, _win_size: PhysicalSize<u32>) -> InitView<F>
     {
         let Self { f, lambda } = self;
@@ -47,34 +52,23 @@ where
         }
     }
 
-    fn setup_render_pipeline(&self, device: &wgpu::Device, surface_format: wgpu::TextureFormat) -> wgpu::RenderPipeline
+    fn fs_main(&self, globals: &Globals<F>, pos: Complex<F>) -> [F; 4]
+    {
+        let e = globals.coord(pos);
+        let z = globals.shift;
+        let (i, z, trap) = fibonacci_hamiltonian(globals, e, z);
+        let m = smooth(i, z, globals.exp.norm());
+
+        colormap_trap(globals, z, m, trap, 3)
+    }
+
+    fn setup_render_pipeline(&self, device: &wgpu::Device, surface_format: wgpu::TextureFormat, defs: &ShaderDefs) -> anyhow::Result<wgpu::RenderPipeline>
+    {
+        pipeline!(fibonacci_hamiltonian_julia, device, surface_format, defs, Fractal::<F>::label(self))
+    }
+
+    fn setup_df_pipeline(&self, device: &wgpu::Device, surface_format: wgpu::TextureFormat, defs: &ShaderDefs) -> anyhow::Result<Option<wgpu::RenderPipeline>>
     {
-        // Create shader module from generated code
-        let shader = fibonacci_hamiltonian_julia::create_shader_module_embed_source(device);
-        
-        // Use generated pipeline layout
-        let pipeline_layout = fibonacci_hamiltonian_julia::create_pipeline_layout(device);
-        
-        // Use generated vertex entry with proper buffer layout
-        let vertex_entry = fibonacci_hamiltonian_julia::vs_main_entry(wgpu::VertexStepMode::Vertex);
-     
-        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some(Fractal::<F>::label(self)),
-            layout: Some(&pipeline_layout),
-            vertex: fibonacci_hamiltonian_julia::vertex_state(&shader, &vertex_entry),
-            fragment: Some(fibonacci_hamiltonian_julia::fragment_state(&shader, &fibonacci_hamiltonian_julia::fs_main_entry([
-                Some(wgpu::ColorTargetState {
-                    format: surface_format,
-                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
-                    write_mask: wgpu::ColorWrites::COLOR,
-                })
-            ]))),
-            primitive: wgpu::PrimitiveState::default(),
-            depth_stencil: None,
-            multisample: wgpu::MultisampleState::default(),
-            multiview_mask: None,
-            cache: None
-            // ... other pipeline state
-        })
+        pipeline!(fibonacci_hamiltonian_julia_df, device, surface_format, defs, &format!("{} (double-float)", Fractal::<F>::label(self))).map(Some)
     }
 }
\ No newline at end of file
@@ -1,5 +1,5 @@
-#import global_bindings::{GlobalUniforms, VertexInput, globals, max_iterations, view_radius, epsilon};
-#import colormap::colormap3;
+#import global_bindings::{GlobalUniforms, VertexInput, globals, max_iterations, view_radius, epsilon, bailout};
+#import colormap::{colormap_trap, trap_distance, TRAP_FAR};
 #import complex::{cmul, cis, norm_sqr, norm, powc}
 
 @vertex
@@ -25,29 +25,33 @@ fn fs_main(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32>
     let lambda = globals.exp;
     let r = max(1.0, norm_sqr(z));
     
+    var trap = TRAP_FAR;
     let n = u32(max_iterations());
     var i: u32 = 0;
-    if norm_sqr(z) <= r*4.0
+    if norm_sqr(z) <= r*bailout()
     {
         var z_prev = z;
         z = e;
         i++;
-        if norm_sqr(z) <= r*4.0
+        trap = min(trap, trap_distance(z));
+        if norm_sqr(z) <= r*bailout()
         {
             var z_prev_prev = z_prev;
             var z_prev = z;
             z = e - lambda;
             i++;
-            for(; i < n && norm_sqr(z) <= r*4.0; i++)
+            trap = min(trap, trap_distance(z));
+            for(; i < n && norm_sqr(z) <= r*bailout(); i++)
             {
                 let z_next = cmul(z, z_prev) - z_prev_prev;
                 z_prev_prev = z_prev;
                 z_prev = z;
                 z = z_next;
+                trap = min(trap, trap_distance(z));
             }
         }
     }
     let m = f32(i) - log(log(norm(z)))/log(norm(globals.exp));
 
-    return colormap3(z, m);
+    return colormap_trap(z, m, trap, 3);
 }
\ No newline at end of file
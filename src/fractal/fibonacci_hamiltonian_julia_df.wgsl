@@ -0,0 +1,59 @@
+#import global_bindings::{GlobalUniforms, VertexInput, globals, max_iterations, bailout};
+#import colormap::{colormap_trap, trap_distance, TRAP_FAR};
+#import complex::{norm_sqr, norm}
+#import complex_df::{coord_df, cdf, cdf_to_f32, cadd_df, csub_df, cmul_df, norm_sqr_df}
+
+@vertex
+fn vs_main(in: VertexInput) -> @builtin(position) vec4<f32>
+{
+    let corner = in.vertex_index % 3;
+    let n = in.vertex_index/3 % 2 == 1;
+    let pos = vec2(
+        f32(u32(corner == 1 || (corner == 0 && n))*globals.window_size.x) - f32(globals.window_size.x)/2.0,
+        f32(u32(corner == 2 || (corner == 0 && n))*globals.window_size.y) - f32(globals.window_size.y)/2.0
+    );
+
+    return vec4<f32>(pos, 0.0, 1.0);
+}
+
+@fragment
+fn fs_main(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32>
+{
+    let pos = position.xy/position.w - vec2(f32(globals.window_size.x), f32(globals.window_size.y))/2.0;
+
+    let e = coord_df(pos);
+    var z = cdf(globals.shift);
+    let lambda = cdf(globals.exp);
+    let r = max(1.0, norm_sqr(cdf_to_f32(z)));
+    
+    var trap = TRAP_FAR;
+    let n = u32(max_iterations());
+    var i: u32 = 0;
+    if norm_sqr_df(z).x <= r*bailout()
+    {
+        var z_prev = z;
+        z = e;
+        i++;
+        trap = min(trap, trap_distance(cdf_to_f32(z)));
+        if norm_sqr_df(z).x <= r*bailout()
+        {
+            let z_next = csub_df(e, lambda);
+            var z_prev_prev = z_prev;
+            z_prev = z;
+            z = z_next;
+            i++;
+            trap = min(trap, trap_distance(cdf_to_f32(z)));
+            for(; i < n && norm_sqr_df(z).x <= r*bailout(); i++)
+            {
+                let z_next = csub_df(cmul_df(z, z_prev), z_prev_prev);
+                z_prev_prev = z_prev;
+                z_prev = z;
+                z = z_next;
+                trap = min(trap, trap_distance(cdf_to_f32(z)));
+            }
+        }
+    }
+    let m = f32(i) - log(log(norm(cdf_to_f32(z))))/log(norm(globals.exp));
+
+    return colormap_trap(cdf_to_f32(z), m, trap, 3);
+}
\ No newline at end of file
@@ -1,12 +1,13 @@
 use core::{f64::consts::{FRAC_PI_2, PI, SQRT_2}, ops::Range};
+use std::sync::Arc;
 
 use num_complex::Complex;
 use num_traits::{Float, One, Zero};
 use winit::dpi::PhysicalSize;
 
-use crate::{MyFloat, app::InitView, f, fractal::{Fractal, dcdz}};
+use crate::{MyFloat, app::InitView, f, fractal::{pipeline, ShaderDefs, FibonacciHamiltonianJulia, Fractal, OrbitPoint, dcdz, cpu::{Globals, colormap_trap, trap_distance, TRAP_FAR, norm_sqr, smooth}}};
 
-use super::wgsl_bindgen::fibonacci_hamiltonian_mandelbrot;
+use super::wgsl_bindgen::{fibonacci_hamiltonian_mandelbrot, fibonacci_hamiltonian_mandelbrot_df};
 
 #[derive(Clone)]
 pub struct FibonacciHamiltonianMandelbrot
@@ -36,6 +37,11 @@ where
         "fibonacci_hamiltonian_mandelbrot"
     }
 
+    fn params(&self) -> String
+    {
+        format!("f={},lambda={}", self.f, self.lambda)
+    }
+
     fn init_view(&self, _zoom: F, _win_size: PhysicalSize<u32>) -> InitView<F>
     {
         let Self { f, lambda } = self;
@@ -47,34 +53,95 @@ where
         }
     }
 
-    fn setup_render_pipeline(&self, device: &wgpu::Device, surface_format: wgpu::TextureFormat) -> wgpu::RenderPipeline
+    fn fs_main(&self, globals: &Globals<F>, pos: Complex<F>) -> [F; 4]
+    {
+        let e = globals.shift;
+        let z = globals.coord(pos);
+        let (i, z, trap) = fibonacci_hamiltonian(globals, e, z);
+        let m = smooth(i, z, globals.exp.norm());
+
+        colormap_trap(globals, z, m, trap, 3)
+    }
+
+    /// Starts from the last of the three starting values, so cycles aren't just the parameters repeating. The recurrence
+    /// depends on the last three values, so there's no derivative of a single step.
+    fn parameter_orbit(&self, globals: &Globals<F>, c: Complex<F>, n: usize) -> Option<Vec<OrbitPoint<F>>>
+    {
+        let n = n + 2;
+        let e = globals.shift;
+        let lambda = globals.exp;
+        let start = [(c, Complex::from(F::one())), (e, Complex::zero()), (e - lambda, Complex::zero())];
+        let mut orbit: Vec<_> = start.into_iter()
+            .take(n)
+            .map(|(z, dc)| OrbitPoint { z, dc, dz: None })
+            .collect();
+        while orbit.len() < n
+        {
+            let [a, b, c] = [&orbit[orbit.len() - 3], &orbit[orbit.len() - 2], &orbit[orbit.len() - 1]];
+            orbit.push(OrbitPoint {
+                z: c.z*b.z - a.z,
+                dc: c.dc*b.z + c.z*b.dc - a.dc,
+                dz: None
+            });
+        }
+        Some(orbit.split_off(2.min(orbit.len())))
+    }
+
+    /// Keeps the `lambda` of the view, which is in the exponent.
+    fn dual(&self, globals: &Globals<F>, c: Complex<F>) -> Option<Arc<dyn Fractal<F>>>
+    {
+        let lambda = globals.exp;
+        Some(Arc::new(FibonacciHamiltonianJulia {
+            f: Complex::new(c.re.to_f64().unwrap(), c.im.to_f64().unwrap()),
+            lambda: Complex::new(lambda.re.to_f64().unwrap(), lambda.im.to_f64().unwrap())
+        }))
+    }
+
+    fn setup_render_pipeline(&self, device: &wgpu::Device, surface_format: wgpu::TextureFormat, defs: &ShaderDefs) -> anyhow::Result<wgpu::RenderPipeline>
     {
-        // Create shader module from generated code
-        let shader = fibonacci_hamiltonian_mandelbrot::create_shader_module_embed_source(device);
-        
-        // Use generated pipeline layout
-        let pipeline_layout = fibonacci_hamiltonian_mandelbrot::create_pipeline_layout(device);
-        
-        // Use generated vertex entry with proper buffer layout
-        let vertex_entry = fibonacci_hamiltonian_mandelbrot::vs_main_entry(wgpu::VertexStepMode::Vertex);
-     
-        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some(Fractal::<F>::label(self)),
-            layout: Some(&pipeline_layout),
-            vertex: fibonacci_hamiltonian_mandelbrot::vertex_state(&shader, &vertex_entry),
-            fragment: Some(fibonacci_hamiltonian_mandelbrot::fragment_state(&shader, &fibonacci_hamiltonian_mandelbrot::fs_main_entry([
-                Some(wgpu::ColorTargetState {
-                    format: surface_format,
-                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
-                    write_mask: wgpu::ColorWrites::COLOR,
-                })
-            ]))),
-            primitive: wgpu::PrimitiveState::default(),
-            depth_stencil: None,
-            multisample: wgpu::MultisampleState::default(),
-            multiview_mask: None,
-            cache: None
-            // ... other pipeline state
-        })
+        pipeline!(fibonacci_hamiltonian_mandelbrot, device, surface_format, defs, Fractal::<F>::label(self))
     }
-}
\ No newline at end of file
+
+    fn setup_df_pipeline(&self, device: &wgpu::Device, surface_format: wgpu::TextureFormat, defs: &ShaderDefs) -> anyhow::Result<Option<wgpu::RenderPipeline>>
+    {
+        pipeline!(fibonacci_hamiltonian_mandelbrot_df, device, surface_format, defs, &format!("{} (double-float)", Fractal::<F>::label(self))).map(Some)
+    }
+}
+
+/// The three-term recurrence both Fibonacci-Hamiltonian shaders share.
+pub(super) fn fibonacci_hamiltonian<F>(globals: &Globals<F>, e: Complex<F>, mut z: Complex<F>) -> (u32, Complex<F>, F)
+where
+    F: MyFloat
+{
+    let lambda = globals.exp;
+    let r = Float::max(F::one(), norm_sqr(z));
+
+    let mut trap = f!(TRAP_FAR);
+    let n = globals.max_iterations().to_u32().unwrap();
+    let mut i = 0;
+    if norm_sqr(z) <= r*globals.bailout
+    {
+        let z_prev = z;
+        z = e;
+        i += 1;
+        trap = Float::min(trap, trap_distance(globals, z));
+        if norm_sqr(z) <= r*globals.bailout
+        {
+            let mut z_prev_prev = z_prev;
+            let mut z_prev = z;
+            z = e - lambda;
+            i += 1;
+            trap = Float::min(trap, trap_distance(globals, z));
+            while i < n && norm_sqr(z) <= r*globals.bailout
+            {
+                let z_next = z*z_prev - z_prev_prev;
+                z_prev_prev = z_prev;
+                z_prev = z;
+                z = z_next;
+                i += 1;
+                trap = Float::min(trap, trap_distance(globals, z));
+            }
+        }
+    }
+    (i, z, trap)
+}
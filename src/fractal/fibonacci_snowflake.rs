@@ -4,7 +4,7 @@ use num_complex::Complex;
 use num_traits::{Float, One, Zero};
 use winit::dpi::PhysicalSize;
 
-use crate::{MyFloat, app::InitView, f, fractal::{Fractal, dcdz}};
+use crate::{MyFloat, app::InitView, f, fractal::{pipeline, ShaderDefs, Fractal, dcdz, cpu::{Globals, colormap_trap, trap_distance, TRAP_FAR, conj, norm_sqr, smooth}}};
 
 use super::wgsl_bindgen::fibonacci_snowflake;
 
@@ -37,34 +37,48 @@ where
         }
     }
 
-    fn setup_render_pipeline(&self, device: &wgpu::Device, surface_format: wgpu::TextureFormat) -> wgpu::RenderPipeline
+    fn fs_main(&self, globals: &Globals<F>, pos: Complex<F>) -> [F; 4]
     {
-        // Create shader module from generated code
-        let shader = fibonacci_snowflake::create_shader_module_embed_source(device);
-        
-        // Use generated pipeline layout
-        let pipeline_layout = fibonacci_snowflake::create_pipeline_layout(device);
-        
-        // Use generated vertex entry with proper buffer layout
-        let vertex_entry = fibonacci_snowflake::vs_main_entry(wgpu::VertexStepMode::Vertex);
-     
-        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some(Fractal::<F>::label(self)),
-            layout: Some(&pipeline_layout),
-            vertex: fibonacci_snowflake::vertex_state(&shader, &vertex_entry),
-            fragment: Some(fibonacci_snowflake::fragment_state(&shader, &fibonacci_snowflake::fs_main_entry([
-                Some(wgpu::ColorTargetState {
-                    format: surface_format,
-                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
-                    write_mask: wgpu::ColorWrites::COLOR,
-                })
-            ]))),
-            primitive: wgpu::PrimitiveState::default(),
-            depth_stencil: None,
-            multisample: wgpu::MultisampleState::default(),
-            multiview_mask: None,
-            cache: None
-            // ... other pipeline state
-        })
+        let mut z = globals.coord(pos);
+        let c = globals.shift;
+        let r = Float::max(Float::max(F::one(), norm_sqr(z)), norm_sqr(c));
+
+        let mut trap = f!(TRAP_FAR);
+        let n = globals.max_iterations().to_u32().unwrap();
+        let mut i: u32 = 0;
+        let mut m_prev: u32 = 1;
+        let mut m: u32 = 1;
+        let mut z_prev = z + c;
+        while i < n && norm_sqr(z) < r*globals.bailout
+        {
+            let m_next = m_prev.wrapping_add(m);
+            m_prev = m;
+            m = m_next;
+
+            let mut v = z - z_prev + c;
+            if m_prev.is_multiple_of(2) == m.is_multiple_of(2)
+            {
+                if i.is_multiple_of(2)
+                {
+                    v *= globals.exp;
+                }
+                else
+                {
+                    v *= conj(globals.exp);
+                }
+            }
+            z_prev = z;
+            z += v;
+            i += 1;
+            trap = Float::min(trap, trap_distance(globals, z));
+        }
+        let mag = smooth(i, z, globals.exp.norm());
+
+        colormap_trap(globals, z, mag, trap, 3)
+    }
+
+    fn setup_render_pipeline(&self, device: &wgpu::Device, surface_format: wgpu::TextureFormat, defs: &ShaderDefs) -> anyhow::Result<wgpu::RenderPipeline>
+    {
+        pipeline!(fibonacci_snowflake, device, surface_format, defs, Fractal::<F>::label(self))
     }
 }
\ No newline at end of file
@@ -1,5 +1,5 @@
-#import global_bindings::{GlobalUniforms, VertexInput, globals, max_iterations, view_radius, epsilon};
-#import colormap::colormap3;
+#import global_bindings::{GlobalUniforms, VertexInput, globals, max_iterations, view_radius, epsilon, bailout};
+#import colormap::{colormap_trap, trap_distance, TRAP_FAR};
 #import complex::{cmul, cis, norm_sqr, norm, powc, conj, cdiv}
 
 @vertex
@@ -24,12 +24,13 @@ fn fs_main(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32>
     var c = globals.shift;
     let r = max(max(1.0, norm_sqr(z)), norm_sqr(c));
     
+    var trap = TRAP_FAR;
     var n = max_iterations();
     var i: u32 = 0;
     var m_prev: u32 = 1;
     var m: u32 = 1;
     var z_prev = z + c;
-    for(; i < u32(n) && norm_sqr(z) < r*4.0; i++)
+    for(; i < u32(n) && norm_sqr(z) < r*bailout(); i++)
     {
         let m_next = m_prev + m;
         m_prev = m;
@@ -49,8 +50,9 @@ fn fs_main(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32>
         }
         z_prev = z;
         z += v;
+        trap = min(trap, trap_distance(z));
     }
     let mag = f32(i) - log(log(norm(z)))/log(norm(globals.exp));
 
-    return colormap3(z, mag);
+    return colormap_trap(z, mag, trap, 3);
 }
\ No newline at end of file
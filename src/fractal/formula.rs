@@ -0,0 +1,547 @@
+use core::{fmt::{self, Display}, ops::Range, str::FromStr};
+
+use num_complex::Complex;
+
+use crate::{MyFloat, f, fractal::cpu::{cexp, clog, conj, powc}};
+
+/// The values a formula can refer to by name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Var
+{
+    /// The point of the orbit, only in the step.
+    Z,
+    /// The point in the plane.
+    C,
+    Shift,
+    Exp
+}
+
+impl Var
+{
+    pub const ALL: [Self; 4] = [Self::Z, Self::C, Self::Shift, Self::Exp];
+    /// What the start of the orbit may refer to, everything but `z`.
+    pub const INIT: [Self; 3] = [Self::C, Self::Shift, Self::Exp];
+
+    fn name(self) -> &'static str
+    {
+        match self
+        {
+            Self::Z => "z",
+            Self::C => "c",
+            Self::Shift => "shift",
+            Self::Exp => "exp"
+        }
+    }
+
+    fn wgsl(self) -> &'static str
+    {
+        match self
+        {
+            Self::Z => "z",
+            Self::C => "c",
+            Self::Shift => "globals.shift",
+            Self::Exp => "globals.exp"
+        }
+    }
+}
+
+/// The functions of one variable in `lib/complex.wgsl` a formula can call.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Func
+{
+    Cexp,
+    Clog,
+    Conj
+}
+
+impl Func
+{
+    const ALL: [Self; 3] = [Self::Cexp, Self::Clog, Self::Conj];
+
+    fn name(self) -> &'static str
+    {
+        match self
+        {
+            Self::Cexp => "cexp",
+            Self::Clog => "clog",
+            Self::Conj => "conj"
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op
+{
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Pow
+}
+
+impl Op
+{
+    fn symbol(self) -> char
+    {
+        match self
+        {
+            Self::Add => '+',
+            Self::Sub => '-',
+            Self::Mul => '*',
+            Self::Div => '/',
+            Self::Pow => '^'
+        }
+    }
+
+    fn precedence(self) -> u8
+    {
+        match self
+        {
+            Self::Add | Self::Sub => 1,
+            Self::Mul | Self::Div => 2,
+            Self::Pow => 4
+        }
+    }
+}
+
+/// Precedence of negation, which binds looser than `^` so that `-z^2` is `-(z^2)`.
+const NEG: u8 = 3;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr
+{
+    Num(Complex<f64>),
+    Var(Var),
+    Neg(Box<Expr>),
+    Binary(Op, Box<Expr>, Box<Expr>),
+    Call(Func, Box<Expr>)
+}
+
+impl Expr
+{
+    /// The expression in WGSL, over `z`, `c` and the globals.
+    pub fn wgsl(&self) -> String
+    {
+        match self
+        {
+            Self::Num(x) => format!("vec2<f32>({:?}, {:?})", x.re, x.im),
+            Self::Var(var) => var.wgsl().to_string(),
+            Self::Neg(x) => format!("(-{})", x.wgsl()),
+            Self::Binary(op @ (Op::Add | Op::Sub), lhs, rhs) => format!("({} {} {})", lhs.wgsl(), op.symbol(), rhs.wgsl()),
+            Self::Binary(Op::Mul, lhs, rhs) => format!("cmul({}, {})", lhs.wgsl(), rhs.wgsl()),
+            Self::Binary(Op::Div, lhs, rhs) => format!("cdiv({}, {})", lhs.wgsl(), rhs.wgsl()),
+            Self::Binary(Op::Pow, lhs, rhs) => format!("powc({}, {})", lhs.wgsl(), rhs.wgsl()),
+            Self::Call(func, x) => format!("{}({})", func.name(), x.wgsl())
+        }
+    }
+
+    /// CPU mirror of [`Expr::wgsl`], with `vars` giving the value of each variable.
+    pub fn eval<F, V>(&self, vars: &V) -> Complex<F>
+    where
+        F: MyFloat,
+        V: Fn(Var) -> Complex<F>
+    {
+        match self
+        {
+            Self::Num(x) => Complex::new(f!(x.re), f!(x.im)),
+            Self::Var(var) => vars(*var),
+            Self::Neg(x) => -x.eval(vars),
+            Self::Binary(op, lhs, rhs) => {
+                let (lhs, rhs) = (lhs.eval(vars), rhs.eval(vars));
+                match op
+                {
+                    Op::Add => lhs + rhs,
+                    Op::Sub => lhs - rhs,
+                    Op::Mul => lhs*rhs,
+                    Op::Div => lhs/rhs,
+                    Op::Pow => powc(lhs, rhs)
+                }
+            },
+            Self::Call(func, x) => {
+                let x = x.eval(vars);
+                match func
+                {
+                    Func::Cexp => cexp(x),
+                    Func::Clog => clog(x),
+                    Func::Conj => conj(x)
+                }
+            }
+        }
+    }
+
+    fn precedence(&self) -> u8
+    {
+        match self
+        {
+            Self::Num(x) if x.re != 0.0 && x.im != 0.0 => Op::Add.precedence(),
+            Self::Num(x) if x.re < 0.0 || x.im < 0.0 => NEG,
+            Self::Neg(_) => NEG,
+            Self::Binary(op, _, _) => op.precedence(),
+            _ => u8::MAX
+        }
+    }
+
+    fn fmt_operand(&self, f: &mut fmt::Formatter<'_>, min: u8) -> fmt::Result
+    {
+        if self.precedence() < min
+        {
+            write!(f, "({self})")
+        }
+        else
+        {
+            write!(f, "{self}")
+        }
+    }
+}
+
+/// Prints the expression without spaces, so it can be given on the command line as it is.
+impl Display for Expr
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        match self
+        {
+            Self::Num(x) if x.im == 0.0 => write!(f, "{}", x.re),
+            Self::Num(x) if x.re == 0.0 => write!(f, "{}i", x.im),
+            Self::Num(x) => write!(f, "{}{:+}i", x.re, x.im),
+            Self::Var(var) => write!(f, "{}", var.name()),
+            Self::Neg(x) => {
+                write!(f, "-")?;
+                x.fmt_operand(f, NEG)
+            },
+            Self::Binary(op, lhs, rhs) => {
+                // Left associative, except for `^`
+                let (left, right) = match op
+                {
+                    Op::Pow => (op.precedence() + 1, NEG),
+                    _ => (op.precedence(), op.precedence() + 1)
+                };
+                lhs.fmt_operand(f, left)?;
+                write!(f, "{}", op.symbol())?;
+                rhs.fmt_operand(f, right)
+            },
+            Self::Call(func, x) => write!(f, "{}({x})", func.name())
+        }
+    }
+}
+
+/// An expression for a complex number, parsed from text like `z^exp*(z - shift) + c`, optionally preceded by `z =`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Formula
+{
+    pub expr: Expr
+}
+
+impl Formula
+{
+    /// Parses `source`, which may only refer to `vars`.
+    pub fn parse(source: &str, vars: &[Var]) -> anyhow::Result<Self>
+    {
+        let tokens = tokenize(source)?;
+        let mut parser = Parser { source, tokens: &tokens, next: 0, vars };
+        if let [(Token::Ident("z"), _), (Token::Eq, _), ..] = parser.tokens
+        {
+            parser.next = 2
+        }
+        let expr = parser.expr()?;
+        if let Some((token, span)) = parser.peek()
+        {
+            return Err(error(source, span, format!("Expected an operator, found {token}")))
+        }
+        Ok(Self { expr })
+    }
+
+    pub fn wgsl(&self) -> String
+    {
+        self.expr.wgsl()
+    }
+}
+
+impl Display for Formula
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        write!(f, "{}", self.expr)
+    }
+}
+
+impl FromStr for Formula
+{
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err>
+    {
+        Self::parse(s, &Var::ALL)
+    }
+}
+
+/// A parse error, with the source underlined at `span`.
+fn error(source: &str, span: Range<usize>, message: String) -> anyhow::Error
+{
+    let start = source[..span.start].chars().count();
+    let len = source[span].chars().count().max(1);
+    anyhow::Error::msg(format!("{message}\n    {source}\n    {}{}", " ".repeat(start), "^".repeat(len)))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Token<'a>
+{
+    Num(f64),
+    /// A number followed by `i`, or `i` on its own.
+    Imag(f64),
+    Ident(&'a str),
+    Op(Op),
+    LParen,
+    RParen,
+    Eq
+}
+
+impl Display for Token<'_>
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        match self
+        {
+            Self::Num(x) => write!(f, "the number {x}"),
+            Self::Imag(x) => write!(f, "the number {x}i"),
+            Self::Ident(name) => write!(f, "'{name}'"),
+            Self::Op(op) => write!(f, "'{}'", op.symbol()),
+            Self::LParen => write!(f, "'('"),
+            Self::RParen => write!(f, "')'"),
+            Self::Eq => write!(f, "'='")
+        }
+    }
+}
+
+fn tokenize(source: &str) -> anyhow::Result<Vec<(Token<'_>, Range<usize>)>>
+{
+    let bytes = source.as_bytes();
+    let digits = |mut i: usize| {
+        while i < bytes.len() && bytes[i].is_ascii_digit()
+        {
+            i += 1
+        }
+        i
+    };
+    let word = |mut i: usize| {
+        while i < bytes.len() && (bytes[i].is_ascii_alphanumeric() || bytes[i] == b'_')
+        {
+            i += 1
+        }
+        i
+    };
+
+    let mut tokens = vec![];
+    let mut start = 0;
+    while let Some(ch) = source[start..].chars().next()
+    {
+        let (token, end) = match ch
+        {
+            _ if ch.is_whitespace() => {
+                start += ch.len_utf8();
+                continue
+            },
+            '+' => (Token::Op(Op::Add), start + 1),
+            '-' => (Token::Op(Op::Sub), start + 1),
+            '*' => (Token::Op(Op::Mul), start + 1),
+            '/' => (Token::Op(Op::Div), start + 1),
+            '^' => (Token::Op(Op::Pow), start + 1),
+            '(' => (Token::LParen, start + 1),
+            ')' => (Token::RParen, start + 1),
+            '=' => (Token::Eq, start + 1),
+            '0'..='9' | '.' => {
+                // <digits>[.<digits>][e[+-]<digits>][i]
+                let mut end = digits(start);
+                if bytes.get(end) == Some(&b'.')
+                {
+                    end = digits(end + 1)
+                }
+                if let Some(b'e' | b'E') = bytes.get(end)
+                {
+                    let sign = usize::from(matches!(bytes.get(end + 1), Some(b'+' | b'-')));
+                    if bytes.get(end + 1 + sign).is_some_and(u8::is_ascii_digit)
+                    {
+                        end = digits(end + 1 + sign)
+                    }
+                }
+                let x: f64 = source[start..end].parse()
+                    .map_err(|_| error(source, start..end, format!("Invalid number '{}'", &source[start..end])))?;
+                if (x as f32).is_infinite()
+                {
+                    return Err(error(source, start..end, format!("{} is out of range of f32", &source[start..end])))
+                }
+                match bytes.get(end)
+                {
+                    Some(b'i') if word(end) == end + 1 => (Token::Imag(x), end + 1),
+                    _ => (Token::Num(x), end)
+                }
+            },
+            'a'..='z' | 'A'..='Z' | '_' => {
+                let end = word(start);
+                match &source[start..end]
+                {
+                    "i" => (Token::Imag(1.0), end),
+                    name => (Token::Ident(name), end)
+                }
+            },
+            _ => return Err(error(source, start..start + ch.len_utf8(), format!("Unexpected character '{ch}'")))
+        };
+        tokens.push((token, start..end));
+        start = end;
+    }
+    Ok(tokens)
+}
+
+/// Recursive descent over the tokens, by precedence: `+ -`, then `* /`, then negation, then `^`, which is right
+/// associative.
+struct Parser<'a, 'b>
+{
+    source: &'a str,
+    tokens: &'b [(Token<'a>, Range<usize>)],
+    next: usize,
+    vars: &'b [Var]
+}
+
+impl<'a> Parser<'a, '_>
+{
+    fn peek(&self) -> Option<(Token<'a>, Range<usize>)>
+    {
+        self.tokens.get(self.next).cloned()
+    }
+
+    fn bump(&mut self) -> Option<(Token<'a>, Range<usize>)>
+    {
+        let token = self.peek();
+        self.next += 1;
+        token
+    }
+
+    /// Where the source ends, for errors about what's missing.
+    fn end(&self) -> Range<usize>
+    {
+        self.source.len()..self.source.len()
+    }
+
+    fn binary<N>(&mut self, ops: &[Op], mut operand: N) -> anyhow::Result<Expr>
+    where
+        N: FnMut(&mut Self) -> anyhow::Result<Expr>
+    {
+        let mut lhs = operand(self)?;
+        while let Some((Token::Op(op), _)) = self.peek()
+            && ops.contains(&op)
+        {
+            self.next += 1;
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(operand(self)?));
+        }
+        Ok(lhs)
+    }
+
+    fn expr(&mut self) -> anyhow::Result<Expr>
+    {
+        self.binary(&[Op::Add, Op::Sub], |parser| parser.binary(&[Op::Mul, Op::Div], Self::neg))
+    }
+
+    fn neg(&mut self) -> anyhow::Result<Expr>
+    {
+        match self.peek()
+        {
+            Some((Token::Op(Op::Sub), _)) => {
+                self.next += 1;
+                Ok(Expr::Neg(Box::new(self.neg()?)))
+            },
+            Some((Token::Op(Op::Add), _)) => {
+                self.next += 1;
+                self.neg()
+            },
+            _ => self.pow()
+        }
+    }
+
+    fn pow(&mut self) -> anyhow::Result<Expr>
+    {
+        let base = self.atom()?;
+        if let Some((Token::Op(Op::Pow), _)) = self.peek()
+        {
+            self.next += 1;
+            return Ok(Expr::Binary(Op::Pow, Box::new(base), Box::new(self.neg()?)))
+        }
+        Ok(base)
+    }
+
+    fn atom(&mut self) -> anyhow::Result<Expr>
+    {
+        let Some((token, span)) = self.bump()
+        else
+        {
+            return Err(error(self.source, self.end(), "Expected an operand at the end".to_string()))
+        };
+        match token
+        {
+            Token::Num(x) => Ok(Expr::Num(Complex::new(x, 0.0))),
+            Token::Imag(x) => Ok(Expr::Num(Complex::new(0.0, x))),
+            Token::LParen => {
+                let expr = self.expr()?;
+                self.close(span)?;
+                Ok(expr)
+            },
+            Token::Ident(name) => {
+                if let Some(func) = Func::ALL.into_iter().find(|func| func.name() == name)
+                {
+                    let Some((Token::LParen, paren)) = self.bump()
+                    else
+                    {
+                        return Err(error(self.source, span, format!("Expected '(' after {name}")))
+                    };
+                    let arg = self.expr()?;
+                    self.close(paren)?;
+                    return Ok(Expr::Call(func, Box::new(arg)))
+                }
+                match Var::ALL.into_iter().find(|var| var.name() == name)
+                {
+                    Some(var) if self.vars.contains(&var) => Ok(Expr::Var(var)),
+                    Some(_) => Err(error(self.source, span, format!("'{name}' can't be used here"))),
+                    None => {
+                        let known = self.vars.iter()
+                            .map(|var| var.name())
+                            .chain(Func::ALL.iter().map(|func| func.name()))
+                            .collect::<Vec<_>>()
+                            .join(", ");
+                        Err(error(self.source, span, format!("Unknown name '{name}', expected one of {known}")))
+                    }
+                }
+            },
+            token => Err(error(self.source, span, format!("Expected an operand, found {token}")))
+        }
+    }
+
+    /// Expects the `)` matching the `(` at `open`.
+    fn close(&mut self, open: Range<usize>) -> anyhow::Result<()>
+    {
+        match self.bump()
+        {
+            Some((Token::RParen, _)) => Ok(()),
+            Some((token, span)) => Err(error(self.source, span, format!("Expected ')', found {token}"))),
+            None => Err(error(self.source, open, "Unclosed '('".to_string()))
+        }
+    }
+}
+
+/// The `formula` module of `custom_formula.wgsl`, standing in for `lib/formula.wgsl`.
+pub fn formula_module(init: &Formula, step: &Formula) -> String
+{
+    format!("\
+#import global_bindings::globals;
+#import complex::{{cmul, cdiv, powc, cexp, clog, conj}}
+
+fn formula_init(c: vec2<f32>) -> vec2<f32>
+{{
+    return {};
+}}
+
+fn formula_step(z: vec2<f32>, c: vec2<f32>) -> vec2<f32>
+{{
+    return {};
+}}
+", init.wgsl(), step.wgsl())
+}
+
@@ -4,9 +4,9 @@ use num_complex::Complex;
 use num_traits::{Float, One, Zero};
 use winit::dpi::PhysicalSize;
 
-use crate::{MyFloat, app::InitView, f, fractal::{Fractal, dcdz}};
+use crate::{MyFloat, app::InitView, f, fractal::{pipeline, ShaderDefs, Fractal, dcdz, cpu::{Globals, colormap_trap, conj, escape, norm_sqr, smooth}}};
 
-use super::wgsl_bindgen::heighway_dragon;
+use super::wgsl_bindgen::{heighway_dragon, heighway_dragon_df};
 
 #[derive(Clone)]
 pub struct HeighwayDragon;
@@ -37,34 +37,26 @@ where
         }
     }
 
-    fn setup_render_pipeline(&self, device: &wgpu::Device, surface_format: wgpu::TextureFormat) -> wgpu::RenderPipeline
+    fn fs_main(&self, globals: &Globals<F>, pos: Complex<F>) -> [F; 4]
     {
-        // Create shader module from generated code
-        let shader = heighway_dragon::create_shader_module_embed_source(device);
-        
-        // Use generated pipeline layout
-        let pipeline_layout = heighway_dragon::create_pipeline_layout(device);
-        
-        // Use generated vertex entry with proper buffer layout
-        let vertex_entry = heighway_dragon::vs_main_entry(wgpu::VertexStepMode::Vertex);
-     
-        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some(Fractal::<F>::label(self)),
-            layout: Some(&pipeline_layout),
-            vertex: heighway_dragon::vertex_state(&shader, &vertex_entry),
-            fragment: Some(heighway_dragon::fragment_state(&shader, &heighway_dragon::fs_main_entry([
-                Some(wgpu::ColorTargetState {
-                    format: surface_format,
-                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
-                    write_mask: wgpu::ColorWrites::COLOR,
-                })
-            ]))),
-            primitive: wgpu::PrimitiveState::default(),
-            depth_stencil: None,
-            multisample: wgpu::MultisampleState::default(),
-            multiview_mask: None,
-            cache: None
-            // ... other pipeline state
-        })
+        let c = globals.coord(pos);
+        let z = globals.shift;
+        let r = Float::max(Float::max(F::one(), norm_sqr(z)), norm_sqr(c));
+
+        let n = globals.max_iterations().to_u32().unwrap();
+        let (i, z, trap) = escape(globals, z, r, n, |z| z - (c - z*globals.exp*conj(globals.exp)));
+        let m = smooth(i, z, globals.exp.norm());
+
+        colormap_trap(globals, z, m, trap, 3)
+    }
+
+    fn setup_render_pipeline(&self, device: &wgpu::Device, surface_format: wgpu::TextureFormat, defs: &ShaderDefs) -> anyhow::Result<wgpu::RenderPipeline>
+    {
+        pipeline!(heighway_dragon, device, surface_format, defs, Fractal::<F>::label(self))
+    }
+
+    fn setup_df_pipeline(&self, device: &wgpu::Device, surface_format: wgpu::TextureFormat, defs: &ShaderDefs) -> anyhow::Result<Option<wgpu::RenderPipeline>>
+    {
+        pipeline!(heighway_dragon_df, device, surface_format, defs, &format!("{} (double-float)", Fractal::<F>::label(self))).map(Some)
     }
 }
\ No newline at end of file
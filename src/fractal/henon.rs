@@ -1,16 +1,16 @@
 use num_complex::{Complex, ComplexFloat};
-use num_traits::Zero;
+use num_traits::{Float, Zero};
 use winit::dpi::PhysicalSize;
 
-use crate::{f, MyFloat, app::InitView, fractal::{Fractal, dcdz}};
+use crate::{f, MyFloat, app::InitView, fractal::{pipeline, ShaderDefs, Fractal, dcdz, cpu::{Globals, colormap_trap, escape, norm_sqr, powc, smooth}}};
 
 use super::wgsl_bindgen::henon;
 
 #[derive(Clone, Copy)]
 pub struct Henon
 {
-    a: f64,
-    b: f64
+    pub a: f64,
+    pub b: f64
 }
 
 impl Default for Henon
@@ -33,6 +33,11 @@ where
         "henon"
     }
 
+    fn params(&self) -> String
+    {
+        format!("a={},b={}", self.a, self.b)
+    }
+
     fn init_view(&self, _zoom: F, _win_size: PhysicalSize<u32>) -> InitView<F>
     {
         let Self { a, b } = self;
@@ -43,34 +48,23 @@ where
         }
     }
 
-    fn setup_render_pipeline(&self, device: &wgpu::Device, surface_format: wgpu::TextureFormat) -> wgpu::RenderPipeline
+    fn fs_main(&self, globals: &Globals<F>, pos: Complex<F>) -> [F; 4]
+    {
+        let z = globals.coord(pos);
+        let c = globals.shift;
+        let r = Float::max(Float::max(F::one(), norm_sqr(z)), norm_sqr(c));
+
+        let n = globals.max_iterations().to_u32().unwrap();
+        let (i, z, trap) = escape(globals, z, r, n, |z| Complex::new(F::one() + z.im, z.re*globals.shift.im)
+            - powc(Complex::from(z.re), globals.exp)*globals.shift.re
+        );
+        let m = smooth(i, z, globals.exp.norm());
+
+        colormap_trap(globals, z, m, trap, 3)
+    }
+
+    fn setup_render_pipeline(&self, device: &wgpu::Device, surface_format: wgpu::TextureFormat, defs: &ShaderDefs) -> anyhow::Result<wgpu::RenderPipeline>
     {
-        // Create shader module from generated code
-        let shader = henon::create_shader_module_embed_source(device);
-        
-        // Use generated pipeline layout
-        let pipeline_layout = henon::create_pipeline_layout(device);
-        
-        // Use generated vertex entry with proper buffer layout
-        let vertex_entry = henon::vs_main_entry(wgpu::VertexStepMode::Vertex);
-     
-        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some(Fractal::<F>::label(self)),
-            layout: Some(&pipeline_layout),
-            vertex: henon::vertex_state(&shader, &vertex_entry),
-            fragment: Some(henon::fragment_state(&shader, &henon::fs_main_entry([
-                Some(wgpu::ColorTargetState {
-                    format: surface_format,
-                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
-                    write_mask: wgpu::ColorWrites::COLOR,
-                })
-            ]))),
-            primitive: wgpu::PrimitiveState::default(),
-            depth_stencil: None,
-            multisample: wgpu::MultisampleState::default(),
-            multiview_mask: None,
-            cache: None
-            // ... other pipeline state
-        })
+        pipeline!(henon, device, surface_format, defs, Fractal::<F>::label(self))
     }
 }
\ No newline at end of file
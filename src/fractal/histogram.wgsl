@@ -0,0 +1,94 @@
+// Builds the histogram and CDF of the smooth escape counts written by the first pass of histogram coloring, see
+// equalize.wgsl for the second
+
+#import colormap::escape_value
+
+const BINS: u32 = 1024;
+
+struct Histogram {
+    // Bits of the least and greatest escape count, which order like the counts since they're non-negative
+    lowest: atomic<u32>,
+    highest: atomic<u32>,
+    total: atomic<u32>,
+    bins: array<atomic<u32>, BINS>,
+};
+
+struct Cdf {
+    lowest: f32,
+    highest: f32,
+    // The fraction of escaped pixels up to the end of each bin
+    values: array<f32, BINS>,
+};
+
+// Negative where the orbit never escaped
+@group(0) @binding(0)
+var escape: texture_2d<f32>;
+@group(0) @binding(1)
+var<storage, read_write> histogram: Histogram;
+@group(0) @binding(2)
+var<storage, read_write> cdf: Cdf;
+
+@compute @workgroup_size(1)
+fn clear()
+{
+    atomicStore(&histogram.lowest, 0xffffffffu);
+    atomicStore(&histogram.highest, 0u);
+    atomicStore(&histogram.total, 0u);
+    for(var k: u32 = 0; k < BINS; k++)
+    {
+        atomicStore(&histogram.bins[k], 0u);
+    }
+}
+
+fn escape_at(id: vec3<u32>) -> f32
+{
+    let size = textureDimensions(escape);
+    if id.x >= size.x || id.y >= size.y
+    {
+        return -1.0;
+    }
+    return escape_value(textureLoad(escape, id.xy, 0));
+}
+
+@compute @workgroup_size(8, 8)
+fn bounds(@builtin(global_invocation_id) id: vec3<u32>)
+{
+    let m = escape_at(id);
+    if m >= 0.0
+    {
+        atomicMin(&histogram.lowest, bitcast<u32>(m));
+        atomicMax(&histogram.highest, bitcast<u32>(m));
+    }
+}
+
+fn bin(m: f32, lowest: f32, highest: f32) -> u32
+{
+    return min(u32((m - lowest)/max(highest - lowest, 1.1920929e-7)*f32(BINS)), BINS - 1);
+}
+
+@compute @workgroup_size(8, 8)
+fn count(@builtin(global_invocation_id) id: vec3<u32>)
+{
+    let m = escape_at(id);
+    if m >= 0.0
+    {
+        let lowest = bitcast<f32>(atomicLoad(&histogram.lowest));
+        let highest = bitcast<f32>(atomicLoad(&histogram.highest));
+        atomicAdd(&histogram.bins[bin(m, lowest, highest)], 1u);
+        atomicAdd(&histogram.total, 1u);
+    }
+}
+
+@compute @workgroup_size(1)
+fn accumulate()
+{
+    let total = max(f32(atomicLoad(&histogram.total)), 1.0);
+    cdf.lowest = bitcast<f32>(atomicLoad(&histogram.lowest));
+    cdf.highest = bitcast<f32>(atomicLoad(&histogram.highest));
+    var sum: u32 = 0;
+    for(var k: u32 = 0; k < BINS; k++)
+    {
+        sum += atomicLoad(&histogram.bins[k]);
+        cdf.values[k] = f32(sum)/total;
+    }
+}
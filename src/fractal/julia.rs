@@ -1,15 +1,16 @@
 use num_complex::{Complex, ComplexFloat};
-use num_traits::Zero;
+use num_traits::{Float, Zero};
 use winit::dpi::PhysicalSize;
 
-use crate::{f, MyFloat, app::InitView, fractal::{Fractal, dcdz}};
+use crate::{f, MyFloat, app::InitView, fractal::{pipeline, ShaderDefs, Fractal, ReferenceOrbit, dcdz, cpu::{Globals, colormap_de, dpowc, escape_derivative, norm_sqr, powc, smooth}}};
 
-use super::wgsl_bindgen::julia;
+use super::wgsl_bindgen::{julia, julia_perturbation};
 
 #[derive(Clone, Copy)]
 pub struct Julia
 {
-    c: Complex<f64>
+    pub c: Complex<f64>,
+    pub exp: Complex<f64>
 }
 
 impl Julia
@@ -17,13 +18,15 @@ impl Julia
     pub fn dendrite() -> Self
     {
         Self {
-            c: Complex { re: 0.0, im: -1.0 }
+            c: Complex { re: 0.0, im: -1.0 },
+            exp: Complex { re: 2.0, im: 0.0 }
         }
     }
     pub fn clover() -> Self
     {
         Self {
-            c: Complex { re: 1.0/4.0, im: 0.0 }
+            c: Complex { re: 1.0/4.0, im: 0.0 },
+            exp: Complex { re: 2.0, im: 0.0 }
         }
     }
 }
@@ -37,43 +40,72 @@ where
         "julia"
     }
 
+    fn params(&self) -> String
+    {
+        format!("c={},exp={}", self.c, self.exp)
+    }
+
     fn init_view(&self, _zoom: F, _win_size: PhysicalSize<u32>) -> InitView<F>
     {
         InitView {
             shift: Complex::new(f!(self.c.re), f!(self.c.im)),
-            exp: Complex::new(f!(2.0), F::zero()),
+            exp: Complex::new(f!(self.exp.re), f!(self.exp.im)),
             ..Default::default()
         }
     }
 
-    fn setup_render_pipeline(&self, device: &wgpu::Device, surface_format: wgpu::TextureFormat) -> wgpu::RenderPipeline
+    fn fs_main(&self, globals: &Globals<F>, pos: Complex<F>) -> [F; 4]
+    {
+        let (i, z, der, trap) = iterate(globals, pos);
+        let m = smooth(i, z, globals.exp.norm());
+
+        colormap_de(globals, z, der, m, trap, 3)
+    }
+
+    fn escape_count(&self, globals: &Globals<F>, pos: Complex<F>) -> Option<u32>
+    {
+        Some(iterate(globals, pos).0)
+    }
+
+    fn distance_estimate(&self) -> bool
+    {
+        true
+    }
+
+    fn setup_render_pipeline(&self, device: &wgpu::Device, surface_format: wgpu::TextureFormat, defs: &ShaderDefs) -> anyhow::Result<wgpu::RenderPipeline>
     {
-        // Create shader module from generated code
-        let shader = julia::create_shader_module_embed_source(device);
-        
-        // Use generated pipeline layout
-        let pipeline_layout = julia::create_pipeline_layout(device);
-        
-        // Use generated vertex entry with proper buffer layout
-        let vertex_entry = julia::vs_main_entry(wgpu::VertexStepMode::Vertex);
-     
-        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some(Fractal::<F>::label(self)),
-            layout: Some(&pipeline_layout),
-            vertex: julia::vertex_state(&shader, &vertex_entry),
-            fragment: Some(julia::fragment_state(&shader, &julia::fs_main_entry([
-                Some(wgpu::ColorTargetState {
-                    format: surface_format,
-                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
-                    write_mask: wgpu::ColorWrites::COLOR,
-                })
-            ]))),
-            primitive: wgpu::PrimitiveState::default(),
-            depth_stencil: None,
-            multisample: wgpu::MultisampleState::default(),
-            multiview_mask: None,
-            cache: None
-            // ... other pipeline state
-        })
+        pipeline!(julia, device, surface_format, defs, Fractal::<F>::label(self))
     }
-}
\ No newline at end of file
+
+    fn reference_orbit(&self, globals: &Globals<F>, point: Complex<F>) -> Option<ReferenceOrbit<F>>
+    {
+        let c = globals.shift;
+        Some(ReferenceOrbit::iterate(globals, point, point, c, |_, zp| zp + c))
+    }
+
+    fn setup_perturbation_pipeline(&self, device: &wgpu::Device, surface_format: wgpu::TextureFormat, defs: &ShaderDefs) -> anyhow::Result<Option<wgpu::RenderPipeline>>
+    {
+        pipeline!(julia_perturbation, device, surface_format, defs, &format!("{} (perturbation)", Fractal::<F>::label(self))).map(Some)
+    }
+}
+
+fn iterate<F>(globals: &Globals<F>, pos: Complex<F>) -> (u32, Complex<F>, Complex<F>, F)
+where
+    F: MyFloat
+{
+    let z = globals.coord(pos);
+    let c = globals.shift;
+    let r = Float::max(Float::max(F::one(), norm_sqr(z)), norm_sqr(c));
+
+    let dc = dcdz::<F, F>(Some(z))/globals.zoom;
+    let n = globals.max_iterations().to_u32().unwrap();
+    escape_derivative(
+        globals,
+        z,
+        Complex::from(F::one()/globals.zoom - dc),
+        Complex::from(dc),
+        r,
+        n,
+        |z| (powc(z, globals.exp) + c, dpowc(z, globals.exp))
+    )
+}
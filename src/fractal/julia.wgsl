@@ -1,6 +1,6 @@
-#import global_bindings::{GlobalUniforms, VertexInput, globals, max_iterations, view_radius, epsilon};
-#import colormap::colormap3;
-#import complex::{cmul, cis, norm_sqr, norm, powc}
+#import global_bindings::{GlobalUniforms, VertexInput, globals, max_iterations, view_radius, epsilon, bailout};
+#import colormap::{colormap_de, trap_distance, TRAP_FAR};
+#import complex::{cmul, cis, norm_sqr, norm, powc, dpowc}
 
 @vertex
 fn vs_main(in: VertexInput) -> @builtin(position) vec4<f32>
@@ -23,14 +23,23 @@ fn fs_main(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32>
     var z = cmul(pos/globals.zoom, cis(globals.rot)) - globals.center;
     var c = globals.shift;
     let r = max(max(1.0, norm_sqr(z)), norm_sqr(c));
+    // The derivative of z in the pixel position, for the distance estimate
+    var der = vec2(1.0/globals.zoom, 0.0);
+    let dcdpos = vec2(0.0, 0.0);
     
+    var trap = TRAP_FAR;
     var n = max_iterations();
     var i: u32 = 0;
-    for(; i < u32(n) && norm_sqr(z) < r*4.0; i++)
+    for(; i < u32(n) && norm_sqr(z) < r*bailout(); i++)
     {
+        if globals.distance != 0
+        {
+            der = cmul(dpowc(z, globals.exp), der) + dcdpos;
+        }
         z = powc(z, globals.exp) + c;
+        trap = min(trap, trap_distance(z));
     }
     let m = f32(i) - log(log(norm(z)))/log(norm(globals.exp));
 
-    return colormap3(z, m);
+    return colormap_de(z, der, m, trap, 3);
 }
\ No newline at end of file
@@ -0,0 +1,57 @@
+#import global_bindings::{GlobalUniforms, VertexInput, globals, max_iterations, bailout};
+#import colormap::{colormap_de, trap_distance, TRAP_FAR};
+#import complex::{cmul, norm_sqr, norm, dpowc}
+#import perturbation::{reference, orbit, powc_delta, reference_delta, rebase}
+
+@vertex
+fn vs_main(in: VertexInput) -> @builtin(position) vec4<f32>
+{
+    let corner = in.vertex_index % 3;
+    let n = in.vertex_index/3 % 2 == 1;
+    let pos = vec2(
+        f32(u32(corner == 1 || (corner == 0 && n))*globals.window_size.x) - f32(globals.window_size.x)/2.0,
+        f32(u32(corner == 2 || (corner == 0 && n))*globals.window_size.y) - f32(globals.window_size.y)/2.0
+    );
+
+    return vec4<f32>(pos, 0.0, 1.0);
+}
+
+@fragment
+fn fs_main(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32>
+{
+    let pos = position.xy/position.w - vec2(f32(globals.window_size.x), f32(globals.window_size.y))/2.0;
+
+    var dz = reference_delta(pos, globals.zoom, globals.rot);
+    let dc = vec2(0.0, 0.0);
+    let c = reference.c;
+    var z = orbit[0].xy + dz;
+    let r = max(max(1.0, norm_sqr(z)), norm_sqr(c));
+    // The derivative of z in the pixel position, for the distance estimate
+    var der = vec2(1.0/globals.zoom, 0.0);
+    let dcdpos = vec2(0.0, 0.0);
+
+    var trap = TRAP_FAR;
+    let n = u32(max_iterations());
+    var i: u32 = 0;
+    var m: u32 = 0;
+    for(; i < n && norm_sqr(z) < r*bailout(); i++)
+    {
+        if globals.distance != 0
+        {
+            der = cmul(dpowc(z, globals.exp), der) + dcdpos;
+        }
+        let zm = orbit[m];
+        dz = powc_delta(zm.xy, zm.zw, dz, globals.exp) + dc;
+        m++;
+        z = orbit[m].xy + dz;
+        if rebase(z, dz, m)
+        {
+            dz = z - orbit[0].xy;
+            m = 0;
+        }
+        trap = min(trap, trap_distance(z));
+    }
+    let m_smooth = f32(i) - log(log(norm(z)))/log(norm(globals.exp));
+
+    return colormap_de(z, der, m_smooth, trap, 3);
+}
\ No newline at end of file
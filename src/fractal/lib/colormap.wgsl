@@ -1,8 +1,14 @@
-#import global_bindings::{wrap, max_iterations};
+#import global_bindings::{globals, wrap, max_iterations};
 #import color::hsl2rgb;
 #import complex::{norm, arg};
 #import consts::{PI, TAU};
 
+// How far out from the axes Pickover stalks show, in the plane
+const STALK_WIDTH: f32 = 0.05;
+// What the closest approach to the orbit trap starts from, as the start of the orbit doesn't count, e.g. 0 in the
+// Mandelbrot set
+const TRAP_FAR: f32 = 1e6;
+
 fn colormap4(z: vec2<f32>) -> vec4<f32>
 {
     let light = 0.25 + wrap(z.x/TAU + 0.25, 1.0)*0.5;
@@ -14,6 +20,151 @@ fn colormap4(z: vec2<f32>) -> vec4<f32>
     );
 }
 
+// Colors by the colormap picked at runtime, or the fractal's `own` if none is
+fn colormap(z: vec2<f32>, i: f32, own: u32) -> vec4<f32>
+{
+#ifdef ESCAPE_VALUE
+    // The smooth escape count for histogram coloring to rank, negative where the orbit never escaped, as the top 24
+    // bits of the float since not every backend can draw to float textures
+    let m = select(-1.0, i, i < max_iterations());
+    return vec4(unpack4x8unorm(bitcast<u32>(m) >> 8).rgb, 1.0);
+#endif
+    var n = globals.colormap;
+    if n == 0
+    {
+        n = own;
+    }
+    switch n
+    {
+        case 1: { return colormap1(z, i); }
+        case 2: { return colormap2(z, i); }
+        case 4: { return colormap4(z); }
+        case 5: { return colormap5(i); }
+        default: { return colormap3(z, i); }
+    }
+}
+
+// How far `z` is from the orbit trap, or 0 if there's none
+fn trap_distance(z: vec2<f32>) -> f32
+{
+    let d = z - globals.trap_pos;
+    switch globals.trap
+    {
+        case 1: {
+            return norm(d);
+        }
+        case 2: {
+            return abs(d.y);
+        }
+        case 3, 5: {
+            return min(abs(d.x), abs(d.y));
+        }
+        case 4: {
+            return abs(norm(d) - 1.0);
+        }
+        default: {
+            return 0.0;
+        }
+    }
+}
+
+// Colors like `colormap`, or by the palette at `trap`, the closest the orbit came to the orbit trap, if there is one
+fn colormap_trap(z: vec2<f32>, i: f32, trap: f32, own: u32) -> vec4<f32>
+{
+    let color = colormap(z, i, own);
+#ifdef ESCAPE_VALUE
+    return color;
+#endif
+    if globals.trap == 0
+    {
+        return color;
+    }
+    let trapped = vec4(palette(trap + globals.palette_offset + globals.palette_speed*globals.time), 0.8);
+    if globals.trap == 5
+    {
+        // Stalks only show where the orbit came close to the axes, over the colormap
+        return mix(trapped, color, smoothstep(0.0, STALK_WIDTH, trap));
+    }
+    return trapped;
+}
+
+// Colors like `colormap_trap`, then by the distance estimate from `der`, the derivative of `z` in the pixel position, if
+// `globals.distance` asks for it
+fn colormap_de(z: vec2<f32>, der: vec2<f32>, i: f32, trap: f32, own: u32) -> vec4<f32>
+{
+    let color = colormap_trap(z, i, trap, own);
+#ifdef ESCAPE_VALUE
+    return color;
+#endif
+    if globals.distance == 0
+    {
+        return color;
+    }
+    let escaped = i < max_iterations();
+    // Roughly how many pixels away the boundary is
+    let d = 0.5*norm(z)*log(norm(z))/norm(der);
+    if globals.distance == 1
+    {
+        return vec4(vec3(select(1.0, smoothstep(0.5, 1.0, d), escaped)), color.a);
+    }
+    if !escaped
+    {
+        return color;
+    }
+    switch globals.distance
+    {
+        case 2: {
+            return vec4(color.rgb*sqrt(clamp(d/8.0, 0.0, 1.0)), color.a);
+        }
+        default: {
+            // The share of the pixel outside the boundary keeps the color, the rest is colored as the inside
+            let inside = colormap_trap(z, max_iterations(), trap, own);
+            return mix(inside, color, clamp(d, 0.0, 1.0));
+        }
+    }
+}
+
+// The smooth escape count drawn by `colormap` for histogram coloring
+fn escape_value(color: vec4<f32>) -> f32
+{
+    return bitcast<f32>(pack4x8unorm(vec4(color.rgb, 0.0)) << 8);
+}
+
+// The palette gradient at `t`, interpolating between the stops around it and wrapping around from the last to the first
+fn palette(x: f32) -> vec3<f32>
+{
+    let t = wrap(x, 1.0);
+    let n = globals.palette_len;
+    var prev = globals.palette[n - 1];
+    prev.w -= 1.0;
+    for(var k: u32 = 0; k <= n; k++)
+    {
+        var next = globals.palette[k % n];
+        next.w += f32(k/n);
+        if t < next.w
+        {
+            let s = clamp((t - prev.w)/max(next.w - prev.w, 1.1920929e-7), 0.0, 1.0);
+            return mix(prev.rgb, next.rgb, s);
+        }
+        prev = next;
+    }
+    return prev.rgb;
+}
+
+fn colormap5(i: f32) -> vec4<f32>
+{
+    let t = i/max_iterations();
+    if !(t < 1.0)
+    {
+        return vec4(0.0, 0.0, 0.0, 0.8);
+    }
+
+    return vec4(
+        palette(t + globals.palette_offset + globals.palette_speed*globals.time),
+        0.8
+    );
+}
+
 fn colormap3(z: vec2<f32>, i: f32) -> vec4<f32>
 {
     let t = clamp(i/max_iterations() % 1.0, 0.0, 1.0);
@@ -52,15 +52,23 @@ fn cexp(x: vec2<f32>) -> vec2<f32>
     return exp(x.x)*cis(x.y);
 }
 
+// Zero to any power is zero, as is anything small enough that its norm squared underflows, which would take the log to
+// infinity and the result to NaN
 fn powc(x: vec2<f32>, y: vec2<f32>) -> vec2<f32>
 {
-    if x.x == 0.0 && x.y == 0.0
+    if norm_sqr(x) == 0.0
     {
         return vec2(0.0, 0.0);
     }
     return cexp(cmul(y, clog(x)));
 }
 
+// The derivative of `powc(x, y)` in `x`
+fn dpowc(x: vec2<f32>, y: vec2<f32>) -> vec2<f32>
+{
+    return cmul(y, powc(x, y - vec2(1.0, 0.0)));
+}
+
 fn croot(c: vec2<f32>) -> vec2<f32>
 {
     var b = bitcast<vec2<u32>>(c);
@@ -0,0 +1,154 @@
+#import global_bindings::globals;
+#import global_bindings_df::{globals_df, barrier};
+#import complex::{cis, powc}
+
+// Double-float arithmetic: a value is the unevaluated sum of two f32s vec2(hi, lo), which gives about 48 bits of
+// mantissa. Complex numbers are vec4(re.hi, re.lo, im.hi, im.lo).
+
+fn df(x: f32) -> vec2<f32>
+{
+    return vec2(x, 0.0);
+}
+
+fn two_sum(a: f32, b: f32) -> vec2<f32>
+{
+    let s = barrier(a + b);
+    let v = s - a;
+    return vec2(s, (a - (s - v)) + (b - v));
+}
+
+fn quick_two_sum(a: f32, b: f32) -> vec2<f32>
+{
+    let s = barrier(a + b);
+    return vec2(s, b - (s - a));
+}
+
+// Veltkamp split into two halves of 12 bits each, so their products are exact
+fn split(a: f32) -> vec2<f32>
+{
+    let t = barrier(4097.0*a);
+    let hi = t - barrier(t - a);
+    return vec2(hi, a - hi);
+}
+
+fn two_prod(a: f32, b: f32) -> vec2<f32>
+{
+    let p = barrier(a*b);
+    let a_split = split(a);
+    let b_split = split(b);
+    let e = ((a_split.x*b_split.x - p) + a_split.x*b_split.y + a_split.y*b_split.x) + a_split.y*b_split.y;
+    return vec2(p, e);
+}
+
+fn df_add(a: vec2<f32>, b: vec2<f32>) -> vec2<f32>
+{
+    let s = two_sum(a.x, b.x);
+    let t = two_sum(a.y, b.y);
+    let u = quick_two_sum(s.x, s.y + t.x);
+    return quick_two_sum(u.x, u.y + t.y);
+}
+
+fn df_sub(a: vec2<f32>, b: vec2<f32>) -> vec2<f32>
+{
+    return df_add(a, -b);
+}
+
+fn df_mul(a: vec2<f32>, b: vec2<f32>) -> vec2<f32>
+{
+    let p = two_prod(a.x, b.x);
+    return quick_two_sum(p.x, p.y + (a.x*b.y + a.y*b.x));
+}
+
+fn df_div(a: vec2<f32>, b: vec2<f32>) -> vec2<f32>
+{
+    let q1 = a.x/b.x;
+    let r1 = df_sub(a, df_mul(b, df(q1)));
+    let q2 = r1.x/b.x;
+    let r2 = df_sub(r1, df_mul(b, df(q2)));
+    let q3 = r2.x/b.x;
+    return df_add(quick_two_sum(q1, q2), df(q3));
+}
+
+fn cdf(z: vec2<f32>) -> vec4<f32>
+{
+    return vec4(z.x, 0.0, z.y, 0.0);
+}
+
+// Rounds back to a single precision complex number
+fn cdf_to_f32(z: vec4<f32>) -> vec2<f32>
+{
+    return vec2(z.x + z.y, z.z + z.w);
+}
+
+fn cadd_df(lhs: vec4<f32>, rhs: vec4<f32>) -> vec4<f32>
+{
+    return vec4(df_add(lhs.xy, rhs.xy), df_add(lhs.zw, rhs.zw));
+}
+
+fn csub_df(lhs: vec4<f32>, rhs: vec4<f32>) -> vec4<f32>
+{
+    return vec4(df_sub(lhs.xy, rhs.xy), df_sub(lhs.zw, rhs.zw));
+}
+
+fn conj_df(z: vec4<f32>) -> vec4<f32>
+{
+    return vec4(z.xy, -z.zw);
+}
+
+fn cmul_df(lhs: vec4<f32>, rhs: vec4<f32>) -> vec4<f32>
+{
+    return vec4(
+        df_sub(df_mul(lhs.xy, rhs.xy), df_mul(lhs.zw, rhs.zw)),
+        df_add(df_mul(lhs.xy, rhs.zw), df_mul(lhs.zw, rhs.xy))
+    );
+}
+
+fn cscale_df(z: vec4<f32>, s: vec2<f32>) -> vec4<f32>
+{
+    return vec4(df_mul(z.xy, s), df_mul(z.zw, s));
+}
+
+fn norm_sqr_df(z: vec4<f32>) -> vec2<f32>
+{
+    return df_add(df_mul(z.xy, z.xy), df_mul(z.zw, z.zw));
+}
+
+fn cdiv_df(lhs: vec4<f32>, rhs: vec4<f32>) -> vec4<f32>
+{
+    let n = norm_sqr_df(rhs);
+    let z = cmul_df(lhs, conj_df(rhs));
+    return vec4(df_div(z.xy, n), df_div(z.zw, n));
+}
+
+// Integer real exponents are exact by repeated squaring, anything else only has single precision
+fn powc_df(x: vec4<f32>, y: vec2<f32>) -> vec4<f32>
+{
+    if y.y != 0.0 || y.x != round(y.x) || abs(y.x) > 64.0
+    {
+        return cdf(powc(cdf_to_f32(x), y));
+    }
+    var base = x;
+    var result = cdf(vec2(1.0, 0.0));
+    var n = u32(abs(y.x));
+    while n > 0
+    {
+        if n % 2 == 1
+        {
+            result = cmul_df(result, base);
+        }
+        base = cmul_df(base, base);
+        n /= 2;
+    }
+    if y.x < 0.0
+    {
+        return cdiv_df(cdf(vec2(1.0, 0.0)), result);
+    }
+    return result;
+}
+
+// The point in the plane under a pixel, like cmul(pos/globals.zoom, cis(globals.rot)) - globals.center
+fn coord_df(pos: vec2<f32>) -> vec4<f32>
+{
+    let scaled = vec4(df_div(df(pos.x), globals_df.zoom), df_div(df(pos.y), globals_df.zoom));
+    return csub_df(cmul_df(scaled, cdf(cis(globals.rot))), globals_df.center);
+}
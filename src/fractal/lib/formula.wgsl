@@ -0,0 +1,13 @@
+// Stands in for the module generated from the formula of a custom fractal, see `fractal/formula.rs`
+#import global_bindings::globals;
+#import complex::{cmul, cdiv, powc, cexp, clog, conj}
+
+fn formula_init(c: vec2<f32>) -> vec2<f32>
+{
+    return globals.shift;
+}
+
+fn formula_step(z: vec2<f32>, c: vec2<f32>) -> vec2<f32>
+{
+    return powc(z, globals.exp) + c;
+}
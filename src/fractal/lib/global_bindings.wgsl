@@ -7,6 +7,20 @@ struct GlobalUniforms {
     rot: f32,
     exp: vec2<f32>,
     shift: vec2<f32>,
+    // 0 for the fractal's own colormap, 1 to 4 for those in colormap.wgsl, or 5 for the palette
+    colormap: u32,
+    // Periods of the palette it's shifted by, and shifted by per second of time
+    palette_offset: f32,
+    palette_speed: f32,
+    palette_len: u32,
+    // 0 to leave escape time fractals be, or else 1 for boundary lines, 2 for shading or 3 for antialiasing by distance
+    distance: u32,
+    // 0 for no orbit trap, or else 1 for a point, 2 for a line, 3 for a cross, 4 for a circle or 5 for Pickover stalks
+    trap: u32,
+    // Where the orbit trap is, in the plane
+    trap_pos: vec2<f32>,
+    // The color of each stop of the palette, with its position in w
+    palette: array<vec4<f32>, 64>,
 };
 
 struct VertexInput {
@@ -26,6 +40,15 @@ fn max_iterations() -> f32
 {
     return f32(globals.max_iterations)*max(1.0, log(globals.zoom));
 }
+// The squared radius orbits escape beyond, relative to how far out they start
+fn bailout() -> f32
+{
+#ifdef ESCAPE_RADIUS
+    return f32(#{ESCAPE_RADIUS})*f32(#{ESCAPE_RADIUS});
+#else
+    return 4.0;
+#endif
+}
 fn view_radius() -> f32
 {
     return 100.0;
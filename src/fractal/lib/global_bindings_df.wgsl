@@ -0,0 +1,17 @@
+// The parts of GlobalUniforms that run out of precision when zooming, split into double-floats
+struct GlobalUniformsDf {
+    center: vec4<f32>,
+    zoom: vec2<f32>,
+    // Always 1.0, see barrier
+    one: f32,
+};
+
+@group(1) @binding(0)
+var<uniform> globals_df: GlobalUniformsDf;
+
+// Shader compilers are free to simplify (a + b) - a to b, which would cancel out the rounding errors double-float
+// arithmetic depends on. Multiplying by a value only known at runtime keeps them from doing so.
+fn barrier(x: f32) -> f32
+{
+    return x*globals_df.one;
+}
@@ -0,0 +1,90 @@
+#import complex::{cmul, cdiv, cis, norm_sqr, arg, powc}
+
+struct Reference {
+    c: vec2<f32>,
+    offset: vec2<f32>,
+    len: u32,
+};
+
+@group(1) @binding(0)
+var<uniform> reference: Reference;
+
+// Reference orbit as (Z, Z^exp) pairs, computed on the CPU in full precision
+@group(1) @binding(1)
+var<storage, read> orbit: array<vec4<f32>>;
+
+fn log1p(x: f32) -> f32
+{
+    let u = 1.0 + x;
+    if u == 1.0
+    {
+        return x;
+    }
+    return log(u)*x/(u - 1.0);
+}
+
+fn expm1(x: f32) -> f32
+{
+    let u = exp(x);
+    if u == 1.0
+    {
+        return x;
+    }
+    if u - 1.0 == -1.0
+    {
+        return -1.0;
+    }
+    return (u - 1.0)*x/log(u);
+}
+
+fn clog1p(x: vec2<f32>) -> vec2<f32>
+{
+    return vec2(
+        log1p(2.0*x.x + norm_sqr(x))/2.0,
+        atan2(x.y, 1.0 + x.x)
+    );
+}
+
+fn cexpm1(x: vec2<f32>) -> vec2<f32>
+{
+    let s = sin(x.y/2.0);
+    return vec2(
+        expm1(x.x)*cos(x.y) - 2.0*s*s,
+        exp(x.x)*sin(x.y)
+    );
+}
+
+// (Z + d)^p - Z^p without catastrophic cancellation, given zp = Z^p
+fn powc_delta(z: vec2<f32>, zp: vec2<f32>, d: vec2<f32>, p: vec2<f32>) -> vec2<f32>
+{
+    if p.x == 2.0 && p.y == 0.0
+    {
+        return cmul(2.0*z + d, d);
+    }
+    if z.x == 0.0 && z.y == 0.0
+    {
+        return powc(d, p);
+    }
+    let w = cdiv(d, z);
+    let l = clog1p(w);
+    // Large deltas don't cancel, and when log(Z) + log(1 + w) lands on another branch than log(Z + d) (off by 2π),
+    // the result must still match powc
+    if norm_sqr(w) > 0.0625 || abs(arg(z) + l.y - arg(z + d)) > 1.0
+    {
+        return powc(z + d, p) - zp;
+    }
+    return cmul(zp, cexpm1(cmul(p, l)));
+}
+
+// Offset of a pixel from the reference point
+fn reference_delta(pos: vec2<f32>, zoom: f32, rot: f32) -> vec2<f32>
+{
+    return cmul(pos/zoom, cis(rot)) + reference.offset;
+}
+
+// Whether to rebase onto the start of the reference orbit: either the orbit has run out, or the pixel came closer to the
+// start of the orbit than to the reference, which is when the delta would lose precision (a glitch)
+fn rebase(z: vec2<f32>, dz: vec2<f32>, m: u32) -> bool
+{
+    return m + 1 >= reference.len || norm_sqr(z - orbit[0].xy) < norm_sqr(dz);
+}
@@ -0,0 +1,187 @@
+use core::{cmp::Ordering, fmt::{self, Display}};
+
+use num_complex::Complex;
+use num_traits::{Float, Zero};
+use serde::{Deserialize, Serialize};
+
+use crate::{MyFloat, NEWTON_MU, NEWTON_N, f, fractal::{Fractal, cpu::{Globals, norm, norm_sqr}}};
+
+/// An iteration of the orbit of a parameter `c`, with derivatives for Newton's method.
+#[derive(Debug, Clone, Copy)]
+pub struct OrbitPoint<F>
+where
+    F: MyFloat
+{
+    pub z: Complex<F>,
+    /// `∂z/∂c`
+    pub dc: Complex<F>,
+    /// The derivative of the step at `z`, for fractals that iterate a map of one variable.
+    pub dz: Option<Complex<F>>
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Target
+{
+    /// The center of a hyperbolic component, where the start of the orbit is periodic.
+    Nucleus,
+    /// Where the orbit lands on a repelling cycle after a few iterations.
+    Misiurewicz
+}
+
+/// A point refined by [`locate`].
+#[derive(Debug, Clone, Copy)]
+pub struct Located<F>
+where
+    F: MyFloat
+{
+    pub point: Complex<F>,
+    /// Iterations before the orbit is periodic, counted from the start of the orbit, so zero for nuclei and e.g. 2 for
+    /// `i` in the Mandelbrot set.
+    pub preperiod: usize,
+    pub period: usize,
+    /// The multiplier of the cycle, zero at a nucleus and outside the unit circle at a Misiurewicz point.
+    pub multiplier: Option<Complex<F>>,
+    /// The atom size estimate of the hyperbolic component, about how much smaller it is than the main cardioid.
+    pub size: Option<F>
+}
+
+impl<F> Display for Located<F>
+where
+    F: MyFloat
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        let Self { point, preperiod, period, multiplier, size } = self;
+        match preperiod
+        {
+            0 => write!(f, "Nucleus of period {period} at {point}")?,
+            _ => write!(f, "Misiurewicz point of preperiod {preperiod} and period {period} at {point}")?
+        }
+        if let Some(multiplier) = multiplier
+        {
+            write!(f, ", multiplier {multiplier}")?
+        }
+        if let Some(size) = size
+        {
+            write!(f, ", size {size}")?
+        }
+        Ok(())
+    }
+}
+
+/// Iterates a map of one variable from `z0`, with `step` giving `(f(z), ∂f/∂z, ∂f/∂c)`.
+pub fn parameter_orbit<F, S>(z0: Complex<F>, n: usize, step: S) -> Vec<OrbitPoint<F>>
+where
+    F: MyFloat,
+    S: Fn(Complex<F>) -> (Complex<F>, Complex<F>, Complex<F>)
+{
+    let mut z = z0;
+    let mut dc = Complex::zero();
+    (0..n).map(|_| {
+            let (next, dz, dfdc) = step(z);
+            let point = OrbitPoint { z, dc, dz: Some(dz) };
+            dc = dz*dc + dfdc;
+            z = next;
+            point
+        })
+        .collect()
+}
+
+/// Refines `point` in the parameter plane to the nucleus or Misiurewicz point the orbit nearly lands on, by
+/// [`NEWTON_N`] steps of Newton's method at most.
+pub fn locate<Z, F>(fractal: &Z, globals: &Globals<F>, point: Complex<F>, target: Target) -> anyhow::Result<Located<F>>
+where
+    Z: Fractal<F>,
+    F: MyFloat
+{
+    let n = globals.max_iterations().to_usize().unwrap();
+    let orbit = |c: Complex<F>, n: usize| fractal.parameter_orbit(globals, c, n)
+        .ok_or(anyhow::Error::msg(format!("Can't locate points in {}, it's not iterated in parameter space", fractal.label())));
+
+    // How close the orbit comes to repeating, before it escapes
+    let mut escaping = orbit(point, n)?;
+    let r = escaping.iter()
+        .take(1)
+        .fold(Float::max(F::one(), norm_sqr(point)), |r, p| Float::max(r, norm_sqr(p.z)));
+    if let Some(i) = escaping.iter().position(|p| norm_sqr(p.z) >= r*globals.bailout)
+    {
+        escaping.truncate(i)
+    }
+    let (preperiod, period) = match target
+    {
+        Target::Nucleus => (1..escaping.len())
+            .map(|p| (0, p))
+            .min_by(|&(_, a), &(_, b)| norm(escaping[a].z - escaping[0].z).partial_cmp(&norm(escaping[b].z - escaping[0].z)).unwrap_or(Ordering::Equal)),
+        Target::Misiurewicz => (1..escaping.len())
+            .flat_map(|k| (1..escaping.len() - k).map(move |p| (k, p)))
+            .min_by(|&(k, p), &(l, q)| norm(escaping[k + p].z - escaping[k].z).partial_cmp(&norm(escaping[l + q].z - escaping[l].z)).unwrap_or(Ordering::Equal))
+    }
+        .ok_or(anyhow::Error::msg("The orbit escapes too soon to tell what it's near"))?;
+
+    // Newton's method on z(k + p) - z(k), until it moves less than a fraction of a pixel
+    let mut c = point;
+    let mut converged = false;
+    for _ in 0..NEWTON_N
+    {
+        let orbit = orbit(c, preperiod + period + 1)?;
+        let (a, b) = (orbit[preperiod], orbit[preperiod + period]);
+        let step = (b.z - a.z)/(b.dc - a.dc);
+        if !(Float::is_finite(step.re) && Float::is_finite(step.im))
+        {
+            break
+        }
+        c -= step;
+        if norm(step)*globals.zoom < f!(NEWTON_MU)
+        {
+            converged = true;
+            break
+        }
+    }
+    if !converged
+    {
+        return Err(anyhow::Error::msg(format!("Newton's method didn't settle within {NEWTON_N} steps")))
+    }
+
+    // The cycle found may be shorter, or start sooner, than what was aimed for
+    let orbit = orbit(c, preperiod + period + 1)?;
+    let tolerance = norm(orbit[preperiod + period].dc - orbit[preperiod].dc)*f!(NEWTON_MU)/globals.zoom;
+    let repeats = |k: usize, p: usize| norm(orbit[k + p].z - orbit[k].z) <= tolerance;
+    let period = (1..=period).find(|&p| period % p == 0 && repeats(preperiod, p)).unwrap_or(period);
+    let preperiod = (0..=preperiod).find(|&k| repeats(k, period)).unwrap_or(preperiod);
+    if target == Target::Misiurewicz && preperiod == 0
+    {
+        return Err(anyhow::Error::msg(format!("Ended up at the nucleus of period {period} at {c} instead of a Misiurewicz point")))
+    }
+
+    let dz: Option<Vec<Complex<F>>> = orbit[preperiod..preperiod + period].iter()
+        .map(|p| p.dz)
+        .collect();
+    let multiplier = dz.as_ref().map(|dz| dz.iter().fold(Complex::from(F::one()), |m, &dz| m*dz));
+    if let Some(multiplier) = multiplier
+        && target == Target::Misiurewicz
+        && norm(multiplier) <= F::one()
+    {
+        return Err(anyhow::Error::msg(format!("Ended up at {c} with a cycle of multiplier {multiplier}, which isn't repelling like at a Misiurewicz point")))
+    }
+    let size = dz.filter(|_| preperiod == 0).map(|dz| atom_size(&dz));
+    Ok(Located { point: c, preperiod, period, multiplier, size })
+}
+
+/// The atom size estimate `1/(b·l²)` of a nucleus from the derivatives of the steps along its cycle, with `l` their
+/// product leaving out the first and `b` the sum of the reciprocals of the partial products.
+fn atom_size<F>(dz: &[Complex<F>]) -> F
+where
+    F: MyFloat
+{
+    let (l, b) = dz.iter()
+        .skip(1)
+        .fold((Complex::from(F::one()), Complex::from(F::one())), |(l, b), &dz| {
+            let l = l*dz;
+            (l, b + l.inv())
+        });
+    if l.is_zero()
+    {
+        return F::zero()
+    }
+    norm((b*l*l).inv())
+}
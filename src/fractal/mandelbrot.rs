@@ -1,10 +1,12 @@
+use std::sync::Arc;
+
 use num_complex::Complex;
 use num_traits::{Float, Zero};
 use winit::dpi::PhysicalSize;
 
-use crate::{f, MyFloat, app::InitView, fractal::{Fractal, dcdz}};
+use crate::{f, MyFloat, app::InitView, fractal::{pipeline, ShaderDefs, Fractal, Julia, OrbitPoint, ReferenceOrbit, parameter_orbit, dcdz, cpu::{Globals, colormap_de, dpowc, escape_derivative, norm_sqr, powc, smooth}}};
 
-use super::wgsl_bindgen::mandelbrot;
+use super::wgsl_bindgen::{mandelbrot, mandelbrot_perturbation};
 
 #[derive(Clone, Copy)]
 pub struct Mandelbrot;
@@ -26,34 +28,76 @@ where
         }
     }
 
-    fn setup_render_pipeline(&self, device: &wgpu::Device, surface_format: wgpu::TextureFormat) -> wgpu::RenderPipeline
-    {
-        // Create shader module from generated code
-        let shader = mandelbrot::create_shader_module_embed_source(device);
-        
-        // Use generated pipeline layout
-        let pipeline_layout = mandelbrot::create_pipeline_layout(device);
-        
-        // Use generated vertex entry with proper buffer layout
-        let vertex_entry = mandelbrot::vs_main_entry(wgpu::VertexStepMode::Vertex);
-     
-        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some(Fractal::<F>::label(self)),
-            layout: Some(&pipeline_layout),
-            vertex: mandelbrot::vertex_state(&shader, &vertex_entry),
-            fragment: Some(mandelbrot::fragment_state(&shader, &mandelbrot::fs_main_entry([
-                Some(wgpu::ColorTargetState {
-                    format: surface_format,
-                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
-                    write_mask: wgpu::ColorWrites::COLOR,
-                })
-            ]))),
-            primitive: wgpu::PrimitiveState::default(),
-            depth_stencil: None,
-            multisample: wgpu::MultisampleState::default(),
-            multiview_mask: None,
-            cache: None
-            // ... other pipeline state
-        })
-    }
-}
\ No newline at end of file
+    fn fs_main(&self, globals: &Globals<F>, pos: Complex<F>) -> [F; 4]
+    {
+        let (i, z, der, trap) = iterate(globals, pos);
+        let m = smooth(i, z, globals.exp.norm());
+
+        colormap_de(globals, z, der, m, trap, 3)
+    }
+
+    fn escape_count(&self, globals: &Globals<F>, pos: Complex<F>) -> Option<u32>
+    {
+        Some(iterate(globals, pos).0)
+    }
+
+    fn distance_estimate(&self) -> bool
+    {
+        true
+    }
+
+    fn setup_render_pipeline(&self, device: &wgpu::Device, surface_format: wgpu::TextureFormat, defs: &ShaderDefs) -> anyhow::Result<wgpu::RenderPipeline>
+    {
+        pipeline!(mandelbrot, device, surface_format, defs, Fractal::<F>::label(self))
+    }
+
+    fn parameter_orbit(&self, globals: &Globals<F>, c: Complex<F>, n: usize) -> Option<Vec<OrbitPoint<F>>>
+    {
+        let e = globals.exp;
+        Some(parameter_orbit(globals.shift, n, |z| {
+            let zp = powc(z, e);
+            (zp + c, e*powc(z, e - F::one()), Complex::from(F::one()))
+        }))
+    }
+
+    fn dual(&self, globals: &Globals<F>, c: Complex<F>) -> Option<Arc<dyn Fractal<F>>>
+    {
+        let exp = globals.exp;
+        Some(Arc::new(Julia {
+            c: Complex::new(c.re.to_f64().unwrap(), c.im.to_f64().unwrap()),
+            exp: Complex::new(exp.re.to_f64().unwrap(), exp.im.to_f64().unwrap())
+        }))
+    }
+
+    fn reference_orbit(&self, globals: &Globals<F>, point: Complex<F>) -> Option<ReferenceOrbit<F>>
+    {
+        let c = point;
+        Some(ReferenceOrbit::iterate(globals, point, globals.shift, c, |_, zp| zp + c))
+    }
+
+    fn setup_perturbation_pipeline(&self, device: &wgpu::Device, surface_format: wgpu::TextureFormat, defs: &ShaderDefs) -> anyhow::Result<Option<wgpu::RenderPipeline>>
+    {
+        pipeline!(mandelbrot_perturbation, device, surface_format, defs, &format!("{} (perturbation)", Fractal::<F>::label(self))).map(Some)
+    }
+}
+
+fn iterate<F>(globals: &Globals<F>, pos: Complex<F>) -> (u32, Complex<F>, Complex<F>, F)
+where
+    F: MyFloat
+{
+    let z = globals.shift;
+    let c = globals.coord(pos);
+    let r = Float::max(Float::max(F::one(), norm_sqr(z)), norm_sqr(c));
+
+    let dc = dcdz::<F, F>(None)/globals.zoom;
+    let n = globals.max_iterations().to_u32().unwrap();
+    escape_derivative(
+        globals,
+        z,
+        Complex::from(F::one()/globals.zoom - dc),
+        Complex::from(dc),
+        r,
+        n,
+        |z| (powc(z, globals.exp) + c, dpowc(z, globals.exp))
+    )
+}
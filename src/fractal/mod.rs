@@ -1,9 +1,15 @@
 const RECURSION_LIMIT: usize = 256;
+/// Samples per pixel of every fractal pipeline, which the texture drawn to has to match.
+const SAMPLE_COUNT: u32 = 1;
+const BLEND: wgpu::BlendState = wgpu::BlendState::ALPHA_BLENDING;
 
 moddef::moddef!(
     mod {
         wgsl_bindgen
     },
+    pub mod {
+        cpu
+    },
     flat(pub) mod {
         blancmange,
         cantor,
@@ -18,10 +24,19 @@ moddef::moddef!(
         rauzy,
         supergolden_julia,
         supergolden_mandelbrot,
-        fibonacci_snowflake
+        fibonacci_snowflake,
+        perturbation,
+        locator,
+        composer,
+        palette,
+        formula,
+        custom_formula
     }
 );
 
+#[cfg(test)]
+mod tests;
+
 use core::ops::Deref;
 use std::sync::Arc;
 
@@ -38,21 +53,126 @@ pub use global_bindings::VertexInput;
 use winit::dpi::PhysicalSize;
 
 use crate::app::InitView;
+use crate::fractal::cpu::Globals;
+pub use wgsl_bindgen::perturbation::Reference;
+pub use wgsl_bindgen::global_bindings_df::GlobalUniformsDf;
 use crate::MyFloat;
 
+/// Builds the render pipeline of a shader module generated by `wgsl_bindgen`, composing the shader anew if there are
+/// [`ShaderDefs`], e.g. `pipeline!(mandelbrot, device, surface_format, defs, "mandelbrot")`. Fails if they don't compose.
+macro_rules! pipeline {
+    ($module:ident, $device:expr, $surface_format:expr, $defs:expr, $label:expr) => {
+        $defs.shader_module(
+            $device,
+            concat!(stringify!($module), ".wgsl"),
+            include_str!(concat!(stringify!($module), ".wgsl")),
+            $module::create_shader_module_embed_source
+        ).map(|shader| {
+            let vertex_entry = $module::vs_main_entry(wgpu::VertexStepMode::Vertex);
+            let fragment_entry = $module::fs_main_entry($crate::fractal::color_targets($surface_format));
+            $crate::fractal::create_pipeline(
+                $device,
+                $label,
+                &$module::create_pipeline_layout($device),
+                $module::vertex_state(&shader, &vertex_entry),
+                $module::fragment_state(&shader, &fragment_entry)
+            )
+        })
+    };
+}
+use pipeline as pipeline;
+
 pub trait Fractal<F>
 where
     F: MyFloat
 {
     fn label(&self) -> &'static str;
 
+    /// The parameters the fractal was built with, as `<param>=<value>,...` like on the command line.
+    fn params(&self) -> String
+    {
+        String::new()
+    }
+
+    /// The fractal as it's given on the command line, `<label>[:<params>]`.
+    fn arg(&self) -> String
+    {
+        let params = self.params();
+        if params.is_empty()
+        {
+            self.label().to_string()
+        }
+        else
+        {
+            format!("{}:{params}", self.label())
+        }
+    }
+
     fn init_view(&self, zoom: F, win_size: PhysicalSize<u32>) -> InitView<F>;
 
+    /// CPU mirror of the shader's `fs_main`, given the pixel position relative to the center of the window.
+    fn fs_main(&self, globals: &Globals<F>, pos: Complex<F>) -> [F; 4];
+
+    /// How many iterations the pixel at `pos` takes to escape, for escape time fractals.
+    fn escape_count(&self, _globals: &Globals<F>, _pos: Complex<F>) -> Option<u32>
+    {
+        None
+    }
+
+    /// Whether every pipeline of the fractal colors by the distance estimate, so [`Distance`] makes a difference.
+    fn distance_estimate(&self) -> bool
+    {
+        false
+    }
+
     fn setup_render_pipeline(
         &self,
         device: &wgpu::Device,
-        surface_format: wgpu::TextureFormat
-    ) -> wgpu::RenderPipeline;
+        surface_format: wgpu::TextureFormat,
+        defs: &ShaderDefs
+    ) -> anyhow::Result<wgpu::RenderPipeline>;
+
+    /// The first `n` iterations of the orbit of the parameter `c`, for fractals whose plane is a parameter space, e.g.
+    /// Mandelbrot-like ones.
+    fn parameter_orbit(&self, _globals: &Globals<F>, _c: Complex<F>, _n: usize) -> Option<Vec<OrbitPoint<F>>>
+    {
+        None
+    }
+
+    /// The fractal the parameter `c` iterates in, with `c` as its shift, e.g. the Julia set of a point of the
+    /// Mandelbrot set, for fractals whose plane is a parameter space.
+    fn dual(&self, _globals: &Globals<F>, _c: Complex<F>) -> Option<Arc<dyn Fractal<F>>>
+    {
+        None
+    }
+
+    /// Iterates the point `point` in full precision, for fractals that have a perturbation pipeline.
+    fn reference_orbit(&self, _globals: &Globals<F>, _point: Complex<F>) -> Option<ReferenceOrbit<F>>
+    {
+        None
+    }
+
+    /// A pipeline that iterates each pixel's delta from a [`ReferenceOrbit`], for when `f32` runs out of precision.
+    fn setup_perturbation_pipeline(
+        &self,
+        _device: &wgpu::Device,
+        _surface_format: wgpu::TextureFormat,
+        _defs: &ShaderDefs
+    ) -> anyhow::Result<Option<wgpu::RenderPipeline>>
+    {
+        Ok(None)
+    }
+
+    /// A pipeline that iterates in double-floats from [`GlobalUniformsDf`], for when `f32` runs out of precision.
+    fn setup_df_pipeline(
+        &self,
+        _device: &wgpu::Device,
+        _surface_format: wgpu::TextureFormat,
+        _defs: &ShaderDefs
+    ) -> anyhow::Result<Option<wgpu::RenderPipeline>>
+    {
+        Ok(None)
+    }
 }
 
 impl<F, T> Fractal<F> for Box<T>
@@ -64,19 +184,75 @@ where
     {
         self.deref().label()
     }
+
+    fn params(&self) -> String
+    {
+        self.deref().params()
+    }
     
     fn init_view(&self, zoom: F, win_size: PhysicalSize<u32>) -> InitView<F>
     {
         self.deref().init_view(zoom, win_size)
     }
+
+    fn fs_main(&self, globals: &Globals<F>, pos: Complex<F>) -> [F; 4]
+    {
+        self.deref().fs_main(globals, pos)
+    }
+
+    fn escape_count(&self, globals: &Globals<F>, pos: Complex<F>) -> Option<u32>
+    {
+        self.deref().escape_count(globals, pos)
+    }
+
+    fn distance_estimate(&self) -> bool
+    {
+        self.deref().distance_estimate()
+    }
     
     fn setup_render_pipeline(
         &self,
         device: &wgpu::Device,
-        surface_format: wgpu::TextureFormat
-    ) -> wgpu::RenderPipeline
+        surface_format: wgpu::TextureFormat,
+        defs: &ShaderDefs
+    ) -> anyhow::Result<wgpu::RenderPipeline>
+    {
+        self.deref().setup_render_pipeline(device, surface_format, defs)
+    }
+
+    fn parameter_orbit(&self, globals: &Globals<F>, c: Complex<F>, n: usize) -> Option<Vec<OrbitPoint<F>>>
+    {
+        self.deref().parameter_orbit(globals, c, n)
+    }
+
+    fn dual(&self, globals: &Globals<F>, c: Complex<F>) -> Option<Arc<dyn Fractal<F>>>
     {
-        self.deref().setup_render_pipeline(device, surface_format)
+        self.deref().dual(globals, c)
+    }
+
+    fn reference_orbit(&self, globals: &Globals<F>, point: Complex<F>) -> Option<ReferenceOrbit<F>>
+    {
+        self.deref().reference_orbit(globals, point)
+    }
+
+    fn setup_perturbation_pipeline(
+        &self,
+        device: &wgpu::Device,
+        surface_format: wgpu::TextureFormat,
+        defs: &ShaderDefs
+    ) -> anyhow::Result<Option<wgpu::RenderPipeline>>
+    {
+        self.deref().setup_perturbation_pipeline(device, surface_format, defs)
+    }
+
+    fn setup_df_pipeline(
+        &self,
+        device: &wgpu::Device,
+        surface_format: wgpu::TextureFormat,
+        defs: &ShaderDefs
+    ) -> anyhow::Result<Option<wgpu::RenderPipeline>>
+    {
+        self.deref().setup_df_pipeline(device, surface_format, defs)
     }
 }
 
@@ -90,22 +266,80 @@ where
     {
         self.deref().label()
     }
+
+    fn params(&self) -> String
+    {
+        self.deref().params()
+    }
     
     fn init_view(&self, zoom: F, win_size: PhysicalSize<u32>) -> InitView<F>
     {
         self.deref().init_view(zoom, win_size)
     }
+
+    fn fs_main(&self, globals: &Globals<F>, pos: Complex<F>) -> [F; 4]
+    {
+        self.deref().fs_main(globals, pos)
+    }
+
+    fn escape_count(&self, globals: &Globals<F>, pos: Complex<F>) -> Option<u32>
+    {
+        self.deref().escape_count(globals, pos)
+    }
+
+    fn distance_estimate(&self) -> bool
+    {
+        self.deref().distance_estimate()
+    }
     
     fn setup_render_pipeline(
         &self,
         device: &wgpu::Device,
-        surface_format: wgpu::TextureFormat
-    ) -> wgpu::RenderPipeline
+        surface_format: wgpu::TextureFormat,
+        defs: &ShaderDefs
+    ) -> anyhow::Result<wgpu::RenderPipeline>
     {
-        self.deref().setup_render_pipeline(device, surface_format)
+        self.deref().setup_render_pipeline(device, surface_format, defs)
+    }
+
+    fn parameter_orbit(&self, globals: &Globals<F>, c: Complex<F>, n: usize) -> Option<Vec<OrbitPoint<F>>>
+    {
+        self.deref().parameter_orbit(globals, c, n)
+    }
+
+    fn dual(&self, globals: &Globals<F>, c: Complex<F>) -> Option<Arc<dyn Fractal<F>>>
+    {
+        self.deref().dual(globals, c)
+    }
+
+    fn reference_orbit(&self, globals: &Globals<F>, point: Complex<F>) -> Option<ReferenceOrbit<F>>
+    {
+        self.deref().reference_orbit(globals, point)
+    }
+
+    fn setup_perturbation_pipeline(
+        &self,
+        device: &wgpu::Device,
+        surface_format: wgpu::TextureFormat,
+        defs: &ShaderDefs
+    ) -> anyhow::Result<Option<wgpu::RenderPipeline>>
+    {
+        self.deref().setup_perturbation_pipeline(device, surface_format, defs)
+    }
+
+    fn setup_df_pipeline(
+        &self,
+        device: &wgpu::Device,
+        surface_format: wgpu::TextureFormat,
+        defs: &ShaderDefs
+    ) -> anyhow::Result<Option<wgpu::RenderPipeline>>
+    {
+        self.deref().setup_df_pipeline(device, surface_format, defs)
     }
 }
 
+/// The derivative of `c` in the pixel position relative to that of the start of the orbit, given the start if it's the
+/// pixel (Julia-like) rather than fixed (Mandelbrot-like), for carrying derivatives for the distance estimate.
 fn dcdz<F, T>(z: Option<Complex<F>>) -> T
 where
     F: MyFloat,
@@ -116,4 +350,41 @@ where
         Some(_) => T::zero(),
         None => T::one()
     }
-}
\ No newline at end of file
+}
+
+/// What every fractal pipeline draws to.
+pub fn color_targets(format: wgpu::TextureFormat) -> [Option<wgpu::ColorTargetState>; 1]
+{
+    [
+        Some(wgpu::ColorTargetState {
+            format,
+            blend: Some(BLEND),
+            write_mask: wgpu::ColorWrites::COLOR
+        })
+    ]
+}
+
+/// The pipeline state every fractal shares, used by [`pipeline!`].
+fn create_pipeline(
+    device: &wgpu::Device,
+    label: &str,
+    layout: &wgpu::PipelineLayout,
+    vertex: wgpu::VertexState,
+    fragment: wgpu::FragmentState
+) -> wgpu::RenderPipeline
+{
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some(label),
+        layout: Some(layout),
+        vertex,
+        fragment: Some(fragment),
+        primitive: wgpu::PrimitiveState::default(),
+        depth_stencil: None,
+        multisample: wgpu::MultisampleState {
+            count: SAMPLE_COUNT,
+            ..Default::default()
+        },
+        multiview_mask: None,
+        cache: None
+    })
+}
@@ -0,0 +1,347 @@
+use std::path::{Path, PathBuf};
+
+use num_traits::Float;
+use serde::{Deserialize, Serialize};
+
+use crate::{MyFloat, f, fractal::cpu::{clamp, wrap}, settings::config_path};
+
+/// Stops that fit in [`GlobalUniforms::palette`](super::GlobalUniforms), gradients with more are resampled.
+pub const PALETTE_STOPS: usize = 64;
+/// The colormaps before the gradients: the fractal's own, the four in `lib/colormap.wgsl` and the default palette.
+const COLORMAPS: usize = 6;
+/// The value of the `colormap` uniform that colors by the palette.
+const PALETTE_COLORMAP: u32 = 5;
+
+/// A gradient that wraps around, as stops of `[r, g, b, position]` sorted by position within `0..=1`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Palette
+{
+    stops: [[f32; 4]; PALETTE_STOPS],
+    len: usize
+}
+
+/// Ultra Fractal's default, from deep blue through white and orange to black.
+impl Default for Palette
+{
+    fn default() -> Self
+    {
+        Self::new(vec![
+            [0.0, 0.03, 0.39, 0.0],
+            [0.13, 0.42, 0.8, 0.16],
+            [0.93, 1.0, 1.0, 0.42],
+            [1.0, 0.67, 0.0, 0.6425],
+            [0.0, 0.01, 0.0, 0.8575]
+        ]).unwrap()
+    }
+}
+
+impl Palette
+{
+    pub fn new(mut stops: Vec<[f32; 4]>) -> anyhow::Result<Self>
+    {
+        if stops.is_empty()
+        {
+            return Err(anyhow::Error::msg("A palette needs at least one stop"))
+        }
+        if let Some(stop) = stops.iter().find(|stop| !stop.iter().all(|x| (0.0..=1.0).contains(x)))
+        {
+            return Err(anyhow::Error::msg(format!("Colors and positions of stops must be within 0..=1, was {stop:?}")))
+        }
+        stops.sort_by(|a, b| a[3].total_cmp(&b[3]));
+        if stops.len() > PALETTE_STOPS
+        {
+            stops = (0..PALETTE_STOPS).map(|k| {
+                    let t = k as f32/PALETTE_STOPS as f32;
+                    let [r, g, b] = sample(&stops, t);
+                    [r, g, b, t]
+                })
+                .collect()
+        }
+        let mut palette = Self { stops: [[0.0; 4]; PALETTE_STOPS], len: stops.len() };
+        palette.stops[..stops.len()].copy_from_slice(&stops);
+        Ok(palette)
+    }
+
+    /// Reads a GIMP gradient (`.ggr`), a Fractint map (`.map`) or a JSON list of stops like
+    /// `[{"position": 0.5, "color": [1.0, 0.5, 0.0]}]`.
+    pub fn load(path: &Path) -> anyhow::Result<Self>
+    {
+        let source = std::fs::read_to_string(path)
+            .map_err(|error| anyhow::Error::new(error).context(format!("Failed to read {}", path.display())))?;
+        let stops = match path.extension().and_then(|extension| extension.to_str())
+        {
+            Some("ggr") => parse_ggr(&source),
+            Some("map") => parse_map(&source),
+            Some("json") => parse_json(&source),
+            _ => Err(anyhow::Error::msg("Expected a .ggr, .map or .json file"))
+        };
+        stops.and_then(Self::new)
+            .map_err(|error| error.context(format!("Invalid palette {}", path.display())))
+    }
+
+    /// CPU mirror of `palette` in `lib/colormap.wgsl`.
+    pub fn sample<F>(&self, t: F) -> [F; 3]
+    where
+        F: MyFloat
+    {
+        sample(&self.stops[..self.len], t.to_f32().unwrap()).map(|x| f!(x))
+    }
+
+    /// The stops as they go in the uniforms, and how many there are.
+    pub fn uniforms(&self) -> ([glam::Vec4; PALETTE_STOPS], u32)
+    {
+        (self.stops.map(glam::Vec4::from), self.len as u32)
+    }
+}
+
+/// Interpolates linearly between the stops around `t`, wrapping around from the last stop to the first.
+fn sample(stops: &[[f32; 4]], t: f32) -> [f32; 3]
+{
+    let t = wrap(t, 1.0);
+    let [r, g, b, position] = stops[stops.len() - 1];
+    let mut prev = [r, g, b, position - 1.0];
+    let [r, g, b, position] = stops[0];
+    for next in stops.iter().copied().chain([[r, g, b, position + 1.0]])
+    {
+        if t < next[3]
+        {
+            let s = clamp((t - prev[3])/Float::max(next[3] - prev[3], f32::EPSILON), 0.0, 1.0);
+            return [0, 1, 2].map(|k| prev[k] + (next[k] - prev[k])*s)
+        }
+        prev = next
+    }
+    [prev[0], prev[1], prev[2]]
+}
+
+/// Each segment of a GIMP gradient as stops at its ends and middle, blended linearly in RGB whatever the file asks for.
+fn parse_ggr(source: &str) -> anyhow::Result<Vec<[f32; 4]>>
+{
+    let mut lines = source.lines();
+    if lines.next().map(str::trim) != Some("GIMP Gradient")
+    {
+        return Err(anyhow::Error::msg("Missing 'GIMP Gradient' header"))
+    }
+    let mut lines = lines.skip_while(|line| line.starts_with("Name:"));
+    let n: usize = lines.next()
+        .ok_or(anyhow::Error::msg("Missing number of segments"))?
+        .trim()
+        .parse()?;
+    let mut stops = vec![];
+    for (i, line) in lines.take(n).enumerate()
+    {
+        let values = line.split_whitespace()
+            .take(11)
+            .map(str::parse)
+            .collect::<Result<Vec<f32>, _>>()
+            .map_err(|error| anyhow::Error::msg(format!("Invalid segment {}: {error}", i + 1)))?;
+        let &[left, middle, right, r0, g0, b0, _, r1, g1, b1, _] = values.as_slice()
+        else
+        {
+            return Err(anyhow::Error::msg(format!("Segment {} has too few values", i + 1)))
+        };
+        stops.extend([
+            [r0, g0, b0, left],
+            [(r0 + r1)/2.0, (g0 + g1)/2.0, (b0 + b1)/2.0, middle],
+            [r1, g1, b1, right]
+        ]);
+    }
+    Ok(stops)
+}
+
+/// A Fractint map of `r g b` from 0 to 255 per line, spread out evenly, with anything after them ignored.
+fn parse_map(source: &str) -> anyhow::Result<Vec<[f32; 4]>>
+{
+    let colors = source.lines()
+        .filter(|line| !line.trim().is_empty())
+        .enumerate()
+        .map(|(i, line)| {
+            let mut values = line.split_whitespace().map(str::parse::<u8>);
+            match values.next_chunk()
+            {
+                Ok([Ok(r), Ok(g), Ok(b)]) => Ok([r, g, b].map(|x| x as f32/255.0)),
+                _ => Err(anyhow::Error::msg(format!("Expected 'r g b' from 0 to 255 on line {}", i + 1)))
+            }
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    let n = colors.len() as f32;
+    Ok(colors.into_iter()
+        .enumerate()
+        .map(|(k, [r, g, b])| [r, g, b, k as f32/n])
+        .collect())
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Stop
+{
+    position: f32,
+    color: [f32; 3]
+}
+
+fn parse_json(source: &str) -> anyhow::Result<Vec<[f32; 4]>>
+{
+    let stops: Vec<Stop> = serde_json::from_str(source)?;
+    Ok(stops.into_iter().map(|Stop { position, color: [r, g, b] }| [r, g, b, position]).collect())
+}
+
+/// A palette loaded from a file, which is given and saved as its path.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "PathBuf", into = "PathBuf")]
+pub struct Gradient
+{
+    path: PathBuf,
+    palette: Palette
+}
+
+impl TryFrom<PathBuf> for Gradient
+{
+    type Error = String;
+
+    fn try_from(path: PathBuf) -> Result<Self, Self::Error>
+    {
+        let full_path = config_path("").map(|dir| dir.join(&path)).unwrap_or(path.clone());
+        let palette = Palette::load(&full_path)
+            .map_err(|error| format!("{error:#}"))?;
+        Ok(Self { path, palette })
+    }
+}
+
+impl From<Gradient> for PathBuf
+{
+    fn from(gradient: Gradient) -> Self
+    {
+        gradient.path
+    }
+}
+
+/// What fractals are colored with, under `[palette]`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PaletteSettings
+{
+    /// 0 for each fractal's own colormap, 1 to 4 for those in `lib/colormap.wgsl`, 5 for the default palette and 6
+    /// onwards for `gradients`.
+    pub colormap: usize,
+    /// Palettes to cycle through, as `.ggr`, `.map` or `.json` files relative to the config directory.
+    pub gradients: Vec<Gradient>,
+    /// How far along the palette starts, in periods.
+    pub offset: f64,
+    /// Periods per second the palette cycles by as time goes on.
+    pub speed: f64,
+    /// How escape time fractals color their boundary by its distance.
+    pub distance: Distance,
+    /// The shape of the orbit trap escape time fractals color by, if any.
+    pub trap: Trap
+}
+
+impl Default for PaletteSettings
+{
+    fn default() -> Self
+    {
+        Self {
+            colormap: 0,
+            gradients: vec![],
+            offset: 0.0,
+            speed: 0.0,
+            distance: Distance::Off,
+            trap: Trap::Off
+        }
+    }
+}
+
+impl PaletteSettings
+{
+    pub fn validate(&self) -> anyhow::Result<()>
+    {
+        if self.colormap >= self.colormaps()
+        {
+            return Err(anyhow::Error::msg(format!("colormap must be below {}, was {}", self.colormaps(), self.colormap)))
+        }
+        for (name, value) in [("offset", self.offset), ("speed", self.speed)]
+        {
+            if !value.is_finite()
+            {
+                return Err(anyhow::Error::msg(format!("{name} must be finite, was {value}")))
+            }
+        }
+        Ok(())
+    }
+
+    /// How many colormaps there are to pick from.
+    pub fn colormaps(&self) -> usize
+    {
+        COLORMAPS + self.gradients.len()
+    }
+
+    /// Moves on to the next colormap, back to the fractal's own after the last.
+    pub fn next_colormap(&mut self)
+    {
+        self.colormap = (self.colormap + 1) % self.colormaps()
+    }
+
+    /// The `colormap` uniform, and the palette it colors by.
+    pub fn pick(&self) -> (u32, Palette)
+    {
+        match self.colormap.checked_sub(COLORMAPS)
+        {
+            Some(k) => (PALETTE_COLORMAP, self.gradients[k].palette),
+            None => (self.colormap as u32, Palette::default())
+        }
+    }
+}
+
+/// Coloring by the distance estimate of escape time fractals, which crisps up their boundary where the escape count
+/// alone leaves speckle. Goes in the `distance` uniform as its discriminant.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Distance
+{
+    #[default]
+    Off,
+    /// Thin black lines along the boundary, on white.
+    Lines,
+    /// The colormap darkened towards the boundary.
+    Shading,
+    /// The colormap blended with the color of the inside by how much of each pixel the boundary covers, which smooths
+    /// the edge of the set.
+    Antialias
+}
+
+impl Distance
+{
+    const ALL: [Self; 4] = [Self::Off, Self::Lines, Self::Shading, Self::Antialias];
+
+    pub fn next(self) -> Self
+    {
+        Self::ALL[(self as usize + 1) % Self::ALL.len()]
+    }
+}
+
+/// Coloring escape time fractals by the closest their orbits come to a shape, the orbit trap, at the trap position of
+/// the view. Goes in the `trap` uniform as its discriminant.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Trap
+{
+    #[default]
+    Off,
+    Point,
+    /// A horizontal line.
+    Line,
+    /// A horizontal and a vertical line.
+    Cross,
+    /// A circle of radius 1.
+    Circle,
+    /// A cross that only colors where orbits come close to it, over the colormap.
+    Stalks
+}
+
+impl Trap
+{
+    const ALL: [Self; 6] = [Self::Off, Self::Point, Self::Line, Self::Cross, Self::Circle, Self::Stalks];
+
+    pub fn next(self) -> Self
+    {
+        Self::ALL[(self as usize + 1) % Self::ALL.len()]
+    }
+}
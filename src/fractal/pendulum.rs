@@ -1,8 +1,8 @@
 use num_complex::Complex;
-use num_traits::Zero;
+use num_traits::{Float, Zero};
 use winit::dpi::PhysicalSize;
 
-use crate::{MyFloat, app::InitView, fractal::Fractal};
+use crate::{MyFloat, app::InitView, f, fractal::{pipeline, ShaderDefs, Fractal, cpu::{Globals, colormap}}};
 
 use super::wgsl_bindgen::pendulum;
 
@@ -27,34 +27,109 @@ where
         }
     }
 
-    fn setup_render_pipeline(&self, device: &wgpu::Device, surface_format: wgpu::TextureFormat) -> wgpu::RenderPipeline
-    {
-        // Create shader module from generated code
-        let shader = pendulum::create_shader_module_embed_source(device);
-        
-        // Use generated pipeline layout
-        let pipeline_layout = pendulum::create_pipeline_layout(device);
-        
-        // Use generated vertex entry with proper buffer layout
-        let vertex_entry = pendulum::vs_main_entry(wgpu::VertexStepMode::Vertex);
-     
-        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some(Fractal::<F>::label(self)),
-            layout: Some(&pipeline_layout),
-            vertex: pendulum::vertex_state(&shader, &vertex_entry),
-            fragment: Some(pendulum::fragment_state(&shader, &pendulum::fs_main_entry([
-                Some(wgpu::ColorTargetState {
-                    format: surface_format,
-                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
-                    write_mask: wgpu::ColorWrites::COLOR,
-                })
-            ]))),
-            primitive: wgpu::PrimitiveState::default(),
-            depth_stencil: None,
-            multisample: wgpu::MultisampleState::default(),
-            multiview_mask: None,
-            cache: None
-            // ... other pipeline state
-        })
-    }
-}
\ No newline at end of file
+    fn fs_main(&self, globals: &Globals<F>, pos: Complex<F>) -> [F; 4]
+    {
+        let arg = globals.coord(pos);
+        let mut state = PendulumState {
+            theta1: arg.re,
+            theta2: arg.im,
+            p1: globals.exp.re,
+            p2: globals.exp.im
+        };
+
+        let n = globals.max_iterations();
+        let dt = Float::sqrt(globals.time)/n;
+        for _ in 0..n.to_u32().unwrap()
+        {
+            state = state.rk4_step(globals, dt);
+        }
+        state = state.rk4_step(globals, dt*Float::fract(n));
+
+        colormap(globals, state.z(), F::zero(), 4)
+    }
+
+    fn setup_render_pipeline(&self, device: &wgpu::Device, surface_format: wgpu::TextureFormat, defs: &ShaderDefs) -> anyhow::Result<wgpu::RenderPipeline>
+    {
+        pipeline!(pendulum, device, surface_format, defs, Fractal::<F>::label(self))
+    }
+}
+
+/// Gravitational acceleration (m/s^2)
+const G: f64 = 9.8;
+
+#[derive(Clone, Copy)]
+struct PendulumState<F>
+where
+    F: MyFloat
+{
+    theta1: F,
+    theta2: F,
+    p1: F,
+    p2: F
+}
+
+impl<F> PendulumState<F>
+where
+    F: MyFloat
+{
+    fn z(self) -> Complex<F>
+    {
+        Complex::new(
+            Float::sin(self.theta1) + Float::sin(self.theta2),
+            Float::cos(self.theta1) + Float::cos(self.theta2)
+        )
+    }
+
+    fn add(self, k: Self, dt: F) -> Self
+    {
+        Self {
+            theta1: self.theta1 + dt*k.theta1,
+            theta2: self.theta2 + dt*k.theta2,
+            p1: self.p1 + dt*k.p1,
+            p2: self.p2 + dt*k.p2
+        }
+    }
+
+    fn rk4_step(self, globals: &Globals<F>, dt: F) -> Self
+    {
+        let half = f!(0.5);
+        let two = f!(2.0);
+
+        let k1 = self.derivatives(globals);
+        let k2 = self.add(k1, half*dt).derivatives(globals);
+        let k3 = self.add(k2, half*dt).derivatives(globals);
+        let k4 = self.add(k3, dt).derivatives(globals);
+
+        let dt6 = dt/f!(6.0);
+        Self {
+            theta1: (self.theta1 + dt6*(k1.theta1 + two*k2.theta1 + two*k3.theta1 + k4.theta1)) % F::TAU(),
+            theta2: (self.theta2 + dt6*(k1.theta2 + two*k2.theta2 + two*k3.theta2 + k4.theta2)) % F::TAU(),
+            p1: self.p1 + dt6*(k1.p1 + two*k2.p1 + two*k3.p1 + k4.p1),
+            p2: self.p2 + dt6*(k1.p2 + two*k2.p2 + two*k3.p2 + k4.p2)
+        }
+    }
+
+    /// The time derivatives, stored in the same layout as the state.
+    fn derivatives(self, globals: &Globals<F>) -> Self
+    {
+        let Self { theta1, theta2, p1, p2 } = self;
+        let delta = theta1 - theta2;
+        let cos_delta = Float::cos(delta);
+        let sin_delta = Float::sin(delta);
+        let denominator = f!(16.0) - f!(9.0)*cos_delta*cos_delta;
+        let coeff = f!(6.0)/denominator;
+        let dtheta1 = coeff*(f!(2.0)*p1 - f!(3.0)*cos_delta*p2);
+        let dtheta2 = coeff*(f!(8.0)*p2 - f!(3.0)*cos_delta*p1);
+        let coeff2 = f!(-0.5);
+        let endbit = dtheta1*dtheta2*sin_delta;
+        let dp1 = coeff2*(f!(3.0*G)*Float::sin(theta1) + endbit);
+        let dp2 = coeff2*(f!(G)*Float::sin(theta2) - endbit);
+
+        Self {
+            theta1: dtheta1 + globals.shift.re,
+            theta2: dtheta2 + globals.shift.im,
+            p1: dp1,
+            p2: dp2
+        }
+    }
+}
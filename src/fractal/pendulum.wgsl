@@ -1,5 +1,5 @@
 #import global_bindings::{GlobalUniforms, VertexInput, globals, max_iterations, view_radius, epsilon};
-#import colormap::colormap4;
+#import colormap::colormap;
 #import complex::{cmul, cis, norm_sqr, norm, powc}
 #import consts::TAU;
 
@@ -55,7 +55,7 @@ fn fs_main(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32>
     state = rk4_step(state, dt*fract(n));
 
     let z = pendulum_z(state);
-    return colormap4(z);
+    return colormap(z, 0.0, 4);
 }
 
 fn pendulum_z(state: PendulumState) -> vec2<f32>
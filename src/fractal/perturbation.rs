@@ -0,0 +1,93 @@
+use num_complex::Complex;
+use num_traits::Float;
+
+use crate::{MyFloat, f, fractal::{Fractal, cpu::{Globals, norm_sqr, powc}, Reference}};
+
+/// Candidate reference points per axis, spread over the window, tried when the center escapes early.
+const REFERENCE_GRID: usize = 5;
+
+/// An orbit iterated in full precision, which the perturbation shaders iterate each pixel's low precision delta against.
+#[derive(Debug, Clone)]
+pub struct ReferenceOrbit<F>
+where
+    F: MyFloat
+{
+    /// The point in the plane the orbit belongs to, i.e. `c` for Mandelbrot-like and `z₀` for Julia-like fractals.
+    pub point: Complex<F>,
+    pub c: Complex<F>,
+    /// `(Z, Z^exp)` for each iteration, up to and including the one that escaped.
+    pub orbit: Vec<[Complex<F>; 2]>
+}
+
+impl<F> ReferenceOrbit<F>
+where
+    F: MyFloat
+{
+    /// Iterates `z = step(z, z^exp)` with the same bailout and iteration limit as the shaders.
+    pub fn iterate<S>(globals: &Globals<F>, point: Complex<F>, mut z: Complex<F>, c: Complex<F>, step: S) -> Self
+    where
+        S: Fn(Complex<F>, Complex<F>) -> Complex<F>
+    {
+        let r = Float::max(Float::max(F::one(), norm_sqr(z)), norm_sqr(c));
+        let n = globals.max_iterations().to_usize().unwrap();
+
+        let mut orbit = Vec::with_capacity(n + 1);
+        while orbit.len() < n && norm_sqr(z) < r*globals.bailout
+        {
+            let zp = powc(z, globals.exp);
+            orbit.push([z, zp]);
+            z = step(z, zp);
+        }
+        orbit.push([z, powc(z, globals.exp)]);
+
+        Self { point, c, orbit }
+    }
+
+    /// Picks the longest lived orbit out of the center of the view and a grid of points around it, since pixels that
+    /// outlive the reference have to continue in low precision.
+    pub fn find<Z>(fractal: &Z, globals: &Globals<F>) -> Option<Self>
+    where
+        Z: Fractal<F>
+    {
+        let n = globals.max_iterations().to_usize().unwrap();
+        let size = Complex::new(f!(globals.window_size.width), f!(globals.window_size.height));
+
+        let mut best = fractal.reference_orbit(globals, globals.coord(Complex::from(F::zero())))?;
+        for (i, j) in (0..REFERENCE_GRID).flat_map(|i| (0..REFERENCE_GRID).map(move |j| (i, j)))
+        {
+            if best.orbit.len() > n
+            {
+                break
+            }
+            let t = Complex::new(f!(i), f!(j))/f!(REFERENCE_GRID - 1) - f!(0.5);
+            let pos = Complex::new(t.re*size.re, t.im*size.im);
+            let orbit = fractal.reference_orbit(globals, globals.coord(pos))?;
+            if orbit.orbit.len() > best.orbit.len()
+            {
+                best = orbit
+            }
+        }
+        Some(best)
+    }
+
+    pub fn reference(&self, globals: &Globals<F>) -> Reference
+    {
+        let offset = globals.coord(Complex::from(F::zero())) - self.point;
+        Reference::new(vec2(self.c), vec2(offset), self.orbit.len() as u32)
+    }
+
+    /// The orbit as `vec4<f32>`s of `(Z, Z^exp)`.
+    pub fn orbit_data(&self) -> Vec<[f32; 4]>
+    {
+        self.orbit.iter()
+            .map(|&[z, zp]| [z.re, z.im, zp.re, zp.im].map(|x| x.to_f32().unwrap()))
+            .collect()
+    }
+}
+
+fn vec2<F>(z: Complex<F>) -> glam::Vec2
+where
+    F: MyFloat
+{
+    glam::vec2(z.re.to_f32().unwrap(), z.im.to_f32().unwrap())
+}
@@ -4,9 +4,9 @@ use num_complex::Complex;
 use num_traits::{Float, One, Zero};
 use winit::dpi::PhysicalSize;
 
-use crate::{MyFloat, app::InitView, f, fractal::{Fractal, dcdz}};
+use crate::{MyFloat, app::InitView, f, fractal::{pipeline, ShaderDefs, Fractal, dcdz, cpu::{Globals, colormap_trap, trap_distance, TRAP_FAR, norm_sqr, smooth}}};
 
-use super::wgsl_bindgen::rauzy;
+use super::wgsl_bindgen::{rauzy, rauzy_df};
 
 #[derive(Clone)]
 pub struct Rauzy
@@ -36,6 +36,11 @@ where
         "rauzy"
     }
 
+    fn params(&self) -> String
+    {
+        format!("f={},lambda={}", self.f, self.lambda)
+    }
+
     fn init_view(&self, _zoom: F, _win_size: PhysicalSize<u32>) -> InitView<F>
     {
         let Self { f, lambda } = self;
@@ -47,34 +52,53 @@ where
         }
     }
 
-    fn setup_render_pipeline(&self, device: &wgpu::Device, surface_format: wgpu::TextureFormat) -> wgpu::RenderPipeline
+    fn fs_main(&self, globals: &Globals<F>, pos: Complex<F>) -> [F; 4]
+    {
+        let a = globals.coord(pos);
+        let mut z = globals.shift;
+        let b = globals.exp;
+        let r = Float::max(F::one(), norm_sqr(z));
+
+        let mut trap = f!(TRAP_FAR);
+        let n = globals.max_iterations().to_u32().unwrap();
+        let mut i = 0;
+        if norm_sqr(z) <= r*globals.bailout
+        {
+            let mut z_prev = z;
+            z = a;
+            i += 1;
+            trap = Float::min(trap, trap_distance(globals, z));
+            if norm_sqr(z) <= r*globals.bailout
+            {
+                let z_next = z*z_prev + b;
+                let mut z_prev_prev = z_prev;
+                z_prev = z;
+                z = z_next;
+                i += 1;
+                trap = Float::min(trap, trap_distance(globals, z));
+                while i < n && norm_sqr(z) <= r*globals.bailout
+                {
+                    let z_next = z*z_prev_prev - z_prev;
+                    z_prev_prev = z_prev;
+                    z_prev = z;
+                    z = z_next;
+                    i += 1;
+                    trap = Float::min(trap, trap_distance(globals, z));
+                }
+            }
+        }
+        let m = smooth(i, z, globals.exp.norm());
+
+        colormap_trap(globals, z, m, trap, 3)
+    }
+
+    fn setup_render_pipeline(&self, device: &wgpu::Device, surface_format: wgpu::TextureFormat, defs: &ShaderDefs) -> anyhow::Result<wgpu::RenderPipeline>
+    {
+        pipeline!(rauzy, device, surface_format, defs, Fractal::<F>::label(self))
+    }
+
+    fn setup_df_pipeline(&self, device: &wgpu::Device, surface_format: wgpu::TextureFormat, defs: &ShaderDefs) -> anyhow::Result<Option<wgpu::RenderPipeline>>
     {
-        // Create shader module from generated code
-        let shader = rauzy::create_shader_module_embed_source(device);
-        
-        // Use generated pipeline layout
-        let pipeline_layout = rauzy::create_pipeline_layout(device);
-        
-        // Use generated vertex entry with proper buffer layout
-        let vertex_entry = rauzy::vs_main_entry(wgpu::VertexStepMode::Vertex);
-     
-        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some(Fractal::<F>::label(self)),
-            layout: Some(&pipeline_layout),
-            vertex: rauzy::vertex_state(&shader, &vertex_entry),
-            fragment: Some(rauzy::fragment_state(&shader, &rauzy::fs_main_entry([
-                Some(wgpu::ColorTargetState {
-                    format: surface_format,
-                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
-                    write_mask: wgpu::ColorWrites::COLOR,
-                })
-            ]))),
-            primitive: wgpu::PrimitiveState::default(),
-            depth_stencil: None,
-            multisample: wgpu::MultisampleState::default(),
-            multiview_mask: None,
-            cache: None
-            // ... other pipeline state
-        })
+        pipeline!(rauzy_df, device, surface_format, defs, &format!("{} (double-float)", Fractal::<F>::label(self))).map(Some)
     }
 }
\ No newline at end of file
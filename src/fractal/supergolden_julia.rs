@@ -2,12 +2,27 @@ use num_complex::Complex;
 use num_traits::{Float, Zero};
 use winit::dpi::PhysicalSize;
 
-use crate::{f, MyFloat, app::InitView, fractal::{Fractal, dcdz}};
+use crate::{f, MyFloat, app::InitView, fractal::{pipeline, ShaderDefs, Fractal, ReferenceOrbit, dcdz, cpu::{Globals, colormap_trap, escape, norm_sqr, powc, smooth}}};
 
-use super::wgsl_bindgen::supergolden_julia;
+use super::wgsl_bindgen::{supergolden_julia, supergolden_julia_perturbation};
 
 #[derive(Clone, Copy)]
-pub struct SupergoldenJulia;
+pub struct SupergoldenJulia
+{
+    pub c: Complex<f64>,
+    pub exp: Complex<f64>
+}
+
+impl Default for SupergoldenJulia
+{
+    fn default() -> Self
+    {
+        Self {
+            c: Complex::from(1.0),
+            exp: Complex::from(2.0)
+        }
+    }
+}
 
 impl<F> Fractal<F> for SupergoldenJulia
 where
@@ -18,43 +33,58 @@ where
         "supergolden_julia"
     }
 
+    fn params(&self) -> String
+    {
+        format!("c={},exp={}", self.c, self.exp)
+    }
+
     fn init_view(&self, _zoom: F, _win_size: PhysicalSize<u32>) -> InitView<F>
     {
         InitView {
-            exp: Complex::new(f!(2.0), F::zero()),
-            shift: Complex::from(f!(1.0)),
+            exp: Complex::new(f!(self.exp.re), f!(self.exp.im)),
+            shift: Complex::new(f!(self.c.re), f!(self.c.im)),
             ..Default::default()
         }
     }
 
-    fn setup_render_pipeline(&self, device: &wgpu::Device, surface_format: wgpu::TextureFormat) -> wgpu::RenderPipeline
-    {
-        // Create shader module from generated code
-        let shader = supergolden_julia::create_shader_module_embed_source(device);
-        
-        // Use generated pipeline layout
-        let pipeline_layout = supergolden_julia::create_pipeline_layout(device);
-        
-        // Use generated vertex entry with proper buffer layout
-        let vertex_entry = supergolden_julia::vs_main_entry(wgpu::VertexStepMode::Vertex);
-     
-        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some(Fractal::<F>::label(self)),
-            layout: Some(&pipeline_layout),
-            vertex: supergolden_julia::vertex_state(&shader, &vertex_entry),
-            fragment: Some(supergolden_julia::fragment_state(&shader, &supergolden_julia::fs_main_entry([
-                Some(wgpu::ColorTargetState {
-                    format: surface_format,
-                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
-                    write_mask: wgpu::ColorWrites::COLOR,
-                })
-            ]))),
-            primitive: wgpu::PrimitiveState::default(),
-            depth_stencil: None,
-            multisample: wgpu::MultisampleState::default(),
-            multiview_mask: None,
-            cache: None
-            // ... other pipeline state
-        })
-    }
-}
\ No newline at end of file
+    fn fs_main(&self, globals: &Globals<F>, pos: Complex<F>) -> [F; 4]
+    {
+        let (i, z, trap) = iterate(globals, pos);
+        let m = smooth(i, z, globals.exp.norm());
+
+        colormap_trap(globals, z, m, trap, 3)
+    }
+
+    fn escape_count(&self, globals: &Globals<F>, pos: Complex<F>) -> Option<u32>
+    {
+        Some(iterate(globals, pos).0)
+    }
+
+    fn setup_render_pipeline(&self, device: &wgpu::Device, surface_format: wgpu::TextureFormat, defs: &ShaderDefs) -> anyhow::Result<wgpu::RenderPipeline>
+    {
+        pipeline!(supergolden_julia, device, surface_format, defs, Fractal::<F>::label(self))
+    }
+
+    fn reference_orbit(&self, globals: &Globals<F>, point: Complex<F>) -> Option<ReferenceOrbit<F>>
+    {
+        let c = globals.shift;
+        Some(ReferenceOrbit::iterate(globals, point, point, c, |z, zp| (z - c)*zp - c))
+    }
+
+    fn setup_perturbation_pipeline(&self, device: &wgpu::Device, surface_format: wgpu::TextureFormat, defs: &ShaderDefs) -> anyhow::Result<Option<wgpu::RenderPipeline>>
+    {
+        pipeline!(supergolden_julia_perturbation, device, surface_format, defs, &format!("{} (perturbation)", Fractal::<F>::label(self))).map(Some)
+    }
+}
+
+fn iterate<F>(globals: &Globals<F>, pos: Complex<F>) -> (u32, Complex<F>, F)
+where
+    F: MyFloat
+{
+    let z = globals.coord(pos);
+    let c = globals.shift;
+    let r = Float::max(Float::max(F::one(), norm_sqr(z)), norm_sqr(c));
+
+    let n = globals.max_iterations().to_u32().unwrap();
+    escape(globals, z, r, n, |z| (z - c)*powc(z, globals.exp) - c)
+}
@@ -1,10 +1,12 @@
+use std::sync::Arc;
+
 use num_complex::Complex;
 use num_traits::{Float, Zero};
 use winit::dpi::PhysicalSize;
 
-use crate::{f, MyFloat, app::InitView, fractal::{Fractal, dcdz}};
+use crate::{f, MyFloat, app::InitView, fractal::{pipeline, ShaderDefs, Fractal, OrbitPoint, SupergoldenJulia, ReferenceOrbit, parameter_orbit, dcdz, cpu::{Globals, colormap_trap, escape, norm_sqr, powc, smooth}}};
 
-use super::wgsl_bindgen::supergolden_mandelbrot;
+use super::wgsl_bindgen::{supergolden_mandelbrot, supergolden_mandelbrot_perturbation};
 
 #[derive(Clone, Copy)]
 pub struct SupergoldenMandelbrot;
@@ -27,34 +29,62 @@ where
         }
     }
 
-    fn setup_render_pipeline(&self, device: &wgpu::Device, surface_format: wgpu::TextureFormat) -> wgpu::RenderPipeline
-    {
-        // Create shader module from generated code
-        let shader = supergolden_mandelbrot::create_shader_module_embed_source(device);
-        
-        // Use generated pipeline layout
-        let pipeline_layout = supergolden_mandelbrot::create_pipeline_layout(device);
-        
-        // Use generated vertex entry with proper buffer layout
-        let vertex_entry = supergolden_mandelbrot::vs_main_entry(wgpu::VertexStepMode::Vertex);
-     
-        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some(Fractal::<F>::label(self)),
-            layout: Some(&pipeline_layout),
-            vertex: supergolden_mandelbrot::vertex_state(&shader, &vertex_entry),
-            fragment: Some(supergolden_mandelbrot::fragment_state(&shader, &supergolden_mandelbrot::fs_main_entry([
-                Some(wgpu::ColorTargetState {
-                    format: surface_format,
-                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
-                    write_mask: wgpu::ColorWrites::COLOR,
-                })
-            ]))),
-            primitive: wgpu::PrimitiveState::default(),
-            depth_stencil: None,
-            multisample: wgpu::MultisampleState::default(),
-            multiview_mask: None,
-            cache: None
-            // ... other pipeline state
-        })
-    }
-}
\ No newline at end of file
+    fn fs_main(&self, globals: &Globals<F>, pos: Complex<F>) -> [F; 4]
+    {
+        let (i, z, trap) = iterate(globals, pos);
+        let m = smooth(i, z, globals.exp.norm());
+
+        colormap_trap(globals, z, m, trap, 3)
+    }
+
+    fn escape_count(&self, globals: &Globals<F>, pos: Complex<F>) -> Option<u32>
+    {
+        Some(iterate(globals, pos).0)
+    }
+
+    fn setup_render_pipeline(&self, device: &wgpu::Device, surface_format: wgpu::TextureFormat, defs: &ShaderDefs) -> anyhow::Result<wgpu::RenderPipeline>
+    {
+        pipeline!(supergolden_mandelbrot, device, surface_format, defs, Fractal::<F>::label(self))
+    }
+
+    fn parameter_orbit(&self, globals: &Globals<F>, c: Complex<F>, n: usize) -> Option<Vec<OrbitPoint<F>>>
+    {
+        let e = globals.exp;
+        Some(parameter_orbit(globals.shift, n, |z| {
+            let zp = powc(z, e);
+            ((z - c)*zp - c, zp + (z - c)*e*powc(z, e - F::one()), -zp - F::one())
+        }))
+    }
+
+    fn dual(&self, globals: &Globals<F>, c: Complex<F>) -> Option<Arc<dyn Fractal<F>>>
+    {
+        let exp = globals.exp;
+        Some(Arc::new(SupergoldenJulia {
+            c: Complex::new(c.re.to_f64().unwrap(), c.im.to_f64().unwrap()),
+            exp: Complex::new(exp.re.to_f64().unwrap(), exp.im.to_f64().unwrap())
+        }))
+    }
+
+    fn reference_orbit(&self, globals: &Globals<F>, point: Complex<F>) -> Option<ReferenceOrbit<F>>
+    {
+        let c = point;
+        Some(ReferenceOrbit::iterate(globals, point, globals.shift, c, |z, zp| (z - c)*zp - c))
+    }
+
+    fn setup_perturbation_pipeline(&self, device: &wgpu::Device, surface_format: wgpu::TextureFormat, defs: &ShaderDefs) -> anyhow::Result<Option<wgpu::RenderPipeline>>
+    {
+        pipeline!(supergolden_mandelbrot_perturbation, device, surface_format, defs, &format!("{} (perturbation)", Fractal::<F>::label(self))).map(Some)
+    }
+}
+
+fn iterate<F>(globals: &Globals<F>, pos: Complex<F>) -> (u32, Complex<F>, F)
+where
+    F: MyFloat
+{
+    let z = globals.shift;
+    let c = globals.coord(pos);
+    let r = Float::max(Float::max(F::one(), norm_sqr(z)), norm_sqr(c));
+
+    let n = globals.max_iterations().to_u32().unwrap();
+    escape(globals, z, r, n, |z| (z - c)*powc(z, globals.exp) - c)
+}
@@ -0,0 +1,152 @@
+use num_complex::Complex;
+use winit::dpi::PhysicalSize;
+
+use crate::{app::{StartView, View}, fractal::{CustomFormula, Expr, Formula, Func, Julia, Located, Mandelbrot, Op, ShaderDefs, Target, Var, cpu::Globals, locate}, settings::Settings};
+
+fn globals() -> Globals<f64>
+{
+    View::new(&Mandelbrot, PhysicalSize::new(800, 600), StartView::default(), Settings::default()).globals()
+}
+
+fn locate_in_mandelbrot(point: Complex<f64>, target: Target) -> Located<f64>
+{
+    locate(&Mandelbrot, &globals(), point, target).unwrap()
+}
+
+fn assert_close(a: Complex<f64>, b: Complex<f64>)
+{
+    assert!((a - b).norm() < 1e-9, "{a} != {b}");
+}
+
+#[test]
+fn locates_nucleus_of_main_cardioid()
+{
+    let located = locate_in_mandelbrot(Complex::new(0.01, -0.02), Target::Nucleus);
+    assert_close(located.point, Complex::new(0.0, 0.0));
+    assert_eq!((located.preperiod, located.period), (0, 1));
+    assert_close(located.multiplier.unwrap(), Complex::new(0.0, 0.0));
+    assert!((located.size.unwrap() - 1.0).abs() < 1e-9, "size {:?}", located.size);
+}
+
+#[test]
+fn locates_nucleus_of_period_2()
+{
+    let located = locate_in_mandelbrot(Complex::new(-1.03, 0.02), Target::Nucleus);
+    assert_close(located.point, Complex::new(-1.0, 0.0));
+    assert_eq!((located.preperiod, located.period), (0, 2));
+    assert_close(located.multiplier.unwrap(), Complex::new(0.0, 0.0));
+    // 1/(b·l²) with l = 2·(-1) and b = 1 + 1/l
+    assert!((located.size.unwrap() - 0.5).abs() < 1e-9, "size {:?}", located.size);
+}
+
+#[test]
+fn locates_misiurewicz_points()
+{
+    // 0, i, -1 + i, -i, -1 + i, ...
+    let located = locate_in_mandelbrot(Complex::new(0.02, 0.98), Target::Misiurewicz);
+    assert_close(located.point, Complex::new(0.0, 1.0));
+    assert_eq!((located.preperiod, located.period), (2, 2));
+    assert_close(located.multiplier.unwrap(), Complex::new(4.0, 4.0));
+    assert_eq!(located.size, None);
+
+    // 0, -2, 2, 2, ...
+    let located = locate_in_mandelbrot(Complex::new(-1.98, 0.01), Target::Misiurewicz);
+    assert_close(located.point, Complex::new(-2.0, 0.0));
+    assert_eq!((located.preperiod, located.period), (2, 1));
+    assert_close(located.multiplier.unwrap(), Complex::new(4.0, 0.0));
+}
+
+#[test]
+fn only_locates_in_parameter_space()
+{
+    let julia = Julia { c: Complex::new(-1.0, 0.0), exp: Complex::new(2.0, 0.0) };
+    assert!(locate(&julia, &globals(), Complex::new(0.0, 0.0), Target::Nucleus).is_err());
+}
+
+#[test]
+fn composes_with_large_escape_radii()
+{
+    for escape_radius in [1, 2, 65535, 65536, u32::MAX]
+    {
+        let defs = ShaderDefs { escape_radius: Some(escape_radius), ..Default::default() };
+        let module = defs.module("mandelbrot.wgsl", include_str!("mandelbrot.wgsl"), &[]);
+        assert!(module.is_ok(), "escape_radius = {escape_radius}: {:#}", module.unwrap_err());
+    }
+}
+
+fn parse(source: &str) -> Expr
+{
+    Formula::parse(source, &Var::ALL).unwrap().expr
+}
+
+fn num(x: f64) -> Box<Expr>
+{
+    Box::new(Expr::Num(Complex::new(x, 0.0)))
+}
+
+fn var(var: Var) -> Box<Expr>
+{
+    Box::new(Expr::Var(var))
+}
+
+fn parse_error(source: &str, vars: &[Var]) -> String
+{
+    format!("{:#}", Formula::parse(source, vars).unwrap_err())
+}
+
+#[test]
+fn formula_precedence()
+{
+    let z_squared = Expr::Binary(Op::Pow, var(Var::Z), num(2.0));
+    assert_eq!(parse("-z^2"), Expr::Neg(Box::new(z_squared.clone())));
+    assert_eq!(parse("(-z)^2"), Expr::Binary(Op::Pow, Box::new(Expr::Neg(var(Var::Z))), num(2.0)));
+    assert_eq!(parse("z^2^3"), Expr::Binary(Op::Pow, var(Var::Z), Box::new(Expr::Binary(Op::Pow, num(2.0), num(3.0)))));
+    assert_eq!(parse("z^-2"), Expr::Binary(Op::Pow, var(Var::Z), Box::new(Expr::Neg(num(2.0)))));
+    assert_eq!(parse("z - c - 1"), Expr::Binary(Op::Sub, Box::new(Expr::Binary(Op::Sub, var(Var::Z), var(Var::C))), num(1.0)));
+    assert_eq!(parse("z = z^2 + c*2"), Expr::Binary(
+        Op::Add,
+        Box::new(z_squared),
+        Box::new(Expr::Binary(Op::Mul, var(Var::C), num(2.0)))
+    ));
+    assert_eq!(parse("cexp(2i)"), Expr::Call(Func::Cexp, Box::new(Expr::Num(Complex::new(0.0, 2.0)))));
+}
+
+#[test]
+fn formula_display_round_trips()
+{
+    for source in [
+        "z^2+c", "-z^2", "(-z)^2", "z^2^3", "(z^2)^3", "z^-2", "z-(c-1)", "z-c-1", "z/(c*shift)", "z^exp*(z-shift)+c",
+        "conj(z)^2+c", "cexp(-z/2)", "clog(z)*1.5", "(1.5-2i)*z", "2i*z", "-(z+c)", "--z", "z^(1/2)"
+    ]
+    {
+        let formula = Formula::parse(source, &Var::ALL).unwrap();
+        let printed = formula.to_string();
+        assert_eq!(printed, source);
+        assert_eq!(Formula::parse(&printed, &Var::ALL).unwrap(), formula, "{source}");
+    }
+    // What the printing makes of numbers the parser can't give by itself
+    let negative = Formula { expr: Expr::Binary(Op::Pow, Box::new(Expr::Num(Complex::new(-1.0, 0.0))), num(2.0)) };
+    let printed = negative.to_string();
+    assert_eq!(printed, "(-1)^2");
+    assert_eq!(parse(&printed), Expr::Binary(Op::Pow, Box::new(Expr::Neg(num(1.0))), num(2.0)));
+}
+
+#[test]
+fn formula_error_spans()
+{
+    assert_eq!(parse_error("z + )", &Var::ALL), "Expected an operand, found ')'\n    z + )\n        ^");
+    assert_eq!(parse_error("z^2 + q", &Var::ALL), "Unknown name 'q', expected one of z, c, shift, exp, cexp, clog, conj\n    z^2 + q\n          ^");
+    assert_eq!(parse_error("(z + c", &Var::ALL), "Unclosed '('\n    (z + c\n    ^");
+    assert_eq!(parse_error("z + c)", &Var::ALL), "Expected an operator, found ')'\n    z + c)\n         ^");
+    assert_eq!(parse_error("shift + z", &Var::INIT), "'z' can't be used here\n    shift + z\n            ^");
+    assert_eq!(parse_error("z^", &Var::ALL), "Expected an operand at the end\n    z^\n      ^");
+    assert_eq!(parse_error("z + 1e99", &Var::ALL), "1e99 is out of range of f32\n    z + 1e99\n        ^^^^");
+    assert_eq!(parse_error("z·c", &Var::ALL), "Unexpected character '·'\n    z·c\n     ^");
+}
+
+#[test]
+fn custom_formulas_compile()
+{
+    CustomFormula::default().validate().unwrap();
+    CustomFormula::parse("conj(z)^exp*cexp(-z) + c/(z - shift)", "c + 1").unwrap().validate().unwrap();
+}
@@ -2,15 +2,19 @@
 #![feature(iter_next_chunk)]
 #![feature(unique_rc_arc)]
 
-use core::{f32::EPSILON, f64::consts::TAU, fmt::{Debug, Display}, ops::Range};
-use std::sync::Arc;
+use core::{f64::consts::TAU, fmt::{Debug, Display}, ops::{Range, RangeInclusive}};
+use std::{path::Path, sync::Arc};
 
+use clap::Parser;
 use num_complex::{Complex, ComplexFloat};
 
 moddef::moddef!(
     mod {
         fractal,
-        app
+        app,
+        terminal,
+        cli,
+        settings
     }
 );
 
@@ -22,9 +26,9 @@ macro_rules! f {
 use f as f;
 use num_traits::{Float, FloatConst, Num, NumAssignOps, float::FloatCore};
 use rand::{distr::{Uniform, uniform::SampleUniform}, prelude::Distribution};
-use winit::{event_loop::{ActiveEventLoop, EventLoop}, window::Window};
+use winit::{dpi::PhysicalSize, event_loop::{ActiveEventLoop, EventLoop}, window::Window};
 
-use crate::{app::{App, State}, fractal::*};
+use crate::{app::{App, Event, Options, FrameSink, Keymap, Offscreen, Recording, Session, StartView, State, Timeline, View, render_cpu}, cli::Cli, fractal::*, settings::Settings, terminal::{Protocol, TerminalApp}};
 
 const NEWTON_N: usize = 16;
 const NEWTON_MU: f64 = 0.0001;
@@ -33,6 +37,18 @@ const ZOOM_MU: f64 = 0.01;
 
 const START_ZOOM: f32 = 2e2;
 
+const AUTOPILOT_CANDIDATES: usize = 16;
+/// Side of the grid of pixels the autopilot probes around each candidate.
+const AUTOPILOT_PROBE: usize = 8;
+const AUTOPILOT_PROBE_SIZE: f64 = 64.0;
+/// Escape count variance below which the autopilot considers a spot to have run out of detail.
+const AUTOPILOT_DETAIL: f64 = 1.0;
+/// Zoom factors per second.
+const AUTOPILOT_DIVE: f64 = 2.0;
+const AUTOPILOT_SURFACE: f64 = 16.0;
+const AUTOPILOT_PAN: f64 = 0.5;
+const AUTOPILOT_RETARGET: f64 = 2.0;
+
 const ROT_SPEED: f64 = TAU/16.0;
 const MOVE_CENTER_SPEED: f64 = 330.0;
 const MOVE_EXP_SPEED: f64 = 30.0;
@@ -44,10 +60,23 @@ const MOVE_EXP_ACCEL: f64 = 1.0;
 const MOVE_ZOOM_ACCEL: f64 = 1.0;
 const MOVE_SHIFT_ACCEL: f64 = 1.0;
 
+/// Fraction of the width and height of the window the inset with the dual fractal takes up.
+const DUAL_INSET: f64 = 0.25;
+
+/// Seconds between looking for changes to the shaders while watching them.
+const SHADER_POLL: f64 = 0.25;
+
+const TIME_SCALE_STEP: f64 = 2.0;
+const TIME_SCALE_RANGE: RangeInclusive<f64> = 1.0/64.0..=64.0;
+
 const ZOOM_RANGE: Range<f32> = START_ZOOM..f32::EPSILON.recip()*100.0;
 const ZOOM_MUL: f64 = 0.1;
 const ZOOM_BASE: f64 = 1e4;
 const MAX_ITERATIONS: u32 = 32;
+/// Zoom beyond which fractals that support it are rendered by perturbation around a full precision reference orbit.
+const PERTURBATION_ZOOM: f64 = 1e4;
+/// Zoom beyond which fractals that support it are rendered in double-float precision.
+const DF_ZOOM: f64 = 1e4;
 
 const SHIFT_ZOOM_VARIANCE: f64 = 1.1;
 const EXP_ZOOM_VARIANCE: f64 = 1.1;
@@ -56,46 +85,229 @@ pub trait MyFloat = Float + FloatConst + FloatCore + ComplexFloat + NumAssignOps
 
 fn main() -> anyhow::Result<()>
 {
-    let event_loop = EventLoop::new()?;
+    let cli = Cli::parse();
+    if cli.list
+    {
+        return cli::list_fractals()
+    }
+    if cli.bookmarks
+    {
+        return cli::list_bookmarks()
+    }
+    if cli.default_keymap
+    {
+        print!("{}", toml::to_string(&Keymap::default())?);
+        return Ok(())
+    }
+    let start = cli.start();
+    let settings = Settings::load()?;
+    let keymap = Keymap::load()?;
+
+    let fractals: Vec<Arc<dyn Fractal<f64>>> = if cli.fractals.is_empty() && cli.location.is_none()
+    {
+        (
+            [
+                Arc::new(Feigenbaum::default()),
+                Arc::new(Cantor::cantor()),
+                Arc::new(Cantor::cantor().sierpinski()),
+                Arc::new(Cantor::assymetric(1.0/4.0..1.0/2.0)),
+                Arc::new(Cantor::assymetric(1.0/4.0..1.0/2.0).sierpinski()),
+                Arc::new(Cantor::assymetric(1.0/8.0..7.0/8.0)),
+                Arc::new(Cantor::assymetric(1.0/8.0..7.0/8.0).sierpinski()),
+                Arc::new(FibonacciHamiltonianJulia::default()),
+                Arc::new(FibonacciHamiltonianMandelbrot::default()), // unknown hausdorf dim
+                //Arc::new(Cantor::smith_volterra()), // TODO (convergance?)
+                //Arc::new(Cantor::smith_volterra().sierpinski()), // TODO (convergance?)
+                // TODO: cantor triangle
+                //Arc::new(Blancmange::default()), // TODO: fail
+                Arc::new(SupergoldenJulia::default()),
+                Arc::new(SupergoldenMandelbrot), // unknown hausdorf dim
+                /*Arc::new(Julia::clover()),
+                Arc::new(Rauzy::default()), // TODO
+                // TODO: gosper island
+                Arc::new(Julia::dendrite()),
+                //Arc::new(FibonacciSnowlake), // TODO: fail
+                // TODO: Boundary of the tame twindragon
+                //Arc::new(Henon::default()),
+                // TODO: Koch snowflake
+                //Arc::new(HeighwayDragon::default()),*/
 
-    let fractals = (
-        [
-            Arc::new(Feigenbaum::default()),
-            Arc::new(Cantor::cantor()),
-            Arc::new(Cantor::cantor().sierpinski()),
-            Arc::new(Cantor::assymetric(1.0/4.0..1.0/2.0)),
-            Arc::new(Cantor::assymetric(1.0/4.0..1.0/2.0).sierpinski()),
-            Arc::new(Cantor::assymetric(1.0/8.0..7.0/8.0)),
-            Arc::new(Cantor::assymetric(1.0/8.0..7.0/8.0).sierpinski()),
-            Arc::new(FibonacciHamiltonianJulia::default()),
-            Arc::new(FibonacciHamiltonianMandelbrot::default()), // unknown hausdorf dim
-            //Arc::new(Cantor::smith_volterra()), // TODO (convergance?)
-            //Arc::new(Cantor::smith_volterra().sierpinski()), // TODO (convergance?)
-            // TODO: cantor triangle
-            //Arc::new(Blancmange::default()), // TODO: fail
-            Arc::new(SupergoldenJulia),
-            Arc::new(SupergoldenMandelbrot), // unknown hausdorf dim
-            /*Arc::new(Julia::clover()),
-            Arc::new(Rauzy::default()), // TODO
-            // TODO: gosper island
-            Arc::new(Julia::dendrite()),
-            //Arc::new(FibonacciSnowlake), // TODO: fail
-            // TODO: Boundary of the tame twindragon
-            //Arc::new(Henon::default()),
-            // TODO: Koch snowflake
-            //Arc::new(HeighwayDragon::default()),*/
-
-        ] as [Arc<dyn Fractal<f64>>; _]
-    ).into_iter()
-        .rev()
-        .cycle();
-    
-    let mut app = App::<f64, _, _>::new(fractals);
+            ] as [Arc<dyn Fractal<f64>>; _]
+        ).into_iter()
+            .rev()
+            .collect()
+    }
+    else
+    {
+        cli.location.iter()
+            .map(|(fractal, _)| fractal.clone())
+            .chain(cli.fractals)
+            .map(|fractal| fractal as Arc<dyn Fractal<f64>>)
+            .collect()
+    };
+    let mut fractals = fractals.into_iter().cycle();
+
+    // Headless: renders the first fractal without opening a window
+    if let Some(path) = &cli.png
+    {
+        let fractal = fractals.next()
+            .ok_or(anyhow::Error::msg("No fractals"))?;
+        return render_png(&fractal, path, cli.size, cli.cpu, start, settings)
+    }
+    // Replay: renders a recorded session frame by frame, at a fixed rate
+    if let (Some(replay), Some(export)) = (&cli.replay, &cli.export)
+    {
+        return render_replay(&Recording::load(replay)?, export, cli.fps, cli.cpu)
+    }
+    // Timeline: renders the first fractal frame by frame, at a fixed rate
+    if let (Some(timeline), Some(export)) = (&cli.timeline, &cli.export)
+    {
+        let fractal = fractals.next()
+            .ok_or(anyhow::Error::msg("No fractals"))?;
+        let view = View::new(&fractal, cli.size, start, settings);
+        return render_timeline(&fractal, timeline, export, cli.fps, cli.cpu, view)
+    }
+    // Terminal: streams the zoom to the TTY instead of a window
+    if let Some(protocol) = cli.terminal
+    {
+        let protocol = protocol.unwrap_or_else(Protocol::detect);
+        let offscreen = futures::executor::block_on(Offscreen::new(false))?;
+        return TerminalApp::<f64, _, _>::new(fractals, protocol, offscreen, start, settings, keymap, cli.autopilot).run()
+    }
+
+    let event_loop = EventLoop::new()?;
+    let mut app = App::<f64, _, _>::new(fractals, start, settings, keymap, Options {
+        fullscreen: !cli.windowed,
+        record: cli.record,
+        time_step: cli.time_step,
+        autopilot: cli.autopilot,
+        watch_shaders: cli.watch_shaders
+    });
 
     event_loop.run_app(&mut app)?;
     Ok(())
 }
 
+fn render_png<F, Z>(fractal: &Z, path: &Path, size: PhysicalSize<u32>, cpu: bool, start: StartView<F>, settings: Settings) -> anyhow::Result<()>
+where
+    F: MyFloat,
+    Z: Fractal<F>
+{
+    let view = View::new(fractal, size, start, settings);
+    let frame = if cpu
+    {
+        render_cpu(fractal, &view)
+    }
+    else
+    {
+        let offscreen = futures::executor::block_on(Offscreen::new(false))?;
+        offscreen.render(fractal, &view)?
+    };
+    frame.save_png(path)
+}
+
+fn render_timeline<F, Z>(fractal: &Z, timeline: &Path, export: &Path, fps: u32, cpu: bool, mut view: View<F>) -> anyhow::Result<()>
+where
+    F: MyFloat,
+    Z: Fractal<F>
+{
+    let timeline = Timeline::load(timeline, &view)?;
+    let offscreen = if cpu
+    {
+        None
+    }
+    else
+    {
+        Some(futures::executor::block_on(Offscreen::new(false))?)
+    };
+    let mut pass = offscreen.as_ref().map(|offscreen| offscreen.pass_or_built_in(fractal, &view, |message| eprintln!("{message}"))).transpose()?;
+    let mut sink = FrameSink::create(export, view.win_size(), fps)?;
+
+    let frame_count = timeline.frame_count(f!(fps));
+    for i in 0..frame_count
+    {
+        timeline.apply(&mut view, f!(i as f64/fps as f64));
+        let frame = match (&offscreen, &mut pass)
+        {
+            (Some(offscreen), Some(pass)) => offscreen.render_view(pass, fractal, &view)?,
+            _ => render_cpu(fractal, &view)
+        };
+        sink.write(i, &frame)?;
+    }
+    sink.finish()?;
+    println!("Rendered {frame_count} frames to {}", export.display());
+    Ok(())
+}
+
+/// Replays the events due by each frame, so the view follows the exact steps of the live session.
+fn render_replay(recording: &Recording, export: &Path, fps: u32, cpu: bool) -> anyhow::Result<()>
+{
+    let Session { fractal, size, start, settings } = &recording.session;
+    let mut fractal = cli::parse_fractal(fractal)?;
+    let mut view = View::new(&fractal, PhysicalSize::new(size[0], size[1]), *start, settings.clone());
+    let mut window = view.win_size();
+    let offscreen = if cpu
+    {
+        None
+    }
+    else
+    {
+        Some(futures::executor::block_on(Offscreen::new(false))?)
+    };
+    let mut pass = offscreen.as_ref().map(|offscreen| offscreen.pass_or_built_in(&fractal, &view, |message| eprintln!("{message}"))).transpose()?;
+    let mut sink = FrameSink::create(export, view.win_size(), fps)?;
+
+    let mut events = recording.events.iter().peekable();
+    let frame_count = (recording.duration()*fps as f64).floor() as usize + 1;
+    for i in 0..frame_count
+    {
+        let t = i as f64/fps as f64;
+        while let Some((_, event)) = events.next_if(|&&(event_t, _)| event_t <= t)
+        {
+            let shader = view.settings().shader.clone();
+            replay_event(event, &mut fractal, &mut view, &mut window)?;
+            match event
+            {
+                Event::Fractal { .. } => {
+                    pass = offscreen.as_ref().map(|offscreen| offscreen.pass_or_built_in(&fractal, &view, |message| eprintln!("{message}"))).transpose()?;
+                },
+                // Like the session did, keeping the shaders it had if the new ones don't work
+                Event::Settings { .. } if view.settings().shader != shader => if let Some(offscreen) = &offscreen
+                {
+                    match offscreen.pass(&fractal, &view)
+                    {
+                        Ok(next) => pass = Some(next),
+                        Err(error) => eprintln!("{error:#}")
+                    }
+                },
+                _ => ()
+            }
+        }
+        let frame = match (&offscreen, &mut pass)
+        {
+            (Some(offscreen), Some(pass)) => offscreen.render_view(pass, &fractal, &view)?,
+            _ => render_cpu(&fractal, &view)
+        };
+        sink.write(i, &frame)?;
+    }
+    sink.finish()?;
+    println!("Rendered {frame_count} frames to {}", export.display());
+    Ok(())
+}
+
+/// Plays `event` back like [`Event::replay`], also moving on to another fractal the way the session did.
+fn replay_event(event: &Event, fractal: &mut cli::DynFractal, view: &mut View<f64>, window: &mut PhysicalSize<u32>) -> anyhow::Result<()>
+{
+    if let Event::Fractal { fractal: next, start } = event
+    {
+        *fractal = cli::parse_fractal(next)?;
+        *view = View::new(fractal, view.win_size(), *start, view.settings().clone());
+        return Ok(())
+    }
+    event.replay(view, fractal, window);
+    Ok(())
+}
+
 fn clamp_rem<T>(x: T, range: Range<T>) -> T
 where
     T: Num + Copy
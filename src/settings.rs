@@ -0,0 +1,185 @@
+use core::ops::Range;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+
+use crate::{EXP_ZOOM_VARIANCE, MAX_ITERATIONS, MOVE_CENTER_ACCEL, MOVE_CENTER_SPEED, MOVE_EXP_ACCEL, MOVE_EXP_SPEED, MOVE_SHIFT_ACCEL, MOVE_SHIFT_SPEED, MOVE_ZOOM_ACCEL, ROT_ACCEL, ROT_SPEED, SHIFT_ZOOM_VARIANCE, ZOOM_BASE, ZOOM_MUL, ZOOM_RANGE, fractal::{PaletteSettings, ShaderDefs}};
+
+const SETTINGS_FILE: &str = "config.toml";
+
+/// The feel of the app, loaded from `config.toml` in the config directory. Anything left out keeps its default.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Settings
+{
+    pub rot_speed: f64,
+    pub move_center_speed: f64,
+    pub move_exp_speed: f64,
+    pub move_shift_speed: f64,
+
+    pub rot_accel: f64,
+    pub move_center_accel: f64,
+    pub move_exp_accel: f64,
+    pub move_zoom_accel: f64,
+    pub move_shift_accel: f64,
+
+    /// Zoom bounces back once it leaves this range.
+    pub zoom_range: Range<f64>,
+    pub zoom_mul: f64,
+    pub zoom_base: f64,
+    pub max_iterations: u32,
+
+    pub shift_zoom_variance: f64,
+    pub exp_zoom_variance: f64,
+
+    /// Rebuilds the shaders with these defs, under `[shader]`.
+    pub shader: ShaderDefs,
+    pub palette: PaletteSettings
+}
+
+impl Default for Settings
+{
+    fn default() -> Self
+    {
+        Self {
+            rot_speed: ROT_SPEED,
+            move_center_speed: MOVE_CENTER_SPEED,
+            move_exp_speed: MOVE_EXP_SPEED,
+            move_shift_speed: MOVE_SHIFT_SPEED,
+
+            rot_accel: ROT_ACCEL,
+            move_center_accel: MOVE_CENTER_ACCEL,
+            move_exp_accel: MOVE_EXP_ACCEL,
+            move_zoom_accel: MOVE_ZOOM_ACCEL,
+            move_shift_accel: MOVE_SHIFT_ACCEL,
+
+            zoom_range: ZOOM_RANGE.start as f64..ZOOM_RANGE.end as f64,
+            zoom_mul: ZOOM_MUL,
+            zoom_base: ZOOM_BASE,
+            max_iterations: MAX_ITERATIONS,
+
+            shift_zoom_variance: SHIFT_ZOOM_VARIANCE,
+            exp_zoom_variance: EXP_ZOOM_VARIANCE,
+
+            shader: ShaderDefs::default(),
+            palette: PaletteSettings::default()
+        }
+    }
+}
+
+impl Settings
+{
+    /// Reads the config file, or falls back to the defaults if there is none.
+    pub fn load() -> anyhow::Result<Self>
+    {
+        let Some((path, settings)) = read_config::<Self>(SETTINGS_FILE)?
+        else
+        {
+            return Ok(Self::default())
+        };
+        settings.validate()
+            .map_err(|error| error.context(format!("Invalid settings in {}", path.display())))?;
+        Ok(settings)
+    }
+
+    pub fn validate(&self) -> anyhow::Result<()>
+    {
+        let Self {
+            rot_speed,
+            move_center_speed,
+            move_exp_speed,
+            move_shift_speed,
+            rot_accel,
+            move_center_accel,
+            move_exp_accel,
+            move_zoom_accel,
+            move_shift_accel,
+            zoom_range,
+            zoom_mul,
+            zoom_base,
+            max_iterations,
+            shift_zoom_variance,
+            exp_zoom_variance,
+            shader,
+            palette
+        } = self;
+
+        for (name, value) in [
+            ("rot_speed", rot_speed),
+            ("move_center_speed", move_center_speed),
+            ("move_exp_speed", move_exp_speed),
+            ("move_shift_speed", move_shift_speed),
+            ("rot_accel", rot_accel),
+            ("move_center_accel", move_center_accel),
+            ("move_exp_accel", move_exp_accel),
+            ("move_zoom_accel", move_zoom_accel),
+            ("move_shift_accel", move_shift_accel)
+        ]
+        {
+            if !value.is_finite() || *value < 0.0
+            {
+                return Err(anyhow::Error::msg(format!("{name} must be finite and non-negative, was {value}")))
+            }
+        }
+        for (name, value) in [
+            ("zoom_mul", zoom_mul),
+            ("zoom_base", zoom_base)
+        ]
+        {
+            if !value.is_finite() || *value <= 0.0
+            {
+                return Err(anyhow::Error::msg(format!("{name} must be finite and positive, was {value}")))
+            }
+        }
+        for (name, value) in [
+            ("shift_zoom_variance", shift_zoom_variance),
+            ("exp_zoom_variance", exp_zoom_variance)
+        ]
+        {
+            if !value.is_finite()
+            {
+                return Err(anyhow::Error::msg(format!("{name} must be finite, was {value}")))
+            }
+        }
+        if !(zoom_range.start > 0.0 && zoom_range.end.is_finite() && zoom_range.start < zoom_range.end)
+        {
+            return Err(anyhow::Error::msg(format!("zoom_range must be a non-empty range of positive zooms, was {zoom_range:?}")))
+        }
+        if *max_iterations == 0
+        {
+            return Err(anyhow::Error::msg("max_iterations must be at least 1"))
+        }
+        shader.validate()
+            .map_err(|error| error.context("Invalid [shader]"))?;
+        palette.validate()
+            .map_err(|error| error.context("Invalid [palette]"))?;
+        Ok(())
+    }
+}
+
+/// Where `file` goes in the config directory.
+pub fn config_path(file: &str) -> Option<PathBuf>
+{
+    dirs::config_dir().map(|dir| dir.join("fractal-zoom").join(file))
+}
+
+/// Reads and parses `file` from the config directory, if it's there.
+pub fn read_config<T>(file: &str) -> anyhow::Result<Option<(PathBuf, T)>>
+where
+    T: DeserializeOwned
+{
+    let Some(path) = config_path(file)
+    else
+    {
+        return Ok(None)
+    };
+    let config = match std::fs::read_to_string(&path)
+    {
+        Ok(config) => config,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(error) => return Err(anyhow::Error::new(error).context(format!("Failed to read {}", path.display())))
+    };
+    let config = toml::from_str(&config)
+        .map_err(|error| anyhow::Error::new(error).context(format!("Failed to parse {}", path.display())))?;
+    Ok(Some((path, config)))
+}
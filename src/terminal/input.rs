@@ -0,0 +1,152 @@
+use std::{collections::HashMap, time::{Duration, Instant}};
+
+use crossterm::event::{KeyCode as TermKeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use winit::{event::ElementState, keyboard::{KeyCode, ModifiersState}};
+
+/// Most terminals only report key presses, so without release events a key counts as held until it hasn't repeated for
+/// this long. It has to outlast the usual auto-repeat delay.
+const HOLD_TIMEOUT: Duration = Duration::from_millis(600);
+
+/// Maps terminal keys to the physical keys the window uses, so both share one keymap.
+///
+/// Terminals report characters rather than physical keys, so this assumes a QWERTY layout.
+pub fn key_code(event: &KeyEvent) -> Option<KeyCode>
+{
+    Some(match event.code
+    {
+        TermKeyCode::Esc => KeyCode::Escape,
+        TermKeyCode::Char('c') if event.modifiers.contains(KeyModifiers::CONTROL) => KeyCode::Escape,
+        TermKeyCode::Up => KeyCode::ArrowUp,
+        TermKeyCode::Down => KeyCode::ArrowDown,
+        TermKeyCode::Left => KeyCode::ArrowLeft,
+        TermKeyCode::Right => KeyCode::ArrowRight,
+        TermKeyCode::Enter => KeyCode::Enter,
+        TermKeyCode::Tab => KeyCode::Tab,
+        TermKeyCode::Backspace => KeyCode::Backspace,
+        TermKeyCode::F(n) => match n
+        {
+            1 => KeyCode::F1,
+            2 => KeyCode::F2,
+            3 => KeyCode::F3,
+            4 => KeyCode::F4,
+            5 => KeyCode::F5,
+            6 => KeyCode::F6,
+            7 => KeyCode::F7,
+            8 => KeyCode::F8,
+            9 => KeyCode::F9,
+            10 => KeyCode::F10,
+            11 => KeyCode::F11,
+            12 => KeyCode::F12,
+            _ => return None
+        },
+        TermKeyCode::Char(c) => match c.to_ascii_lowercase()
+        {
+            'a' => KeyCode::KeyA,
+            'b' => KeyCode::KeyB,
+            'c' => KeyCode::KeyC,
+            'd' => KeyCode::KeyD,
+            'e' => KeyCode::KeyE,
+            'f' => KeyCode::KeyF,
+            'g' => KeyCode::KeyG,
+            'h' => KeyCode::KeyH,
+            'i' => KeyCode::KeyI,
+            'j' => KeyCode::KeyJ,
+            'k' => KeyCode::KeyK,
+            'l' => KeyCode::KeyL,
+            'm' => KeyCode::KeyM,
+            'n' => KeyCode::KeyN,
+            'o' => KeyCode::KeyO,
+            'p' => KeyCode::KeyP,
+            'q' => KeyCode::KeyQ,
+            'r' => KeyCode::KeyR,
+            's' => KeyCode::KeyS,
+            't' => KeyCode::KeyT,
+            'u' => KeyCode::KeyU,
+            'v' => KeyCode::KeyV,
+            'w' => KeyCode::KeyW,
+            'x' => KeyCode::KeyX,
+            'y' => KeyCode::KeyY,
+            'z' => KeyCode::KeyZ,
+            '0' | ')' => KeyCode::Digit0,
+            '1' | '!' => KeyCode::Digit1,
+            '2' | '@' => KeyCode::Digit2,
+            '3' | '#' => KeyCode::Digit3,
+            '4' | '$' => KeyCode::Digit4,
+            '5' | '%' => KeyCode::Digit5,
+            '6' | '^' => KeyCode::Digit6,
+            '7' | '&' => KeyCode::Digit7,
+            '8' | '*' => KeyCode::Digit8,
+            '9' | '(' => KeyCode::Digit9,
+            ' ' => KeyCode::Space,
+            ',' | '<' => KeyCode::Comma,
+            '.' | '>' => KeyCode::Period,
+            '[' | '{' => KeyCode::BracketLeft,
+            ']' | '}' => KeyCode::BracketRight,
+            '+' => KeyCode::NumpadAdd,
+            '-' => KeyCode::NumpadSubtract,
+            _ => return None
+        },
+        _ => return None
+    })
+}
+
+/// Like [`modifiers`], but counts shifted symbols like `!` as shift, which terminals don't always report.
+pub fn key_modifiers(event: &KeyEvent) -> ModifiersState
+{
+    let shifted = matches!(event.code, TermKeyCode::Char(c) if c.is_ascii_uppercase() || ")!@#$%^&*(<>{}".contains(c));
+    modifiers(event.modifiers) | if shifted { ModifiersState::SHIFT } else { ModifiersState::empty() }
+}
+
+pub fn modifiers(modifiers: KeyModifiers) -> ModifiersState
+{
+    [
+        (KeyModifiers::SHIFT, ModifiersState::SHIFT),
+        (KeyModifiers::CONTROL, ModifiersState::CONTROL),
+        (KeyModifiers::ALT, ModifiersState::ALT),
+        (KeyModifiers::SUPER, ModifiersState::SUPER)
+    ].into_iter()
+        .filter(|&(modifier, _)| modifiers.contains(modifier))
+        .fold(ModifiersState::empty(), |state, (_, modifier)| state | modifier)
+}
+
+/// Synthesizes release events for terminals that only report presses.
+#[derive(Debug, Default)]
+pub struct HeldKeys
+{
+    deadlines: HashMap<KeyCode, Instant>
+}
+
+impl HeldKeys
+{
+    /// Returns the button state to act on, or `None` if the event is a repeat of an already held key.
+    pub fn event(&mut self, key_code: KeyCode, kind: KeyEventKind, emulate_release: bool) -> Option<ElementState>
+    {
+        match kind
+        {
+            KeyEventKind::Release => {
+                self.deadlines.remove(&key_code);
+                Some(ElementState::Released)
+            },
+            KeyEventKind::Press | KeyEventKind::Repeat if emulate_release => {
+                let held = self.deadlines.insert(key_code, Instant::now() + HOLD_TIMEOUT).is_some();
+                (!held).then_some(ElementState::Pressed)
+            },
+            KeyEventKind::Press | KeyEventKind::Repeat => Some(ElementState::Pressed)
+        }
+    }
+
+    /// Keys that have not repeated for a while, and should now be considered released.
+    pub fn expired(&mut self) -> Vec<KeyCode>
+    {
+        let now = Instant::now();
+        let expired: Vec<_> = self.deadlines.iter()
+            .filter(|&(_, &deadline)| deadline <= now)
+            .map(|(&key_code, _)| key_code)
+            .collect();
+        for key_code in expired.iter()
+        {
+            self.deadlines.remove(key_code);
+        }
+        expired
+    }
+}
@@ -0,0 +1,235 @@
+use std::{io::Write, time::{Duration, Instant}};
+
+use crossterm::{cursor::{Hide, Show}, event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyboardEnhancementFlags, MouseButton as TermMouseButton, MouseEvent, MouseEventKind, PopKeyboardEnhancementFlags, PushKeyboardEnhancementFlags}, execute, terminal::{self, EnterAlternateScreen, LeaveAlternateScreen}};
+use winit::{dpi::{PhysicalPosition, PhysicalSize}, event::{ElementState, MouseButton}, keyboard::ModifiersState};
+
+use crate::{MyFloat, app::{Action, Autopilot, Bookmarks, Input, Keymap, Offscreen, StartView, View}, f, fractal::Fractal, settings::Settings};
+
+moddef::moddef!(
+    flat(pub) mod {
+        input,
+        protocol
+    }
+);
+
+const FRAME_TIME: Duration = Duration::from_millis(33);
+
+/// Runs the zoom in the terminal, rendering offscreen and streaming the frames to stdout.
+pub struct TerminalApp<F, Z, G>
+where
+    F: MyFloat,
+    G: IntoIterator<Item = Z>,
+    Z: Fractal<F>
+{
+    fractal: G::IntoIter,
+    protocol: Protocol,
+    offscreen: Offscreen,
+    start: StartView<F>,
+    settings: Settings,
+    keymap: Keymap,
+    autopilot: bool,
+    /// Lines to print once the terminal is restored, since stdout is taken up by the frames until then.
+    printed: Vec<String>,
+    marker: core::marker::PhantomData<F>
+}
+
+impl<F, Z, G> TerminalApp<F, Z, G>
+where
+    F: MyFloat,
+    G: IntoIterator<Item = Z>,
+    Z: Fractal<F>
+{
+    pub fn new(fractal: G, protocol: Protocol, offscreen: Offscreen, start: StartView<F>, settings: Settings, keymap: Keymap, autopilot: bool) -> Self
+    {
+        Self {
+            fractal: fractal.into_iter(),
+            protocol,
+            offscreen,
+            start,
+            settings,
+            keymap,
+            autopilot,
+            printed: vec![],
+            marker: core::marker::PhantomData
+        }
+    }
+
+    pub fn run(mut self) -> anyhow::Result<()>
+    {
+        let mut stdout = std::io::stdout();
+        terminal::enable_raw_mode()?;
+        execute!(stdout, EnterAlternateScreen, Hide, EnableMouseCapture)?;
+
+        // Release events are only reported with the kitty keyboard protocol
+        let enhanced = terminal::supports_keyboard_enhancement().unwrap_or(false);
+        if enhanced
+        {
+            execute!(stdout, PushKeyboardEnhancementFlags(KeyboardEnhancementFlags::REPORT_EVENT_TYPES))?;
+        }
+
+        let result = self.event_loop(&mut stdout, enhanced);
+
+        if enhanced
+        {
+            execute!(stdout, PopKeyboardEnhancementFlags)?;
+        }
+        execute!(stdout, DisableMouseCapture, Show, LeaveAlternateScreen)?;
+        terminal::disable_raw_mode()?;
+        for line in &self.printed
+        {
+            println!("{line}")
+        }
+        result
+    }
+
+    fn event_loop<W>(&mut self, w: &mut W, enhanced: bool) -> anyhow::Result<()>
+    where
+        W: Write
+    {
+        let Some(mut fractal) = self.fractal.next()
+        else
+        {
+            return Ok(())
+        };
+        let mut window_size = terminal::window_size()?;
+        let mut view = View::new(&fractal, self.protocol.frame_size(&window_size), self.start, self.settings.clone());
+        let mut pass = self.offscreen.pass_or_built_in(&fractal, &view, |message| self.printed.push(message))?;
+        let mut held_keys = HeldKeys::default();
+        let mut autopilot = self.autopilot.then(Autopilot::default);
+
+        loop
+        {
+            let deadline = Instant::now() + FRAME_TIME;
+            while event::poll(deadline.saturating_duration_since(Instant::now()))?
+            {
+                let input = match event::read()?
+                {
+                    Event::Key(event) => input::key_code(&event)
+                        .and_then(|key_code| held_keys.event(key_code, event.kind, !enhanced)
+                            .map(|state| (Input::Key(key_code), input::key_modifiers(&event), state))
+                        ),
+                    Event::Mouse(MouseEvent { kind, column, row, modifiers }) => {
+                        let PhysicalSize { width, height } = view.win_size();
+                        view.update_mouse_pos(PhysicalPosition::new(
+                            (column as f64 + 0.5)*width as f64/window_size.columns.max(1) as f64,
+                            (row as f64 + 0.5)*height as f64/window_size.rows.max(1) as f64
+                        ));
+                        let button = |button| Input::Mouse(match button
+                        {
+                            TermMouseButton::Left => MouseButton::Left,
+                            TermMouseButton::Right => MouseButton::Right,
+                            TermMouseButton::Middle => MouseButton::Middle
+                        });
+                        match kind
+                        {
+                            MouseEventKind::Down(down) => Some((button(down), input::modifiers(modifiers), ElementState::Pressed)),
+                            MouseEventKind::Up(up) => Some((button(up), input::modifiers(modifiers), ElementState::Released)),
+                            MouseEventKind::ScrollUp => {
+                                view.scroll(F::one(), F::zero());
+                                None
+                            },
+                            MouseEventKind::ScrollDown => {
+                                view.scroll(-F::one(), F::zero());
+                                None
+                            },
+                            MouseEventKind::ScrollLeft => {
+                                view.scroll(F::zero(), -F::one());
+                                None
+                            },
+                            MouseEventKind::ScrollRight => {
+                                view.scroll(F::zero(), F::one());
+                                None
+                            },
+                            _ => None
+                        }
+                    },
+                    Event::Resize(..) => {
+                        window_size = terminal::window_size()?;
+                        view.resize(self.protocol.frame_size(&window_size));
+                        None
+                    },
+                    _ => None
+                };
+
+                let Some((input, modifiers, state)) = input
+                else
+                {
+                    continue
+                };
+                for action in self.keymap.actions(input, modifiers, state)
+                {
+                    match action
+                    {
+                        Action::Exit | Action::Stop => return Ok(()),
+                        Action::NextFractal => {
+                            let Some(next) = self.fractal.next()
+                            else
+                            {
+                                return Ok(())
+                            };
+                            fractal = next;
+                            let mut next = View::new(&fractal, view.win_size(), self.start, view.settings().clone());
+                            next.take_clock(&mut view);
+                            view = next;
+                            pass = self.offscreen.pass_or_built_in(&fractal, &view, |message| self.printed.push(message))?;
+                        },
+                        Action::PrintLocation => self.printed.push(view.location(&fractal).to_string()),
+                        Action::Locate(target) => match view.locate(&fractal, target)
+                        {
+                            Ok(located) => self.printed.push(located.to_string()),
+                            Err(error) => self.printed.push(format!("{error:#}"))
+                        },
+                        Action::SaveBookmark(slot) => if let Err(error) = Bookmarks::save_slot(&fractal.arg(), slot, view.bookmark())
+                        {
+                            self.printed.push(format!("{error:#}"))
+                        },
+                        Action::GotoBookmark(slot) => match Bookmarks::load_slot(&fractal.arg(), slot)
+                        {
+                            Ok(bookmark) => view.goto(&bookmark),
+                            Err(error) => self.printed.push(format!("{error:#}"))
+                        },
+                        Action::Autopilot => autopilot = match autopilot
+                        {
+                            Some(_) => None,
+                            None => Some(Autopilot::default())
+                        },
+                        Action::ReloadSettings => match Settings::load()
+                        {
+                            Ok(settings) => {
+                                let shader = view.settings().shader.clone();
+                                view.set_settings(settings);
+                                if view.settings().shader != shader
+                                {
+                                    match self.offscreen.pass(&fractal, &view)
+                                    {
+                                        Ok(next) => pass = next,
+                                        Err(error) => self.printed.push(format!("{error:#}"))
+                                    }
+                                }
+                            },
+                            Err(error) => self.printed.push(format!("{error:#}"))
+                        },
+                        action => view.act(action, state, &fractal)
+                    }
+                }
+            }
+            for key_code in held_keys.expired()
+            {
+                for action in self.keymap.actions(Input::Key(key_code), ModifiersState::empty(), ElementState::Released)
+                {
+                    view.act(action, ElementState::Released, &fractal);
+                }
+            }
+
+            let dt = view.update();
+            if let Some(autopilot) = &mut autopilot
+            {
+                autopilot.steer(&mut view, &fractal, f!(dt));
+            }
+            let frame = self.offscreen.render_view(&mut pass, &fractal, &view)?;
+            self.protocol.encode(&frame, w)?;
+            w.flush()?;
+        }
+    }
+}
+
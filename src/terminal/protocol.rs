@@ -0,0 +1,284 @@
+use core::{fmt::Display, str::FromStr};
+use std::io::Write;
+
+use crossterm::{cursor::MoveTo, queue, terminal::WindowSize};
+use winit::dpi::PhysicalSize;
+
+use crate::app::Frame;
+
+/// Assumed cell size in pixels for terminals that don't report their pixel size.
+const CELL_SIZE: PhysicalSize<u16> = PhysicalSize::new(8, 16);
+const KITTY_CHUNK_SIZE: usize = 4096;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Protocol
+{
+    /// Two truecolor pixels per cell using '▀' with separate foreground and background colors.
+    HalfBlocks,
+    Sixel,
+    Kitty
+}
+
+impl Protocol
+{
+    /// Guesses the best supported protocol from the environment.
+    pub fn detect() -> Self
+    {
+        let var = |key| std::env::var(key).unwrap_or_default();
+        let term = var("TERM");
+        let term_program = var("TERM_PROGRAM");
+
+        if std::env::var_os("KITTY_WINDOW_ID").is_some()
+            || term.contains("kitty")
+            || matches!(term_program.as_str(), "WezTerm" | "ghostty")
+        {
+            Self::Kitty
+        }
+        else if term.contains("sixel")
+            || term.starts_with("foot")
+            || term.starts_with("mlterm")
+        {
+            Self::Sixel
+        }
+        else
+        {
+            Self::HalfBlocks
+        }
+    }
+
+    /// The resolution to render at to fill the terminal.
+    pub fn frame_size(self, window_size: &WindowSize) -> PhysicalSize<u32>
+    {
+        let &WindowSize { rows, columns, width, height } = window_size;
+        match self
+        {
+            Self::HalfBlocks => PhysicalSize::new(columns.max(1) as u32, rows.max(1) as u32*2),
+            Self::Sixel | Self::Kitty => {
+                let (width, height) = if width == 0 || height == 0
+                {
+                    (columns*CELL_SIZE.width, rows*CELL_SIZE.height)
+                }
+                else
+                {
+                    (width, height)
+                };
+                // Leave the last row free, so the image never makes the terminal scroll
+                let height = height as u32*rows.saturating_sub(1) as u32/rows.max(1) as u32;
+                // Sixel images are drawn in bands of six pixels, and there's always at least one so a terminal that's
+                // a single row high still has something to render
+                PhysicalSize::new((width as u32).max(1), (height - height % 6).max(6))
+            }
+        }
+    }
+
+    pub fn encode<W>(self, frame: &Frame, w: &mut W) -> std::io::Result<()>
+    where
+        W: Write
+    {
+        queue!(w, MoveTo(0, 0))?;
+        match self
+        {
+            Self::HalfBlocks => encode_half_blocks(frame, w),
+            Self::Sixel => encode_sixel(frame, w),
+            Self::Kitty => encode_kitty(frame, w)
+        }
+    }
+}
+
+impl Display for Protocol
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result
+    {
+        f.write_str(match self
+        {
+            Self::HalfBlocks => "halfblocks",
+            Self::Sixel => "sixel",
+            Self::Kitty => "kitty"
+        })
+    }
+}
+
+impl FromStr for Protocol
+{
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err>
+    {
+        match s
+        {
+            "halfblocks" | "half-blocks" | "ansi" => Ok(Self::HalfBlocks),
+            "sixel" => Ok(Self::Sixel),
+            "kitty" => Ok(Self::Kitty),
+            _ => Err(anyhow::Error::msg(format!("Unknown terminal protocol '{s}', expected halfblocks, sixel or kitty")))
+        }
+    }
+}
+
+fn encode_half_blocks<W>(frame: &Frame, w: &mut W) -> std::io::Result<()>
+where
+    W: Write
+{
+    let PhysicalSize { width, height } = frame.size;
+    let mut prev = None;
+    for y in (0..height).step_by(2)
+    {
+        queue!(w, MoveTo(0, (y/2) as u16))?;
+        for x in 0..width
+        {
+            let [r1, g1, b1, _] = frame.pixel(x, y);
+            let [r2, g2, b2, _] = if y + 1 < height
+            {
+                frame.pixel(x, y + 1)
+            }
+            else
+            {
+                [0; 4]
+            };
+            let colors = ([r1, g1, b1], [r2, g2, b2]);
+            if prev != Some(colors)
+            {
+                write!(w, "\x1b[38;2;{r1};{g1};{b1};48;2;{r2};{g2};{b2}m")?;
+                prev = Some(colors);
+            }
+            w.write_all("▀".as_bytes())?;
+        }
+    }
+    w.write_all(b"\x1b[0m")
+}
+
+fn encode_sixel<W>(frame: &Frame, w: &mut W) -> std::io::Result<()>
+where
+    W: Write
+{
+    // Quantize to a 6x6x6 color cube, which fits in the 256 color registers most terminals have
+    fn quantize(c: u8) -> usize
+    {
+        (c as usize*5 + 127)/255
+    }
+
+    let PhysicalSize { width, height } = frame.size;
+    write!(w, "\x1bP0;1;0q\"1;1;{width};{height}")?;
+    for i in 0..216
+    {
+        let [r, g, b] = [i/36, i/6 % 6, i % 6].map(|c| c*100/5);
+        write!(w, "#{i};2;{r};{g};{b}")?;
+    }
+
+    let mut band = vec![0usize; width as usize*6];
+    for y0 in (0..height).step_by(6)
+    {
+        let mut used = [false; 216];
+        for dy in 0..6
+        {
+            for x in 0..width
+            {
+                let color = if y0 + dy < height
+                {
+                    let [r, g, b, _] = frame.pixel(x, y0 + dy);
+                    quantize(r)*36 + quantize(g)*6 + quantize(b)
+                }
+                else
+                {
+                    0
+                };
+                band[dy as usize*width as usize + x as usize] = color;
+                used[color] = true;
+            }
+        }
+
+        let mut first = true;
+        for color in (0..216).filter(|&color| used[color])
+        {
+            if !first
+            {
+                // Carriage return to draw the next color over the same band
+                w.write_all(b"$")?;
+            }
+            first = false;
+            write!(w, "#{color}")?;
+
+            let mut run: Option<(u8, usize)> = None;
+            for x in 0..width as usize
+            {
+                let bits = (0..6).filter(|&dy| band[dy*width as usize + x] == color)
+                    .fold(0u8, |bits, dy| bits | 1 << dy);
+                let sixel = b'?' + bits;
+                run = match run
+                {
+                    Some((prev, n)) if prev == sixel => Some((prev, n + 1)),
+                    Some((prev, n)) => {
+                        write_sixel_run(w, prev, n)?;
+                        Some((sixel, 1))
+                    },
+                    None => Some((sixel, 1))
+                };
+            }
+            if let Some((prev, n)) = run
+            {
+                write_sixel_run(w, prev, n)?;
+            }
+        }
+        w.write_all(b"-")?;
+    }
+    w.write_all(b"\x1b\\")
+}
+
+fn write_sixel_run<W>(w: &mut W, sixel: u8, n: usize) -> std::io::Result<()>
+where
+    W: Write
+{
+    match n
+    {
+        1 => w.write_all(&[sixel]),
+        2 | 3 => w.write_all(&[sixel; 3][..n]),
+        _ => write!(w, "!{n}{}", sixel as char)
+    }
+}
+
+fn encode_kitty<W>(frame: &Frame, w: &mut W) -> std::io::Result<()>
+where
+    W: Write
+{
+    let PhysicalSize { width, height } = frame.size;
+    let data = base64(&frame.data);
+    let mut chunks = data.as_bytes()
+        .chunks(KITTY_CHUNK_SIZE)
+        .peekable();
+
+    // Reusing the image and placement ids replaces the previous frame instead of stacking them
+    let mut header = format!("a=T,f=32,s={width},v={height},i=1,p=1,q=2,C=1,");
+    while let Some(chunk) = chunks.next()
+    {
+        let more = chunks.peek().is_some() as u8;
+        write!(w, "\x1b_G{header}m={more};")?;
+        w.write_all(chunk)?;
+        w.write_all(b"\x1b\\")?;
+        header.clear();
+    }
+    Ok(())
+}
+
+fn base64(data: &[u8]) -> String
+{
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut out = String::with_capacity(data.len().div_ceil(3)*4);
+    for chunk in data.chunks(3)
+    {
+        let n = chunk.iter()
+            .enumerate()
+            .fold(0u32, |n, (i, &b)| n | (b as u32) << (16 - 8*i));
+        for i in 0..4
+        {
+            if i <= chunk.len()
+            {
+                out.push(ALPHABET[(n >> (18 - 6*i) & 0x3f) as usize] as char);
+            }
+            else
+            {
+                out.push('=');
+            }
+        }
+    }
+    out
+}